@@ -0,0 +1,41 @@
+use std::time::Duration;
+use tokio::time;
+use umatoi::characteristic::button::ButtonState;
+use umatoi::interface::ble::BleScanner;
+use umatoi::interface::CubeScanner;
+use umatoi::Cube;
+
+#[tokio::main]
+pub async fn main() {
+    let scanner = BleScanner::default();
+    let mut cubes = scanner.scan(1, Duration::from_secs(5)).await.unwrap();
+
+    assert!(!cubes.is_empty());
+
+    let mut cube = Cube::new(cubes.remove(0));
+    cube.connect().await.unwrap();
+    println!("battery: {:?}", cube.battery().await);
+
+    let mut position = cube.watch_position();
+    let mut button = cube.watch_button();
+    let timeout = time::sleep(Duration::from_secs(30));
+    tokio::pin!(timeout);
+
+    println!("move the cube on the mat (press the button to quit)");
+    loop {
+        tokio::select! {
+            _ = position.changed() => {
+                println!("position: {:?}", *position.borrow());
+            }
+            _ = button.changed() => {
+                if *button.borrow() == Some(ButtonState::Pressed) {
+                    break;
+                }
+            }
+            _ = &mut timeout => break,
+        }
+    }
+
+    println!("disconnect");
+    cube.disconnect().await.unwrap();
+}
@@ -2,11 +2,13 @@ use clap::Parser;
 use once_cell::sync::OnceCell;
 use std::sync::Mutex;
 use time::Duration;
+use tokio::sync::mpsc;
 use tokio::time;
 use umatoi::characteristic::id;
 use umatoi::characteristic::NotificationData;
 use umatoi::interface::ble::BleScanner;
-use umatoi::interface::CubeScanner;
+use umatoi::interface::{CoreCubeNotificationControl, CubeScanner, PausePolicy};
+use umatoi::notification_manager::NotificationFilter;
 use umatoi::payload::FromPayload;
 
 #[derive(Parser)]
@@ -82,7 +84,7 @@ fn notify_handler2(data: NotificationData) {
 #[tokio::main]
 pub async fn main() {
     let _arg: AppArg = AppArg::parse();
-    let scanner = BleScanner;
+    let scanner = BleScanner::default();
     let mut cubes = scanner.scan(1, Duration::from_secs(5)).await.unwrap();
 
     assert!(!cubes.is_empty());
@@ -90,15 +92,24 @@ pub async fn main() {
     let cube = &mut cubes[0];
     cube.connect().await.unwrap();
 
-    let notification_receiver = cube.create_notification_receiver(Box::new(vec![
-        Box::new(notify_handler1),
-        Box::new(notify_handler2),
-    ]));
+    let (control, control_receiver) = mpsc::channel(1);
+    let notification_receiver = cube.create_controlled_notification_receiver(
+        vec![
+            (NotificationFilter::All, Box::new(notify_handler1)),
+            (NotificationFilter::All, Box::new(notify_handler2)),
+        ],
+        control_receiver,
+        PausePolicy::Drop,
+    );
     let notification_task = tokio::spawn(notification_receiver);
 
     tokio::time::sleep(tokio::time::Duration::from_secs(10)).await;
     println!("disconnect");
 
-    notification_task.abort();
+    control
+        .send(CoreCubeNotificationControl::Quit)
+        .await
+        .unwrap();
+    notification_task.await.unwrap();
     cube.disconnect().await.unwrap();
 }
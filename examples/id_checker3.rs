@@ -1,15 +1,13 @@
 use clap::Parser;
-use once_cell::sync::OnceCell;
-use std::sync::Mutex;
+use futures::stream::StreamExt;
 use time::Duration;
 use tokio::time;
 use umatoi::api::Simple;
 use umatoi::characteristic::id;
 use umatoi::characteristic::motor;
-use umatoi::characteristic::NotificationData;
+use umatoi::characteristic::CubeEvent;
 use umatoi::interface::ble::BleScanner;
-use umatoi::interface::CubeScanner;
-use umatoi::payload::FromPayload;
+use umatoi::interface::{CubeEventStream, CubeScanner};
 use umatoi::position::{CubeLocation, Point};
 
 #[derive(Parser)]
@@ -24,77 +22,31 @@ struct AppArg {
     run: bool,
 }
 
-static POSITION_ID_READ: OnceCell<Mutex<usize>> = OnceCell::new();
-static POSITION_ID_MISSED: OnceCell<Mutex<usize>> = OnceCell::new();
-static STANDARD_ID_READ: OnceCell<Mutex<usize>> = OnceCell::new();
-static STANDARD_ID_MISSED: OnceCell<Mutex<usize>> = OnceCell::new();
-
-fn notify_handler1(data: NotificationData) {
-    if let Some(id_data) = id::IdInformation::from_payload(&data.value) {
-        match id_data {
-            id::IdInformation::PositionId(pos_id) => {
-                let mut update = POSITION_ID_READ
-                    .get_or_init(|| Mutex::new(0))
-                    .lock()
-                    .unwrap();
-                *update += 1;
+async fn event_receiver(mut events: CubeEventStream) {
+    while let Some(event) = events.next().await {
+        match event {
+            CubeEvent::Id(id::IdInformation::PositionId(pos_id)) => {
                 println!("position id: {:?}", pos_id);
             }
-            id::IdInformation::StandardId(std_id) => {
-                let mut update = STANDARD_ID_READ
-                    .get_or_init(|| Mutex::new(0))
-                    .lock()
-                    .unwrap();
-                *update += 1;
+            CubeEvent::Id(id::IdInformation::StandardId(std_id)) => {
                 println!("standard id: {:?}", std_id);
             }
-            _ => (),
-        }
-    } else {
-        println!(
-            "notify handler1: uuid: {:?} value: {:?}",
-            data.uuid, data.value
-        );
-    }
-}
-
-fn notify_handler2(data: NotificationData) {
-    if let Some(id_data) = id::IdInformation::from_payload(&data.value) {
-        match id_data {
-            id::IdInformation::PositionIdMissed => {
-                let mut update = POSITION_ID_MISSED
-                    .get_or_init(|| Mutex::new(0))
-                    .lock()
-                    .unwrap();
-                *update += 1;
+            CubeEvent::Id(id::IdInformation::PositionIdMissed) => {
                 println!("position id missed");
             }
-            id::IdInformation::StandardIdMissed => {
-                let mut update = STANDARD_ID_MISSED
-                    .get_or_init(|| Mutex::new(0))
-                    .lock()
-                    .unwrap();
-                *update += 1;
+            CubeEvent::Id(id::IdInformation::StandardIdMissed) => {
                 println!("standard id missed");
             }
-            _ => (),
-        }
-    }
-}
-
-fn notify_handler3(data: NotificationData) {
-    if let Some(motor_response) = motor::MotorInformation::from_payload(&data.value) {
-        match motor_response {
-            motor::MotorInformation::MotorControlTarget(res) => {
+            CubeEvent::Motor(motor::MotorInformation::MotorControlTarget(res)) => {
                 println!("ResponseMotorControlTarget: {:?}", res.response_code);
             }
-            motor::MotorInformation::MotorControlMultipleTargets(res) => {
+            CubeEvent::Motor(motor::MotorInformation::MotorControlMultipleTargets(res)) => {
                 println!(
                     "ResponseMotorControlMultipleTargets: {:?}",
                     res.response_code
                 );
             }
-            _ => (),
+            event => println!("event: {:?}", event),
         }
     }
 }
@@ -102,7 +54,7 @@ fn notify_handler3(data: NotificationData) {
 #[tokio::main]
 pub async fn main() {
     let _arg: AppArg = AppArg::parse();
-    let scanner = BleScanner;
+    let scanner = BleScanner::default();
     let mut cubes = scanner.scan(1, Duration::from_secs(5)).await.unwrap();
 
     assert!(!cubes.is_empty());
@@ -110,12 +62,8 @@ pub async fn main() {
     let cube = &mut cubes[0];
     cube.connect().await.unwrap();
 
-    let notification_receiver = cube.create_notification_receiver(Box::new(vec![
-        Box::new(notify_handler1),
-        Box::new(notify_handler2),
-        Box::new(notify_handler3),
-    ]));
-    let notification_task = tokio::spawn(notification_receiver);
+    let events = cube.event_stream().await.unwrap();
+    let notification_task = tokio::spawn(event_receiver(events));
 
     // cube.motor_control(50, 50, 2000).await.unwrap();
 
@@ -126,9 +74,8 @@ pub async fn main() {
         },
         ..motor::TargetPosition::default()
     };
-    cube.motor_control_target(30, target).await.unwrap();
-
-    tokio::time::sleep(tokio::time::Duration::from_secs(10)).await;
+    let response = cube.motor_control_target(30, target).await.unwrap();
+    println!("motor control target: {:?}", response.await);
     cube.motor_stop().await.unwrap();
 
     println!("disconnect");
@@ -0,0 +1,30 @@
+//! decode arbitrary payloads as every characteristic of the cube
+//!
+//! ```text
+//! cargo +nightly fuzz run decode_payload
+//! ```
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use umatoi::characteristic::{CoreCubeUuid, CubeEvent};
+use umatoi::describe::describe;
+
+const CHARACTERISTICS: [CoreCubeUuid; 8] = [
+    CoreCubeUuid::IdInfo,
+    CoreCubeUuid::SensorInfo,
+    CoreCubeUuid::ButtonInfo,
+    CoreCubeUuid::BatteryInfo,
+    CoreCubeUuid::MotorCtrl,
+    CoreCubeUuid::LightCtrl,
+    CoreCubeUuid::SoundCtrl,
+    CoreCubeUuid::Config,
+];
+
+fuzz_target!(|data: &[u8]| {
+    for characteristic in CHARACTERISTICS {
+        // notifications are decoded by CubeEvent, commands are decoded by describe()
+        let _ = CubeEvent::decode(characteristic.uuid(), data);
+        let _ = describe(characteristic.uuid(), data);
+    }
+});
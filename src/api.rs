@@ -1,5 +1,7 @@
 //! API
 
+mod response;
 mod simple;
 
+pub use response::{PendingResponse, ResponseMatcher, RESPONSE_TIMEOUT};
 pub use simple::Simple;
@@ -0,0 +1,124 @@
+//! Response of a request
+
+use crate::characteristic::motor::{CommandId, InFlightRequestId, MotorInformation, ResponseCode};
+use crate::characteristic::CubeEvent;
+use crate::interface::CubeEventStream;
+use crate::CoreCubeError;
+use futures::stream::StreamExt;
+use std::future::{Future, IntoFuture};
+use std::pin::Pin;
+use std::time::Duration;
+
+/// Default time to wait for a response
+///
+/// Longer than the default timeout of the target controls (10 seconds) in the firmware.
+pub const RESPONSE_TIMEOUT: Duration = Duration::from_secs(15);
+
+pub type ResponseMatcher<T> = Box<dyn Fn(&CubeEvent) -> Option<T> + Send + Sync>;
+
+/// Response which will be notified by the cube
+///
+/// The notification stream is opened before the request is written, so the response is
+/// not missed even if it arrives before this is awaited.
+pub struct PendingResponse<T> {
+    events: CubeEventStream,
+    matcher: ResponseMatcher<T>,
+    timeout: Duration,
+    /// released when the response is resolved or given up
+    request_id: Option<InFlightRequestId>,
+}
+
+impl<T> PendingResponse<T> {
+    pub fn new(events: CubeEventStream, matcher: ResponseMatcher<T>) -> Self {
+        Self {
+            events,
+            matcher,
+            timeout: RESPONSE_TIMEOUT,
+            request_id: None,
+        }
+    }
+
+    /// change the time to wait for the response
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// response converted by `f` (the responses which `f` returns `None` for are skipped)
+    pub fn filter_map<U>(
+        self,
+        f: impl Fn(T) -> Option<U> + Send + Sync + 'static,
+    ) -> PendingResponse<U>
+    where
+        T: 'static,
+    {
+        let Self {
+            events,
+            matcher,
+            timeout,
+            request_id,
+        } = self;
+        PendingResponse {
+            events,
+            matcher: Box::new(move |event| matcher(event).and_then(&f)),
+            timeout,
+            request_id,
+        }
+    }
+}
+
+impl PendingResponse<ResponseCode> {
+    /// response to motor control with target(s) specified
+    pub fn motor_control(
+        events: CubeEventStream,
+        command: CommandId,
+        in_flight: InFlightRequestId,
+    ) -> Self {
+        let request_id = in_flight.request_id();
+        let mut pending = Self::new(
+            events,
+            Box::new(move |event| match (command, event) {
+                (
+                    CommandId::TargetPosition,
+                    CubeEvent::Motor(MotorInformation::MotorControlTarget(response)),
+                ) if response.request_id == request_id => Some(response.response_code),
+                (
+                    CommandId::MultiTargetPositions,
+                    CubeEvent::Motor(MotorInformation::MotorControlMultipleTargets(response)),
+                ) if response.request_id == request_id => Some(response.response_code),
+                _ => None,
+            }),
+        );
+        pending.request_id = Some(in_flight);
+        pending
+    }
+}
+
+impl<T: Send + 'static> IntoFuture for PendingResponse<T> {
+    type Output = Result<T, CoreCubeError>;
+    type IntoFuture = Pin<Box<dyn Future<Output = Self::Output> + Send>>;
+
+    fn into_future(self) -> Self::IntoFuture {
+        let Self {
+            mut events,
+            matcher,
+            timeout,
+            request_id,
+        } = self;
+        Box::pin(async move {
+            let _request_id = request_id;
+            let response = async {
+                while let Some(event) = events.next().await {
+                    if let Some(response) = matcher(&event) {
+                        return Ok(response);
+                    }
+                }
+                // notification stream ends when the cube is disconnected
+                Err(CoreCubeError::Disconnected)
+            };
+            tokio::time::timeout(timeout, response)
+                .await
+                .unwrap_or(Err(CoreCubeError::Timeout))
+        })
+    }
+}
@@ -1,18 +1,43 @@
 //! Simple API
 
+use crate::api::{PendingResponse, ResponseMatcher};
+use crate::characteristic::battery::BatteryInformation;
+use crate::characteristic::configuration::{
+    ConfigurationInformation, EnableMotorSpeed, IdSensorNotificationCondition, MagnetFunction,
+    MagnetNotificationCondition, PostureAngleNotificationCondition, RequestBleProtocolVersion,
+    ResponseBleProtocolVersionData, ResponseEnableMotorSpeedData,
+    ResponseIdSensorMissedNotificationData, ResponseIdSensorNotificationData,
+    ResponseMagneticSensorData, ResponsePostureAngleDetectionData, SetCollisionDetectionThreshold,
+    SetDoubleTapDetectionTimeInterval, SetHorizontalDetectionThreshold,
+    SetIdSensorMissedNotification, SetIdSensorNotification, SetMagneticSensor,
+    SetPostureAngleDetection,
+};
+use crate::characteristic::id::IdInformation;
+use crate::characteristic::indicator::{
+    IndicatorParam, RepeatedTuringOnAndOff, TurnOffAll, TurningOnAndOff,
+};
 use crate::characteristic::motor::command::{
     MotorControl, MotorControlAcceleration, MotorControlMultipleTargets, MotorControlTarget,
     MotorControlWithSpecifiedDuration,
 };
 use crate::characteristic::motor::def::{
-    Acceleration, AngleVelocity, MovingDirection, Period, Priority, Speed, TargetPosition,
+    Acceleration, AngleVelocity, CommandId, MovingDirection, Period, Priority, ResponseCode, Speed,
+    TargetPosition,
+};
+use crate::characteristic::sensor::{
+    MagneticSensorInformation, MotionDetectionInformation, PostureDataType, RequestMagneticSensor,
+    RequestMotionDetection, RequestPostureAngleDetection, SensorInformation,
 };
-use crate::characteristic::CoreCubeUuid;
+use crate::characteristic::sound::{
+    MidiNote, PlayMidiNote, PlaySoundEffect, SoundEffectId, StopSound,
+};
+use crate::characteristic::{CoreCubeUuid, CubeEvent};
 use crate::integer_converter::{i_to_i16, i_to_u8};
 use crate::interface::CubeInterface;
 use crate::payload::ToPayload;
+use crate::CoreCubeError;
 use async_trait::async_trait;
-use std::error::Error;
+use uuid::Uuid;
 
 #[async_trait]
 pub trait Simple {
@@ -25,21 +50,25 @@ pub trait Simple {
         left: isize,
         right: isize,
         period_ms: usize,
-    ) -> Result<(), Box<dyn Error + Send + Sync + 'static>>;
+    ) -> Result<(), CoreCubeError>;
 
     /// Motor control with specified target
+    ///
+    /// The returned response resolves when the cube reports the result of this request.
     async fn motor_control_target(
         &self,
         speed: isize,
         target: TargetPosition,
-    ) -> Result<(), Box<dyn Error + Send + Sync + 'static>>;
+    ) -> Result<PendingResponse<ResponseCode>, CoreCubeError>;
 
     /// Motor control with multiple targets
+    ///
+    /// The returned response resolves when the cube reports the result of this request.
     async fn motor_control_multiple_targets(
         &self,
         speed: isize,
         target_list: Vec<TargetPosition>,
-    ) -> Result<(), Box<dyn Error + Send + Sync + 'static>>;
+    ) -> Result<PendingResponse<ResponseCode>, CoreCubeError>;
 
     /// Motor control with specified acceleration
     async fn motor_control_acceleration(
@@ -49,17 +78,150 @@ pub trait Simple {
         moving_direction: MovingDirection,
         period_ms: usize,
         priority: Priority,
-    ) -> Result<(), Box<dyn Error + Send + Sync + 'static>>;
+    ) -> Result<(), CoreCubeError>;
 
     /// Stop
-    async fn motor_stop(&self) -> Result<(), Box<dyn Error + Send + Sync + 'static>>;
+    async fn motor_stop(&self) -> Result<(), CoreCubeError>;
 
     // --------------------------------------------------------------------------------
     // Position ID
 
+    /// Read the current ID information
+    async fn id_information(&self) -> Result<IdInformation, CoreCubeError>;
+
+    // --------------------------------------------------------------------------------
+    // Light
+
+    /// Turn the indicator on (and off after the duration)
+    async fn light_turn_on_and_off(&self, param: IndicatorParam) -> Result<(), CoreCubeError>;
+
+    /// Turn the indicator on and off repeatedly (repeat 0: endless)
+    async fn light_repeat_turn_on_and_off(
+        &self,
+        repeat: u8,
+        param_list: Vec<IndicatorParam>,
+    ) -> Result<(), CoreCubeError>;
+
+    /// Turn off all indicators
+    async fn light_turn_off_all(&self) -> Result<(), CoreCubeError>;
+
+    // --------------------------------------------------------------------------------
+    // Sound
+
+    /// Play sound effect
+    async fn sound_play_effect(&self, id: SoundEffectId, volume: u8) -> Result<(), CoreCubeError>;
+
+    /// Play MIDI notes (repeat 0: endless)
+    async fn sound_play_midi_notes(
+        &self,
+        repeat: u8,
+        midi_notes: Vec<MidiNote>,
+    ) -> Result<(), CoreCubeError>;
+
+    /// Stop playing
+    async fn sound_stop(&self) -> Result<(), CoreCubeError>;
+
+    // --------------------------------------------------------------------------------
+    // Sensor
+
+    /// Request motion detection information
+    async fn request_motion_detection(
+        &self,
+    ) -> Result<PendingResponse<MotionDetectionInformation>, CoreCubeError>;
+
+    /// Request posture angle (Euler or quaternions by `data_type`)
+    async fn request_posture_angle_detection(
+        &self,
+        data_type: PostureDataType,
+    ) -> Result<PendingResponse<SensorInformation>, CoreCubeError>;
+
+    /// Request magnetic sensor information
+    async fn request_magnetic_sensor(
+        &self,
+    ) -> Result<PendingResponse<MagneticSensorInformation>, CoreCubeError>;
+
+    // --------------------------------------------------------------------------------
+    // Battery
+
+    /// Read the battery level
+    async fn battery(&self) -> Result<BatteryInformation, CoreCubeError>;
+
+    // --------------------------------------------------------------------------------
+    // Configuration
+
+    /// Request the BLE protocol version
+    async fn ble_protocol_version(
+        &self,
+    ) -> Result<PendingResponse<ResponseBleProtocolVersionData>, CoreCubeError>;
+
+    /// Horizontal detection threshold (degree)
+    async fn config_horizontal_detection_threshold(
+        &self,
+        threshold: u8,
+    ) -> Result<(), CoreCubeError>;
+
+    /// Collision detection threshold (1-10)
+    async fn config_collision_detection_threshold(
+        &self,
+        threshold: u8,
+    ) -> Result<(), CoreCubeError>;
+
+    /// Double tap detection time interval (1-7)
+    async fn config_double_tap_detection_time_interval(
+        &self,
+        interval: u8,
+    ) -> Result<(), CoreCubeError>;
+
+    /// ID notification (minimum interval: 10ms unit)
+    async fn config_id_sensor_notification(
+        &self,
+        minimum_interval: u8,
+        condition: IdSensorNotificationCondition,
+    ) -> Result<PendingResponse<ResponseIdSensorNotificationData>, CoreCubeError>;
+
+    /// ID missed notification (sensitivity: 10ms unit)
+    async fn config_id_sensor_missed_notification(
+        &self,
+        sensitivity: u8,
+    ) -> Result<PendingResponse<ResponseIdSensorMissedNotificationData>, CoreCubeError>;
+
+    /// Magnetic sensor (interval: 20ms unit)
+    async fn config_magnetic_sensor(
+        &self,
+        function_type: MagnetFunction,
+        interval: u8,
+        condition: MagnetNotificationCondition,
+    ) -> Result<PendingResponse<ResponseMagneticSensorData>, CoreCubeError>;
+
+    /// Motor speed information
+    async fn config_motor_speed(
+        &self,
+        enable: bool,
+    ) -> Result<PendingResponse<ResponseEnableMotorSpeedData>, CoreCubeError>;
+
+    /// Posture angle detection (interval: 10ms unit)
+    async fn config_posture_angle_detection(
+        &self,
+        data_type: PostureDataType,
+        interval: u8,
+        condition: PostureAngleNotificationCondition,
+    ) -> Result<PendingResponse<ResponsePostureAngleDetectionData>, CoreCubeError>;
+
     // --------------------------------------------------------------------------------
 }
 
+/// write the request after opening the notification stream to wait for its response
+async fn request<T>(
+    cube: &(dyn CubeInterface + Send + Sync + 'static),
+    uuid: Uuid,
+    payload: &[u8],
+    matcher: ResponseMatcher<T>,
+) -> Result<PendingResponse<T>, CoreCubeError> {
+    let events = cube.event_stream().await?;
+    cube.write(uuid, payload).await?;
+    Ok(PendingResponse::new(events, matcher))
+}
+
 #[async_trait]
 impl Simple for dyn CubeInterface + Send + Sync + 'static {
     async fn motor_control(
@@ -67,7 +229,7 @@ impl Simple for dyn CubeInterface + Send + Sync + 'static {
         left: isize,
         right: isize,
         period_ms: usize,
-    ) -> Result<(), Box<dyn Error + Send + Sync + 'static>> {
+    ) -> Result<(), CoreCubeError> {
         match period_ms {
             0 => {
                 let motor: MotorControl = MotorControl::set_value(i_to_i16(left), i_to_i16(right))?;
@@ -92,38 +254,56 @@ impl Simple for dyn CubeInterface + Send + Sync + 'static {
         &self,
         speed: isize,
         target: TargetPosition,
-    ) -> Result<(), Box<dyn Error + Send + Sync + 'static>> {
+    ) -> Result<PendingResponse<ResponseCode>, CoreCubeError> {
         let speed: Speed = Speed {
             max: i_to_u8(speed),
             ..Speed::default()
         };
+        let in_flight = self
+            .request_id_allocator()
+            .allocate()
+            .ok_or(CoreCubeError::RequestIdExhausted)?;
         let motor: MotorControlTarget = MotorControlTarget {
             speed,
             target,
-            ..MotorControlTarget::default()
+            ..MotorControlTarget::new(in_flight.request_id())
         };
+        let events = self.event_stream().await?;
         self.write(CoreCubeUuid::MotorCtrl.uuid(), &motor.to_payload())
             .await?;
-        Ok(())
+        Ok(PendingResponse::motor_control(
+            events,
+            CommandId::TargetPosition,
+            in_flight,
+        ))
     }
 
     async fn motor_control_multiple_targets(
         &self,
         speed: isize,
         target_list: Vec<TargetPosition>,
-    ) -> Result<(), Box<dyn Error + Send + Sync + 'static>> {
+    ) -> Result<PendingResponse<ResponseCode>, CoreCubeError> {
         let speed: Speed = Speed {
             max: i_to_u8(speed),
             ..Speed::default()
         };
+        let in_flight = self
+            .request_id_allocator()
+            .allocate()
+            .ok_or(CoreCubeError::RequestIdExhausted)?;
         let motor: MotorControlMultipleTargets = MotorControlMultipleTargets {
             speed,
             target_list,
-            ..MotorControlMultipleTargets::default()
+            ..MotorControlMultipleTargets::new(in_flight.request_id())
         };
+        let events = self.event_stream().await?;
         self.write(CoreCubeUuid::MotorCtrl.uuid(), &motor.to_payload())
             .await?;
-        Ok(())
+        Ok(PendingResponse::motor_control(
+            events,
+            CommandId::MultiTargetPositions,
+            in_flight,
+        ))
     }
 
     async fn motor_control_acceleration(
@@ -133,7 +313,7 @@ impl Simple for dyn CubeInterface + Send + Sync + 'static {
         moving_direction: MovingDirection,
         period_ms: usize,
         priority: Priority,
-    ) -> Result<(), Box<dyn Error + Send + Sync + 'static>> {
+    ) -> Result<(), CoreCubeError> {
         let period: Period = Period::from_millis(period_ms);
         let motor: MotorControlAcceleration = MotorControlAcceleration {
             acceleration,
@@ -148,20 +328,453 @@ impl Simple for dyn CubeInterface + Send + Sync + 'static {
         Ok(())
     }
 
-    async fn motor_stop(&self) -> Result<(), Box<dyn Error + Send + Sync + 'static>> {
+    async fn motor_stop(&self) -> Result<(), CoreCubeError> {
         self.motor_control(0, 0, 0).await?;
         Ok(())
     }
+
+    async fn id_information(&self) -> Result<IdInformation, CoreCubeError> {
+        self.read_id().await
+    }
+
+    async fn light_turn_on_and_off(&self, param: IndicatorParam) -> Result<(), CoreCubeError> {
+        let light = TurningOnAndOff {
+            param,
+            ..TurningOnAndOff::default()
+        };
+        self.write(CoreCubeUuid::LightCtrl.uuid(), &light.to_payload())
+            .await?;
+        Ok(())
+    }
+
+    async fn light_repeat_turn_on_and_off(
+        &self,
+        repeat: u8,
+        param_list: Vec<IndicatorParam>,
+    ) -> Result<(), CoreCubeError> {
+        let light = RepeatedTuringOnAndOff {
+            repeat,
+            param_list,
+            ..RepeatedTuringOnAndOff::default()
+        };
+        self.write(CoreCubeUuid::LightCtrl.uuid(), &light.to_payload())
+            .await?;
+        Ok(())
+    }
+
+    async fn light_turn_off_all(&self) -> Result<(), CoreCubeError> {
+        let light = TurnOffAll::default();
+        self.write(CoreCubeUuid::LightCtrl.uuid(), &light.to_payload())
+            .await?;
+        Ok(())
+    }
+
+    async fn sound_play_effect(&self, id: SoundEffectId, volume: u8) -> Result<(), CoreCubeError> {
+        let sound = PlaySoundEffect {
+            id,
+            volume,
+            ..PlaySoundEffect::default()
+        };
+        self.write(CoreCubeUuid::SoundCtrl.uuid(), &sound.to_payload())
+            .await?;
+        Ok(())
+    }
+
+    async fn sound_play_midi_notes(
+        &self,
+        repeat: u8,
+        midi_notes: Vec<MidiNote>,
+    ) -> Result<(), CoreCubeError> {
+        let sound = PlayMidiNote {
+            repeat,
+            midi_notes,
+            ..PlayMidiNote::default()
+        };
+        self.write(CoreCubeUuid::SoundCtrl.uuid(), &sound.to_payload())
+            .await?;
+        Ok(())
+    }
+
+    async fn sound_stop(&self) -> Result<(), CoreCubeError> {
+        let sound = StopSound::default();
+        self.write(CoreCubeUuid::SoundCtrl.uuid(), &sound.to_payload())
+            .await?;
+        Ok(())
+    }
+
+    async fn request_motion_detection(
+        &self,
+    ) -> Result<PendingResponse<MotionDetectionInformation>, CoreCubeError> {
+        let sensor = RequestMotionDetection::default();
+        request(
+            self,
+            CoreCubeUuid::SensorInfo.uuid(),
+            &sensor.to_payload(),
+            Box::new(|event| match event {
+                CubeEvent::Sensor(SensorInformation::MotionDetection(motion)) => Some(*motion),
+                _ => None,
+            }),
+        )
+        .await
+    }
+
+    async fn request_posture_angle_detection(
+        &self,
+        data_type: PostureDataType,
+    ) -> Result<PendingResponse<SensorInformation>, CoreCubeError> {
+        let sensor = RequestPostureAngleDetection {
+            data_type,
+            ..RequestPostureAngleDetection::default()
+        };
+        request(
+            self,
+            CoreCubeUuid::SensorInfo.uuid(),
+            &sensor.to_payload(),
+            Box::new(move |event| match (data_type, event) {
+                (
+                    PostureDataType::Euler,
+                    CubeEvent::Sensor(posture @ SensorInformation::PostureAngleEuler(_)),
+                )
+                | (
+                    PostureDataType::Quaternions,
+                    CubeEvent::Sensor(posture @ SensorInformation::PostureAngleQuaternion(_)),
+                ) => Some(*posture),
+                _ => None,
+            }),
+        )
+        .await
+    }
+
+    async fn request_magnetic_sensor(
+        &self,
+    ) -> Result<PendingResponse<MagneticSensorInformation>, CoreCubeError> {
+        let sensor = RequestMagneticSensor::default();
+        request(
+            self,
+            CoreCubeUuid::SensorInfo.uuid(),
+            &sensor.to_payload(),
+            Box::new(|event| match event {
+                CubeEvent::Sensor(SensorInformation::MagneticSensor(magnetic)) => Some(*magnetic),
+                _ => None,
+            }),
+        )
+        .await
+    }
+
+    async fn battery(&self) -> Result<BatteryInformation, CoreCubeError> {
+        self.read_battery().await
+    }
+
+    async fn ble_protocol_version(
+        &self,
+    ) -> Result<PendingResponse<ResponseBleProtocolVersionData>, CoreCubeError> {
+        let config = RequestBleProtocolVersion::new();
+        let response = self.request_configuration(&config.to_payload()).await?;
+        Ok(response.filter_map(|response| match response {
+            ConfigurationInformation::BleProtocolVersion(response) => Some(response),
+            _ => None,
+        }))
+    }
+
+    async fn config_horizontal_detection_threshold(
+        &self,
+        threshold: u8,
+    ) -> Result<(), CoreCubeError> {
+        let config = SetHorizontalDetectionThreshold::new(threshold);
+        self.write_configuration(&config.to_payload()).await
+    }
+
+    async fn config_collision_detection_threshold(
+        &self,
+        threshold: u8,
+    ) -> Result<(), CoreCubeError> {
+        let config = SetCollisionDetectionThreshold::new(threshold);
+        self.write_configuration(&config.to_payload()).await
+    }
+
+    async fn config_double_tap_detection_time_interval(
+        &self,
+        interval: u8,
+    ) -> Result<(), CoreCubeError> {
+        let config = SetDoubleTapDetectionTimeInterval::new(interval);
+        self.write_configuration(&config.to_payload()).await
+    }
+
+    async fn config_id_sensor_notification(
+        &self,
+        minimum_interval: u8,
+        condition: IdSensorNotificationCondition,
+    ) -> Result<PendingResponse<ResponseIdSensorNotificationData>, CoreCubeError> {
+        let config = SetIdSensorNotification::new(minimum_interval, condition);
+        let response = self.request_configuration(&config.to_payload()).await?;
+        Ok(response.filter_map(|response| match response {
+            ConfigurationInformation::IdSensorNotification(response) => Some(response),
+            _ => None,
+        }))
+    }
+
+    async fn config_id_sensor_missed_notification(
+        &self,
+        sensitivity: u8,
+    ) -> Result<PendingResponse<ResponseIdSensorMissedNotificationData>, CoreCubeError> {
+        let config = SetIdSensorMissedNotification::new(sensitivity);
+        let response = self.request_configuration(&config.to_payload()).await?;
+        Ok(response.filter_map(|response| match response {
+            ConfigurationInformation::IdSensorMissedNotification(response) => Some(response),
+            _ => None,
+        }))
+    }
+
+    async fn config_magnetic_sensor(
+        &self,
+        function_type: MagnetFunction,
+        interval: u8,
+        condition: MagnetNotificationCondition,
+    ) -> Result<PendingResponse<ResponseMagneticSensorData>, CoreCubeError> {
+        let config = SetMagneticSensor::new(function_type, interval, condition);
+        let response = self.request_configuration(&config.to_payload()).await?;
+        Ok(response.filter_map(|response| match response {
+            ConfigurationInformation::MagneticSensor(response) => Some(response),
+            _ => None,
+        }))
+    }
+
+    async fn config_motor_speed(
+        &self,
+        enable: bool,
+    ) -> Result<PendingResponse<ResponseEnableMotorSpeedData>, CoreCubeError> {
+        let config = EnableMotorSpeed::new(enable);
+        let response = self.request_configuration(&config.to_payload()).await?;
+        Ok(response.filter_map(|response| match response {
+            ConfigurationInformation::MotorSpeed(response) => Some(response),
+            _ => None,
+        }))
+    }
+
+    async fn config_posture_angle_detection(
+        &self,
+        data_type: PostureDataType,
+        interval: u8,
+        condition: PostureAngleNotificationCondition,
+    ) -> Result<PendingResponse<ResponsePostureAngleDetectionData>, CoreCubeError> {
+        let config = SetPostureAngleDetection::new(data_type, interval, condition);
+        let response = self.request_configuration(&config.to_payload()).await?;
+        Ok(response.filter_map(|response| match response {
+            ConfigurationInformation::PostureAngleDetection(response) => Some(response),
+            _ => None,
+        }))
+    }
 }
 
 #[cfg(test)]
 mod test {
+    use super::*;
+    use crate::characteristic::indicator::Color;
+    use crate::characteristic::motor::Period;
+    use crate::characteristic::sensor::{Posture, PostureAngleEulerInformation};
+    use crate::interface::virtual_cube::{
+        VirtualCube, VirtualSoundState, VIRTUAL_CUBE_PROTOCOL_VERSION,
+    };
+    use crate::position::{CubeLocation, Point};
+    use std::time::Duration;
+
     fn _setup() {
         let _ = env_logger::builder().is_test(true).try_init();
     }
 
+    fn target(x: isize, y: isize) -> TargetPosition {
+        TargetPosition {
+            cube_location: CubeLocation {
+                point: Point { x, y },
+                angle: 0,
+            },
+            ..TargetPosition::default()
+        }
+    }
+
     #[test]
     fn api_simple_test1() {
         _setup();
     }
+
+    // the simulation runs on the paused clock, so it does not take real time
+    #[tokio::test(start_paused = true)]
+    async fn api_simple_target1() {
+        _setup();
+        let virtual_cube = VirtualCube::default();
+        let mut cube: Box<dyn CubeInterface + Send + Sync + 'static> =
+            Box::new(virtual_cube.clone());
+        cube.connect().await.unwrap();
+        virtual_cube.place(CubeLocation {
+            point: Point { x: 100, y: 100 },
+            angle: 0,
+        });
+        let simulation = tokio::spawn(virtual_cube.run(Duration::from_millis(10)));
+
+        let first = cube
+            .motor_control_target(80, target(200, 100))
+            .await
+            .unwrap();
+        let second = cube
+            .motor_control_multiple_targets(80, vec![target(200, 200), target(100, 200)])
+            .await
+            .unwrap();
+        assert_eq!(cube.request_id_allocator().in_flight_count(), 2);
+        assert_eq!(first.await.unwrap(), ResponseCode::SuccessWithOverwrite);
+        assert_eq!(second.await.unwrap(), ResponseCode::Success);
+        assert_eq!(cube.request_id_allocator().in_flight_count(), 0);
+        assert_eq!(virtual_cube.request_id_allocator().in_flight_count(), 0);
+
+        let response = cube
+            .motor_control_target(80, target(100, 100))
+            .await
+            .unwrap();
+        assert!(matches!(
+            response.timeout(Duration::from_millis(100)).await,
+            Err(CoreCubeError::Timeout)
+        ));
+        simulation.abort();
+    }
+
+    async fn connected_cube(
+        virtual_cube: &VirtualCube,
+    ) -> Box<dyn CubeInterface + Send + Sync + 'static> {
+        let mut cube = virtual_cube.clone();
+        cube.connect().await.unwrap();
+        Box::new(cube)
+    }
+
+    #[tokio::test]
+    async fn api_simple_light_sound1() {
+        _setup();
+        let virtual_cube = VirtualCube::default();
+        let cube = connected_cube(&virtual_cube).await;
+
+        let red = IndicatorParam {
+            duration: Period::from_millis(0),
+            color: Color { r: 255, g: 0, b: 0 },
+        };
+        cube.light_turn_on_and_off(red).await.unwrap();
+        assert_eq!(virtual_cube.state().indicator.color, Some(red.color));
+        cube.light_turn_off_all().await.unwrap();
+        assert_eq!(virtual_cube.state().indicator.color, None);
+        cube.light_repeat_turn_on_and_off(3, vec![red])
+            .await
+            .unwrap();
+        assert_eq!(virtual_cube.state().indicator.repeat, 3);
+
+        cube.sound_play_effect(SoundEffectId::Cancel, 0x80)
+            .await
+            .unwrap();
+        assert_eq!(
+            virtual_cube.state().sound,
+            VirtualSoundState::SoundEffect {
+                id: SoundEffectId::Cancel.into(),
+                volume: 0x80
+            }
+        );
+        cube.sound_stop().await.unwrap();
+        assert_eq!(virtual_cube.state().sound, VirtualSoundState::Stopped);
+    }
+
+    #[tokio::test]
+    async fn api_simple_sensor1() {
+        _setup();
+        let virtual_cube = VirtualCube::default();
+        let cube = connected_cube(&virtual_cube).await;
+
+        virtual_cube.set_motion(MotionDetectionInformation {
+            posture: Posture::Front,
+            ..MotionDetectionInformation::default()
+        });
+        let motion = cube
+            .request_motion_detection()
+            .await
+            .unwrap()
+            .await
+            .unwrap();
+        assert_eq!(motion.posture, Posture::Front);
+
+        let euler = PostureAngleEulerInformation {
+            roll: 10,
+            pitch: -20,
+            yaw: 30,
+        };
+        virtual_cube.set_posture_angle(euler);
+        let posture = cube
+            .request_posture_angle_detection(PostureDataType::Euler)
+            .await
+            .unwrap()
+            .await
+            .unwrap();
+        assert_eq!(posture, SensorInformation::PostureAngleEuler(euler));
+
+        let magnetic = cube.request_magnetic_sensor().await.unwrap().await.unwrap();
+        assert_eq!(magnetic, virtual_cube.state().magnetic);
+
+        virtual_cube.set_battery_level(40);
+        assert_eq!(
+            cube.battery().await.unwrap(),
+            BatteryInformation { level: 40 }
+        );
+        assert_eq!(
+            cube.id_information().await.unwrap(),
+            IdInformation::PositionIdMissed
+        );
+    }
+
+    #[tokio::test]
+    async fn api_simple_config1() {
+        _setup();
+        let virtual_cube = VirtualCube::default();
+        let cube = connected_cube(&virtual_cube).await;
+
+        let version = cube.ble_protocol_version().await.unwrap().await.unwrap();
+        assert_eq!(&version.version, VIRTUAL_CUBE_PROTOCOL_VERSION);
+
+        cube.config_horizontal_detection_threshold(30)
+            .await
+            .unwrap();
+        cube.config_collision_detection_threshold(5).await.unwrap();
+        cube.config_double_tap_detection_time_interval(3)
+            .await
+            .unwrap();
+        let response = cube
+            .config_id_sensor_notification(5, IdSensorNotificationCondition::AtChange)
+            .await
+            .unwrap();
+        assert!(response.await.unwrap().result);
+        let response = cube.config_id_sensor_missed_notification(10).await.unwrap();
+        assert!(response.await.unwrap().result);
+        let response = cube
+            .config_magnetic_sensor(
+                MagnetFunction::MagnetState,
+                1,
+                MagnetNotificationCondition::Always,
+            )
+            .await
+            .unwrap();
+        assert!(response.await.unwrap().result);
+        let response = cube.config_motor_speed(true).await.unwrap();
+        assert!(response.await.unwrap().result);
+        let response = cube
+            .config_posture_angle_detection(
+                PostureDataType::Euler,
+                1,
+                PostureAngleNotificationCondition::Always,
+            )
+            .await
+            .unwrap();
+        assert!(response.await.unwrap().result);
+
+        let config = virtual_cube.state().config;
+        assert_eq!(config.horizontal_detection_threshold, 30);
+        assert_eq!(config.collision_detection_threshold, 5);
+        assert_eq!(config.double_tap_detection_time_interval, 3);
+        assert_eq!(config.id_notification_interval, 5);
+        assert_eq!(config.id_missed_notification_sensitivity, 10);
+        assert_eq!(config.magnetic_sensor_function, 0x01);
+        assert!(config.motor_speed_enabled);
+        assert_eq!(config.posture_angle_data_type, 0x01);
+    }
 }
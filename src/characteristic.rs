@@ -1,4 +1,5 @@
 mod characteristic_uuid;
+mod cube_event;
 
 pub mod battery;
 pub mod button;
@@ -13,6 +14,7 @@ use crate::notification_manager::HandlerFunction;
 use btleplug::api::ValueNotification;
 
 pub use crate::characteristic::characteristic_uuid::CoreCubeUuid;
+pub use crate::characteristic::cube_event::{CubeEvent, CubeEventKind};
 
 pub type NotificationData = ValueNotification;
 pub type NotificationHandler = HandlerFunction<NotificationData>;
@@ -1,22 +1,44 @@
 //! Official Specification:  <https://toio.github.io/toio-spec/en/docs/ble_battery>
 
-use crate::payload::FromPayload;
+use crate::integer_converter::u_to_u8;
+use crate::payload::{check_length, DecodeError, ToPayload, TryFromPayload};
 
 #[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
 pub struct BatteryInformation {
     pub level: usize,
 }
 
-impl FromPayload<&[u8]> for BatteryInformation {
-    fn from_payload(payload: &[u8]) -> Option<Self>
-    where
-        Self: Sized,
-    {
-        if payload.is_empty() {
-            return None;
-        }
-        Some(BatteryInformation {
-            level: payload[1].into(),
+impl TryFromPayload<&[u8]> for BatteryInformation {
+    fn try_from_payload(payload: &[u8]) -> Result<Self, DecodeError> {
+        check_length(payload, 1)?;
+        Ok(BatteryInformation {
+            level: payload[0].into(),
         })
     }
 }
+
+impl ToPayload<Vec<u8>> for BatteryInformation {
+    fn to_payload(self) -> Vec<u8> {
+        vec![u_to_u8(self.level)]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::payload::{assert_round_trip, FromPayload};
+
+    #[test]
+    fn battery_information1() {
+        let payload: [u8; 1] = [0x50];
+        let battery = BatteryInformation::from_payload(&payload);
+        assert_eq!(battery, Some(BatteryInformation { level: 80 }));
+    }
+
+    #[test]
+    fn battery_information_round_trip1() {
+        for level in 0..=u8::MAX as usize {
+            assert_round_trip(BatteryInformation { level });
+        }
+    }
+}
@@ -1,6 +1,7 @@
 //! Official Specification:  <https://toio.github.io/toio-spec/en/docs/ble_button>
 
 use super::super::def::button_state::ButtonState;
+use crate::payload::{decode_message, DecodeError, FromPayload, ToPayload, TryFromPayload};
 use std::time;
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -20,15 +21,44 @@ impl Default for ButtonInformation {
 
 impl ButtonInformation {
     pub fn new(byte_data: &[u8]) -> Option<ButtonInformation> {
-        if byte_data.len() < 2 {
-            return None;
-        }
-        match byte_data[0] {
-            0x01u8 => Some(ButtonInformation {
+        Self::from_payload(byte_data)
+    }
+}
+
+impl TryFromPayload<&[u8]> for ButtonInformation {
+    fn try_from_payload(payload: &[u8]) -> Result<Self, DecodeError> {
+        decode_message(payload, 0x01u8, 2, || {
+            Ok(ButtonInformation {
                 time: time::Instant::now(),
-                state: ButtonState::from(byte_data[1]),
-            }),
-            _ => None,
+                state: ButtonState::from(payload[1]),
+            })
+        })
+    }
+}
+
+impl ToPayload<Vec<u8>> for ButtonInformation {
+    fn to_payload(self) -> Vec<u8> {
+        vec![0x01u8, self.state.into()]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn button_information1() {
+        let button = ButtonInformation::from_payload(&[0x01, 0x80]).unwrap();
+        assert_eq!(button.state, ButtonState::Pressed);
+        assert_eq!(ButtonInformation::from_payload(&[0x01]), None);
+    }
+
+    #[test]
+    fn button_information_round_trip1() {
+        for code in 0..=u8::MAX {
+            let payload = vec![0x01u8, code];
+            let button = ButtonInformation::from_payload(&payload).unwrap();
+            assert_eq!(button.to_payload(), payload);
         }
     }
 }
@@ -39,6 +39,23 @@ impl CoreCubeUuid {
         }
     }
 
+    /// characteristic (or service) which has the uuid
+    pub fn from_uuid(uuid: Uuid) -> Option<Self> {
+        [
+            CoreCubeUuid::Service,
+            CoreCubeUuid::IdInfo,
+            CoreCubeUuid::SensorInfo,
+            CoreCubeUuid::ButtonInfo,
+            CoreCubeUuid::BatteryInfo,
+            CoreCubeUuid::MotorCtrl,
+            CoreCubeUuid::LightCtrl,
+            CoreCubeUuid::SoundCtrl,
+            CoreCubeUuid::Config,
+        ]
+        .into_iter()
+        .find(|core_cube_uuid| core_cube_uuid.uuid() == uuid)
+    }
+
     pub fn name(&self) -> &str {
         match self {
             CoreCubeUuid::Service => "Service",
@@ -75,4 +92,13 @@ mod test {
         );
         assert_eq!("Service", uuid_service.name());
     }
+
+    #[test]
+    fn uuid_2() {
+        assert_eq!(
+            CoreCubeUuid::from_uuid(CoreCubeUuid::Config.uuid()),
+            Some(CoreCubeUuid::Config)
+        );
+        assert_eq!(CoreCubeUuid::from_uuid(Uuid::nil()), None);
+    }
 }
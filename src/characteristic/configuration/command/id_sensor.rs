@@ -1,6 +1,6 @@
 use super::super::def::command_id::CommandId;
 use super::super::def::condition::IdSensorNotificationCondition;
-use crate::payload::ToPayload;
+use crate::payload::{decode_field, decode_message, DecodeError, ToPayload, TryFromPayload};
 
 /// Id sensor notification settings
 /// ref:<https://toio.github.io/toio-spec/en/docs/ble_configuration#identification-sensor-id-notification-settings>
@@ -16,7 +16,7 @@ pub struct SetIdSensorNotification {
 impl SetIdSensorNotification {
     pub fn new(minimum_interval: u8, condition: IdSensorNotificationCondition) -> Self {
         Self {
-            configuration_type: CommandId::BleProtocolVersion,
+            configuration_type: CommandId::IdSensorNotification,
             _reserved: 0,
             minimum_interval,
             condition,
@@ -35,6 +35,24 @@ impl ToPayload<Vec<u8>> for SetIdSensorNotification {
     }
 }
 
+impl TryFromPayload<&[u8]> for SetIdSensorNotification {
+    fn try_from_payload(payload: &[u8]) -> Result<Self, DecodeError> {
+        decode_message(
+            payload,
+            u8::from(CommandId::IdSensorNotification),
+            4,
+            || {
+                Ok(Self {
+                    configuration_type: CommandId::IdSensorNotification,
+                    _reserved: payload[1],
+                    minimum_interval: payload[2],
+                    condition: decode_field("condition", payload[3])?,
+                })
+            },
+        )
+    }
+}
+
 /// Id sensor missed notification settings
 /// ref:<https://toio.github.io/toio-spec/en/docs/ble_configuration#identification-sensor-id-missed-notification-settings>
 
@@ -64,3 +82,63 @@ impl ToPayload<Vec<u8>> for SetIdSensorMissedNotification {
         payload
     }
 }
+
+impl TryFromPayload<&[u8]> for SetIdSensorMissedNotification {
+    fn try_from_payload(payload: &[u8]) -> Result<Self, DecodeError> {
+        decode_message(
+            payload,
+            u8::from(CommandId::IdSensorMissedNotification),
+            3,
+            || {
+                Ok(Self {
+                    configuration_type: CommandId::IdSensorMissedNotification,
+                    _reserved: payload[1],
+                    sensitivity: payload[2],
+                })
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::payload::{assert_random_round_trip, assert_round_trip};
+    use rand::rngs::StdRng;
+    use rand::Rng;
+
+    fn _setup() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    fn random_set_id_sensor_notification(rng: &mut StdRng) -> SetIdSensorNotification {
+        SetIdSensorNotification {
+            _reserved: rng.gen(),
+            ..SetIdSensorNotification::new(
+                rng.gen(),
+                IdSensorNotificationCondition::try_from([0x00u8, 0x01, 0xff][rng.gen_range(0..3)])
+                    .unwrap(),
+            )
+        }
+    }
+
+    fn random_set_id_sensor_missed_notification(rng: &mut StdRng) -> SetIdSensorMissedNotification {
+        SetIdSensorMissedNotification {
+            _reserved: rng.gen(),
+            ..SetIdSensorMissedNotification::new(rng.gen())
+        }
+    }
+
+    #[test]
+    fn configuration_id_sensor1() {
+        _setup();
+
+        assert_round_trip(SetIdSensorNotification::new(
+            10,
+            IdSensorNotificationCondition::AtChange,
+        ));
+        assert_round_trip(SetIdSensorMissedNotification::new(7));
+        assert_random_round_trip(random_set_id_sensor_notification);
+        assert_random_round_trip(random_set_id_sensor_missed_notification);
+    }
+}
@@ -1,5 +1,5 @@
 use super::super::def::CommandId;
-use crate::payload::ToPayload;
+use crate::payload::{decode_message, DecodeError, ToPayload, TryFromPayload};
 
 /// Enable motor speed information
 /// ref:<https://toio.github.io/toio-spec/en/docs/ble_configuration#motor-speed-information-acquisition-settings>
@@ -28,3 +28,42 @@ impl ToPayload<Vec<u8>> for EnableMotorSpeed {
         payload
     }
 }
+
+impl TryFromPayload<&[u8]> for EnableMotorSpeed {
+    fn try_from_payload(payload: &[u8]) -> Result<Self, DecodeError> {
+        decode_message(payload, u8::from(CommandId::MotorSpeed), 3, || {
+            Ok(Self {
+                configuration_type: CommandId::MotorSpeed,
+                _reserved: payload[1],
+                enable: payload[2] != 0,
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::payload::{assert_random_round_trip, assert_round_trip};
+    use rand::rngs::StdRng;
+    use rand::Rng;
+
+    fn _setup() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    fn random_enable_motor_speed(rng: &mut StdRng) -> EnableMotorSpeed {
+        EnableMotorSpeed {
+            _reserved: rng.gen(),
+            ..EnableMotorSpeed::new(rng.gen())
+        }
+    }
+
+    #[test]
+    fn configuration_motor1() {
+        _setup();
+
+        assert_round_trip(EnableMotorSpeed::new(true));
+        assert_random_round_trip(random_enable_motor_speed);
+    }
+}
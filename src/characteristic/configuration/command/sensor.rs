@@ -4,7 +4,7 @@ use super::super::def::condition::{
 };
 use super::super::def::function_type::MagnetFunction;
 use crate::characteristic::sensor::def::posture_angle::PostureDataType;
-use crate::payload::ToPayload;
+use crate::payload::{decode_field, decode_message, DecodeError, ToPayload, TryFromPayload};
 
 /// Horizontal detection threshold setting
 /// ref:<https://toio.github.io/toio-spec/en/docs/ble_configuration#horizontal-detection-threshold-settings>
@@ -37,6 +37,23 @@ impl ToPayload<Vec<u8>> for SetHorizontalDetectionThreshold {
     }
 }
 
+impl TryFromPayload<&[u8]> for SetHorizontalDetectionThreshold {
+    fn try_from_payload(payload: &[u8]) -> Result<Self, DecodeError> {
+        decode_message(
+            payload,
+            u8::from(CommandId::HorizontalDetectionThreshold),
+            3,
+            || {
+                Ok(Self {
+                    configuration_type: CommandId::HorizontalDetectionThreshold,
+                    _reserved: payload[1],
+                    threshold: payload[2],
+                })
+            },
+        )
+    }
+}
+
 /// Collision detection threshold settings
 /// ref:<https://toio.github.io/toio-spec/en/docs/ble_configuration#collision-detection-threshold-settings>
 
@@ -68,6 +85,23 @@ impl ToPayload<Vec<u8>> for SetCollisionDetectionThreshold {
     }
 }
 
+impl TryFromPayload<&[u8]> for SetCollisionDetectionThreshold {
+    fn try_from_payload(payload: &[u8]) -> Result<Self, DecodeError> {
+        decode_message(
+            payload,
+            u8::from(CommandId::CollisionDetectionThreshold),
+            3,
+            || {
+                Ok(Self {
+                    configuration_type: CommandId::CollisionDetectionThreshold,
+                    _reserved: payload[1],
+                    threshold: payload[2],
+                })
+            },
+        )
+    }
+}
+
 /// Double tap detection time interval settings
 /// ref:<https://toio.github.io/toio-spec/en/docs/ble_configuration#double-tap-detection-time-interval-settings>
 
@@ -81,7 +115,7 @@ pub struct SetDoubleTapDetectionTimeInterval {
 impl SetDoubleTapDetectionTimeInterval {
     pub fn new(interval: u8) -> Self {
         Self {
-            configuration_type: CommandId::CollisionDetectionThreshold,
+            configuration_type: CommandId::DoubleTapDetectionTimeInterval,
             _reserved: 0,
             interval,
         }
@@ -99,6 +133,23 @@ impl ToPayload<Vec<u8>> for SetDoubleTapDetectionTimeInterval {
     }
 }
 
+impl TryFromPayload<&[u8]> for SetDoubleTapDetectionTimeInterval {
+    fn try_from_payload(payload: &[u8]) -> Result<Self, DecodeError> {
+        decode_message(
+            payload,
+            u8::from(CommandId::DoubleTapDetectionTimeInterval),
+            3,
+            || {
+                Ok(Self {
+                    configuration_type: CommandId::DoubleTapDetectionTimeInterval,
+                    _reserved: payload[1],
+                    interval: payload[2],
+                })
+            },
+        )
+    }
+}
+
 /// Set magnetic sensor
 /// ref:<https://toio.github.io/toio-spec/en/docs/ble_configuration#magnetic-sensor-settings->
 
@@ -140,6 +191,20 @@ impl ToPayload<Vec<u8>> for SetMagneticSensor {
     }
 }
 
+impl TryFromPayload<&[u8]> for SetMagneticSensor {
+    fn try_from_payload(payload: &[u8]) -> Result<Self, DecodeError> {
+        decode_message(payload, u8::from(CommandId::MagneticSensor), 5, || {
+            Ok(Self {
+                configuration_type: CommandId::MagneticSensor,
+                _reserved: payload[1],
+                function_type: decode_field("magnet function", payload[2])?,
+                interval: payload[3],
+                condition: decode_field("condition", payload[4])?,
+            })
+        })
+    }
+}
+
 /// Posture angle detection settings
 /// ref:<https://toio.github.io/toio-spec/en/docs/ble_configuration#posture-angle-detection-settings->
 
@@ -180,3 +245,95 @@ impl ToPayload<Vec<u8>> for SetPostureAngleDetection {
         payload
     }
 }
+
+impl TryFromPayload<&[u8]> for SetPostureAngleDetection {
+    fn try_from_payload(payload: &[u8]) -> Result<Self, DecodeError> {
+        decode_message(
+            payload,
+            u8::from(CommandId::PostureAngleDetection),
+            5,
+            || {
+                Ok(Self {
+                    configuration_type: CommandId::PostureAngleDetection,
+                    _reserved: payload[1],
+                    data_type: decode_field("posture data type", payload[2])?,
+                    interval: payload[3],
+                    condition: decode_field("condition", payload[4])?,
+                })
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::payload::{assert_random_round_trip, assert_round_trip};
+    use rand::rngs::StdRng;
+    use rand::Rng;
+
+    fn _setup() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    fn random_set_horizontal_detection_threshold(
+        rng: &mut StdRng,
+    ) -> SetHorizontalDetectionThreshold {
+        SetHorizontalDetectionThreshold {
+            _reserved: rng.gen(),
+            ..SetHorizontalDetectionThreshold::new(rng.gen())
+        }
+    }
+
+    fn random_set_collision_detection_threshold(
+        rng: &mut StdRng,
+    ) -> SetCollisionDetectionThreshold {
+        SetCollisionDetectionThreshold {
+            _reserved: rng.gen(),
+            ..SetCollisionDetectionThreshold::new(rng.gen())
+        }
+    }
+
+    fn random_set_double_tap_detection_time_interval(
+        rng: &mut StdRng,
+    ) -> SetDoubleTapDetectionTimeInterval {
+        SetDoubleTapDetectionTimeInterval {
+            _reserved: rng.gen(),
+            ..SetDoubleTapDetectionTimeInterval::new(rng.gen())
+        }
+    }
+
+    fn random_set_magnetic_sensor(rng: &mut StdRng) -> SetMagneticSensor {
+        SetMagneticSensor {
+            _reserved: rng.gen(),
+            ..SetMagneticSensor::new(
+                MagnetFunction::try_from(rng.gen_range(0..3)).unwrap(),
+                rng.gen(),
+                MagnetNotificationCondition::try_from(rng.gen_range(0..2)).unwrap(),
+            )
+        }
+    }
+
+    fn random_set_posture_angle_detection(rng: &mut StdRng) -> SetPostureAngleDetection {
+        SetPostureAngleDetection {
+            _reserved: rng.gen(),
+            ..SetPostureAngleDetection::new(
+                PostureDataType::try_from(rng.gen_range(1..3)).unwrap(),
+                rng.gen(),
+                PostureAngleNotificationCondition::try_from(rng.gen_range(0..2)).unwrap(),
+            )
+        }
+    }
+
+    #[test]
+    fn configuration_sensor1() {
+        _setup();
+
+        assert_round_trip(SetHorizontalDetectionThreshold::new(45));
+        assert_random_round_trip(random_set_horizontal_detection_threshold);
+        assert_random_round_trip(random_set_collision_detection_threshold);
+        assert_random_round_trip(random_set_double_tap_detection_time_interval);
+        assert_random_round_trip(random_set_magnetic_sensor);
+        assert_random_round_trip(random_set_posture_angle_detection);
+    }
+}
@@ -1,5 +1,5 @@
 use super::super::def::command_id::CommandId;
-use crate::payload::ToPayload;
+use crate::payload::{decode_message, DecodeError, ToPayload, TryFromPayload};
 
 /// Requesting the BLE protocol version
 /// ref:<https://toio.github.io/toio-spec/en/docs/ble_configuration#requesting-the-ble-protocol-version>
@@ -31,3 +31,41 @@ impl ToPayload<Vec<u8>> for RequestBleProtocolVersion {
         payload
     }
 }
+
+impl TryFromPayload<&[u8]> for RequestBleProtocolVersion {
+    fn try_from_payload(payload: &[u8]) -> Result<Self, DecodeError> {
+        decode_message(payload, u8::from(CommandId::BleProtocolVersion), 2, || {
+            Ok(Self {
+                configuration_type: CommandId::BleProtocolVersion,
+                _reserved: payload[1],
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::payload::{assert_random_round_trip, assert_round_trip};
+    use rand::rngs::StdRng;
+    use rand::Rng;
+
+    fn _setup() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    fn random_request_ble_protocol_version(rng: &mut StdRng) -> RequestBleProtocolVersion {
+        RequestBleProtocolVersion {
+            _reserved: rng.gen(),
+            ..RequestBleProtocolVersion::new()
+        }
+    }
+
+    #[test]
+    fn configuration_version1() {
+        _setup();
+
+        assert_round_trip(RequestBleProtocolVersion::new());
+        assert_random_round_trip(random_request_ble_protocol_version);
+    }
+}
@@ -29,10 +29,39 @@ impl From<CommandId> for u8 {
     }
 }
 
+impl TryFrom<u8> for CommandId {
+    type Error = ();
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0x01u8 => Ok(CommandId::BleProtocolVersion),
+            0x05u8 => Ok(CommandId::HorizontalDetectionThreshold),
+            0x06u8 => Ok(CommandId::CollisionDetectionThreshold),
+            0x17u8 => Ok(CommandId::DoubleTapDetectionTimeInterval),
+            0x18u8 => Ok(CommandId::IdSensorNotification),
+            0x19u8 => Ok(CommandId::IdSensorMissedNotification),
+            0x1bu8 => Ok(CommandId::MagneticSensor),
+            0x1cu8 => Ok(CommandId::MotorSpeed),
+            0x1du8 => Ok(CommandId::PostureAngleDetection),
+            _ => Err(()),
+        }
+    }
+}
+
 impl CommandId {
     pub fn response(self) -> u8 {
         u8::from(self) | 0x80u8
     }
+
+    /// the cube does not respond to the threshold settings
+    pub fn has_response(self) -> bool {
+        !matches!(
+            self,
+            CommandId::HorizontalDetectionThreshold
+                | CommandId::CollisionDetectionThreshold
+                | CommandId::DoubleTapDetectionTimeInterval
+        )
+    }
 }
 
 impl ToPayload<Vec<u8>> for CommandId {
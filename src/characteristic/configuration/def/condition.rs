@@ -17,6 +17,18 @@ impl From<MagnetNotificationCondition> for u8 {
     }
 }
 
+impl TryFrom<u8> for MagnetNotificationCondition {
+    type Error = ();
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0x00u8 => Ok(MagnetNotificationCondition::Always),
+            0x01u8 => Ok(MagnetNotificationCondition::AtChange),
+            _ => Err(()),
+        }
+    }
+}
+
 impl ToPayload<Vec<u8>> for MagnetNotificationCondition {
     fn to_payload(self) -> Vec<u8> {
         let payload: Vec<u8> = vec![self.into()];
@@ -41,6 +53,18 @@ impl From<PostureAngleNotificationCondition> for u8 {
     }
 }
 
+impl TryFrom<u8> for PostureAngleNotificationCondition {
+    type Error = ();
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0x00u8 => Ok(PostureAngleNotificationCondition::Always),
+            0x01u8 => Ok(PostureAngleNotificationCondition::AtChange),
+            _ => Err(()),
+        }
+    }
+}
+
 impl ToPayload<Vec<u8>> for PostureAngleNotificationCondition {
     fn to_payload(self) -> Vec<u8> {
         let payload: Vec<u8> = vec![self.into()];
@@ -67,6 +91,19 @@ impl From<IdSensorNotificationCondition> for u8 {
     }
 }
 
+impl TryFrom<u8> for IdSensorNotificationCondition {
+    type Error = ();
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0x00u8 => Ok(IdSensorNotificationCondition::Always),
+            0x01u8 => Ok(IdSensorNotificationCondition::AtChange),
+            0xffu8 => Ok(IdSensorNotificationCondition::Sleep300WithNoChange),
+            _ => Err(()),
+        }
+    }
+}
+
 impl ToPayload<Vec<u8>> for IdSensorNotificationCondition {
     fn to_payload(self) -> Vec<u8> {
         let payload: Vec<u8> = vec![self.into()];
@@ -19,6 +19,19 @@ impl From<MagnetFunction> for u8 {
     }
 }
 
+impl TryFrom<u8> for MagnetFunction {
+    type Error = ();
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0x00u8 => Ok(MagnetFunction::Disable),
+            0x01u8 => Ok(MagnetFunction::MagnetState),
+            0x02u8 => Ok(MagnetFunction::MagnetForce),
+            _ => Err(()),
+        }
+    }
+}
+
 impl ToPayload<Vec<u8>> for MagnetFunction {
     fn to_payload(self) -> Vec<u8> {
         let payload: Vec<u8> = vec![self.into()];
@@ -7,5 +7,153 @@ pub use self::id_sensor::{
     ResponseIdSensorMissedNotificationData, ResponseIdSensorNotificationData,
 };
 pub use self::motor::ResponseEnableMotorSpeedData;
-pub use self::sensor::ResponseMagneticSensorData;
+pub use self::sensor::{ResponseMagneticSensorData, ResponsePostureAngleDetectionData};
 pub use self::version::ResponseBleProtocolVersionData;
+
+use crate::characteristic::configuration::def::CommandId;
+use crate::payload::{check_length, DecodeError, ToPayload, TryFromPayload};
+
+/// Configuration response
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ConfigurationInformation {
+    BleProtocolVersion(ResponseBleProtocolVersionData),
+    IdSensorNotification(ResponseIdSensorNotificationData),
+    IdSensorMissedNotification(ResponseIdSensorMissedNotificationData),
+    MagneticSensor(ResponseMagneticSensorData),
+    MotorSpeed(ResponseEnableMotorSpeedData),
+    PostureAngleDetection(ResponsePostureAngleDetectionData),
+}
+
+impl TryFromPayload<&[u8]> for ConfigurationInformation {
+    fn try_from_payload(payload: &[u8]) -> Result<Self, DecodeError> {
+        check_length(payload, 1)?;
+        let opcode = payload[0];
+        if opcode == CommandId::BleProtocolVersion.response() {
+            ResponseBleProtocolVersionData::try_from_payload(payload)
+                .map(ConfigurationInformation::BleProtocolVersion)
+        } else if opcode == CommandId::IdSensorNotification.response() {
+            ResponseIdSensorNotificationData::try_from_payload(payload)
+                .map(ConfigurationInformation::IdSensorNotification)
+        } else if opcode == CommandId::IdSensorMissedNotification.response() {
+            ResponseIdSensorMissedNotificationData::try_from_payload(payload)
+                .map(ConfigurationInformation::IdSensorMissedNotification)
+        } else if opcode == CommandId::MagneticSensor.response() {
+            ResponseMagneticSensorData::try_from_payload(payload)
+                .map(ConfigurationInformation::MagneticSensor)
+        } else if opcode == CommandId::MotorSpeed.response() {
+            ResponseEnableMotorSpeedData::try_from_payload(payload)
+                .map(ConfigurationInformation::MotorSpeed)
+        } else if opcode == CommandId::PostureAngleDetection.response() {
+            ResponsePostureAngleDetectionData::try_from_payload(payload)
+                .map(ConfigurationInformation::PostureAngleDetection)
+        } else {
+            Err(DecodeError::UnknownOpcode(opcode))
+        }
+    }
+}
+
+impl ToPayload<Vec<u8>> for ConfigurationInformation {
+    fn to_payload(self) -> Vec<u8> {
+        match self {
+            ConfigurationInformation::BleProtocolVersion(response_data) => {
+                response_data.to_payload()
+            }
+            ConfigurationInformation::IdSensorNotification(response_data) => {
+                response_data.to_payload()
+            }
+            ConfigurationInformation::IdSensorMissedNotification(response_data) => {
+                response_data.to_payload()
+            }
+            ConfigurationInformation::MagneticSensor(response_data) => response_data.to_payload(),
+            ConfigurationInformation::MotorSpeed(response_data) => response_data.to_payload(),
+            ConfigurationInformation::PostureAngleDetection(response_data) => {
+                response_data.to_payload()
+            }
+        }
+    }
+}
+
+impl From<ConfigurationInformation> for u8 {
+    fn from(response_type: ConfigurationInformation) -> u8 {
+        match response_type {
+            ConfigurationInformation::BleProtocolVersion(_) => {
+                CommandId::BleProtocolVersion.response()
+            }
+            ConfigurationInformation::IdSensorNotification(_) => {
+                CommandId::IdSensorNotification.response()
+            }
+            ConfigurationInformation::IdSensorMissedNotification(_) => {
+                CommandId::IdSensorMissedNotification.response()
+            }
+            ConfigurationInformation::MagneticSensor(_) => CommandId::MagneticSensor.response(),
+            ConfigurationInformation::MotorSpeed(_) => CommandId::MotorSpeed.response(),
+            ConfigurationInformation::PostureAngleDetection(_) => {
+                CommandId::PostureAngleDetection.response()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::payload::{assert_random_round_trip, FromPayload};
+    use rand::rngs::StdRng;
+    use rand::Rng;
+
+    fn _setup() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    fn random_configuration_information(rng: &mut StdRng) -> ConfigurationInformation {
+        let result: bool = rng.gen();
+        match rng.gen_range(0..6) {
+            0 => ConfigurationInformation::BleProtocolVersion(ResponseBleProtocolVersionData {
+                version: rng.gen(),
+            }),
+            1 => ConfigurationInformation::IdSensorNotification(ResponseIdSensorNotificationData {
+                result,
+            }),
+            2 => ConfigurationInformation::IdSensorMissedNotification(
+                ResponseIdSensorMissedNotificationData { result },
+            ),
+            3 => ConfigurationInformation::MagneticSensor(ResponseMagneticSensorData { result }),
+            4 => ConfigurationInformation::MotorSpeed(ResponseEnableMotorSpeedData { result }),
+            _ => {
+                ConfigurationInformation::PostureAngleDetection(ResponsePostureAngleDetectionData {
+                    result,
+                })
+            }
+        }
+    }
+
+    #[test]
+    fn configuration_response1() {
+        _setup();
+
+        let payload: [u8; 7] = [0x81, 0x00, 0x32, 0x2e, 0x34, 0x2e, 0x30];
+        assert_eq!(
+            ConfigurationInformation::from_payload(&payload),
+            Some(ConfigurationInformation::BleProtocolVersion(
+                ResponseBleProtocolVersionData { version: *b"2.4.0" }
+            ))
+        );
+        let payload: [u8; 3] = [0x98, 0x00, 0x00];
+        assert_eq!(
+            ConfigurationInformation::from_payload(&payload),
+            Some(ConfigurationInformation::IdSensorNotification(
+                ResponseIdSensorNotificationData { result: true }
+            ))
+        );
+        let payload: [u8; 3] = [0x9d, 0x00, 0x01];
+        assert_eq!(
+            ConfigurationInformation::from_payload(&payload),
+            Some(ConfigurationInformation::PostureAngleDetection(
+                ResponsePostureAngleDetectionData { result: false }
+            ))
+        );
+        assert_eq!(ConfigurationInformation::from_payload(&[0x98]), None);
+        assert_random_round_trip(random_configuration_information);
+    }
+}
@@ -1,5 +1,5 @@
 use super::super::def::command_id::CommandId;
-use crate::payload::FromPayload;
+use crate::payload::{decode_message, DecodeError, ToPayload, TryFromPayload};
 
 /// Response to
 /// ref:<https://toio.github.io/toio-spec/en/docs/ble_configuration#responses-to-identification-sensor-id-notification-settings>
@@ -9,18 +9,28 @@ pub struct ResponseIdSensorNotificationData {
     pub result: bool,
 }
 
-impl FromPayload<&[u8]> for ResponseIdSensorNotificationData {
-    fn from_payload(payload: &[u8]) -> Option<Self>
-    where
-        Self: Sized,
-    {
-        if payload[0] == CommandId::IdSensorNotification.response() {
-            Some(Self {
-                result: payload[2] == 0x00u8,
-            })
-        } else {
-            None
-        }
+impl TryFromPayload<&[u8]> for ResponseIdSensorNotificationData {
+    fn try_from_payload(payload: &[u8]) -> Result<Self, DecodeError> {
+        decode_message(
+            payload,
+            CommandId::IdSensorNotification.response(),
+            3,
+            || {
+                Ok(Self {
+                    result: payload[2] == 0x00u8,
+                })
+            },
+        )
+    }
+}
+
+impl ToPayload<Vec<u8>> for ResponseIdSensorNotificationData {
+    fn to_payload(self) -> Vec<u8> {
+        vec![
+            CommandId::IdSensorNotification.response(),
+            0x00u8,
+            if self.result { 0x00u8 } else { 0x01u8 },
+        ]
     }
 }
 
@@ -32,17 +42,27 @@ pub struct ResponseIdSensorMissedNotificationData {
     pub result: bool,
 }
 
-impl FromPayload<&[u8]> for ResponseIdSensorMissedNotificationData {
-    fn from_payload(payload: &[u8]) -> Option<Self>
-    where
-        Self: Sized,
-    {
-        if payload[0] == CommandId::IdSensorMissedNotification.response() {
-            Some(Self {
-                result: payload[2] == 0x00u8,
-            })
-        } else {
-            None
-        }
+impl TryFromPayload<&[u8]> for ResponseIdSensorMissedNotificationData {
+    fn try_from_payload(payload: &[u8]) -> Result<Self, DecodeError> {
+        decode_message(
+            payload,
+            CommandId::IdSensorMissedNotification.response(),
+            3,
+            || {
+                Ok(Self {
+                    result: payload[2] == 0x00u8,
+                })
+            },
+        )
+    }
+}
+
+impl ToPayload<Vec<u8>> for ResponseIdSensorMissedNotificationData {
+    fn to_payload(self) -> Vec<u8> {
+        vec![
+            CommandId::IdSensorMissedNotification.response(),
+            0x00u8,
+            if self.result { 0x00u8 } else { 0x01u8 },
+        ]
     }
 }
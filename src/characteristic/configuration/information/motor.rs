@@ -1,5 +1,5 @@
 use super::super::def::command_id::CommandId;
-use crate::payload::FromPayload;
+use crate::payload::{decode_message, DecodeError, ToPayload, TryFromPayload};
 
 /// Response to motor speed information\
 /// ref:<https://toio.github.io/toio-spec/en/docs/ble_configuration#responses-to-motor-speed-information-acquisition-settings>
@@ -9,20 +9,22 @@ pub struct ResponseEnableMotorSpeedData {
     pub result: bool,
 }
 
-impl FromPayload<&[u8]> for ResponseEnableMotorSpeedData {
-    fn from_payload(payload: &[u8]) -> Option<Self>
-    where
-        Self: Sized,
-    {
-        if payload.len() < 3 {
-            return None;
-        }
-        if payload[0] == CommandId::MotorSpeed.response() {
-            Some(Self {
+impl TryFromPayload<&[u8]> for ResponseEnableMotorSpeedData {
+    fn try_from_payload(payload: &[u8]) -> Result<Self, DecodeError> {
+        decode_message(payload, CommandId::MotorSpeed.response(), 3, || {
+            Ok(Self {
                 result: payload[2] == 0x00u8,
             })
-        } else {
-            None
-        }
+        })
+    }
+}
+
+impl ToPayload<Vec<u8>> for ResponseEnableMotorSpeedData {
+    fn to_payload(self) -> Vec<u8> {
+        vec![
+            CommandId::MotorSpeed.response(),
+            0x00u8,
+            if self.result { 0x00u8 } else { 0x01u8 },
+        ]
     }
 }
@@ -1,5 +1,5 @@
 use super::super::def::CommandId;
-use crate::payload::FromPayload;
+use crate::payload::{decode_message, DecodeError, ToPayload, TryFromPayload};
 
 /// Response to magnetic sensor settings
 /// ref:<https://toio.github.io/toio-spec/en/docs/ble_configuration#responses-to-magnetic-sensor-settings>
@@ -9,20 +9,55 @@ pub struct ResponseMagneticSensorData {
     pub result: bool,
 }
 
-impl FromPayload<&[u8]> for ResponseMagneticSensorData {
-    fn from_payload(payload: &[u8]) -> Option<Self>
-    where
-        Self: Sized,
-    {
-        if payload.len() < 3 {
-            return None;
-        }
-        if payload[0] == CommandId::MagneticSensor.response() {
-            Some(Self {
+impl TryFromPayload<&[u8]> for ResponseMagneticSensorData {
+    fn try_from_payload(payload: &[u8]) -> Result<Self, DecodeError> {
+        decode_message(payload, CommandId::MagneticSensor.response(), 3, || {
+            Ok(Self {
                 result: payload[2] == 0x00u8,
             })
-        } else {
-            None
-        }
+        })
+    }
+}
+
+impl ToPayload<Vec<u8>> for ResponseMagneticSensorData {
+    fn to_payload(self) -> Vec<u8> {
+        vec![
+            CommandId::MagneticSensor.response(),
+            0x00u8,
+            if self.result { 0x00u8 } else { 0x01u8 },
+        ]
+    }
+}
+
+/// Response to posture angle detection settings
+/// ref:<https://toio.github.io/toio-spec/en/docs/ble_configuration#responses-to-posture-angle-detection-settings>
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ResponsePostureAngleDetectionData {
+    pub result: bool,
+}
+
+impl TryFromPayload<&[u8]> for ResponsePostureAngleDetectionData {
+    fn try_from_payload(payload: &[u8]) -> Result<Self, DecodeError> {
+        decode_message(
+            payload,
+            CommandId::PostureAngleDetection.response(),
+            3,
+            || {
+                Ok(Self {
+                    result: payload[2] == 0x00u8,
+                })
+            },
+        )
+    }
+}
+
+impl ToPayload<Vec<u8>> for ResponsePostureAngleDetectionData {
+    fn to_payload(self) -> Vec<u8> {
+        vec![
+            CommandId::PostureAngleDetection.response(),
+            0x00u8,
+            if self.result { 0x00u8 } else { 0x01u8 },
+        ]
     }
 }
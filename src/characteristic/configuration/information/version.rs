@@ -1,5 +1,5 @@
 use super::super::def::command_id::CommandId;
-use crate::payload::FromPayload;
+use crate::payload::{decode_message, DecodeError, ToPayload, TryFromPayload};
 
 /// Obtaining the BLE protocol version
 /// ref:<https://toio.github.io/toio-spec/en/docs/ble_configuration#obtaining-the-ble-protocol-version>
@@ -9,19 +9,19 @@ pub struct ResponseBleProtocolVersionData {
     pub version: [u8; 5],
 }
 
-impl FromPayload<&[u8]> for ResponseBleProtocolVersionData {
-    fn from_payload(payload: &[u8]) -> Option<Self>
-    where
-        Self: Sized,
-    {
-        if payload.len() != 7 {
-            return None;
-        }
-        if payload[0] == CommandId::BleProtocolVersion.response() {
+impl TryFromPayload<&[u8]> for ResponseBleProtocolVersionData {
+    fn try_from_payload(payload: &[u8]) -> Result<Self, DecodeError> {
+        decode_message(payload, CommandId::BleProtocolVersion.response(), 7, || {
             let version: [u8; 5] = [payload[2], payload[3], payload[4], payload[5], payload[6]];
-            Some(Self { version })
-        } else {
-            None
-        }
+            Ok(Self { version })
+        })
+    }
+}
+
+impl ToPayload<Vec<u8>> for ResponseBleProtocolVersionData {
+    fn to_payload(self) -> Vec<u8> {
+        let mut payload: Vec<u8> = vec![CommandId::BleProtocolVersion.response(), 0x00u8];
+        payload.extend(self.version);
+        payload
     }
 }
@@ -0,0 +1,138 @@
+use crate::characteristic::battery::BatteryInformation;
+use crate::characteristic::button::ButtonInformation;
+use crate::characteristic::configuration::ConfigurationInformation;
+use crate::characteristic::id::IdInformation;
+use crate::characteristic::motor::MotorInformation;
+use crate::characteristic::sensor::SensorInformation;
+use crate::characteristic::{CoreCubeUuid, NotificationData};
+use crate::payload::FromPayload;
+use uuid::Uuid;
+
+/// Decoded notification from a cube
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CubeEvent {
+    Id(IdInformation),
+    Sensor(SensorInformation),
+    Motor(MotorInformation),
+    Button(ButtonInformation),
+    Battery(BatteryInformation),
+    Configuration(ConfigurationInformation),
+    /// notification which could not be decoded
+    Unknown {
+        uuid: Uuid,
+        value: Vec<u8>,
+    },
+}
+
+/// Kind of CubeEvent (without the decoded data)
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum CubeEventKind {
+    Id,
+    Sensor,
+    Motor,
+    Button,
+    Battery,
+    Configuration,
+    Unknown,
+}
+
+impl CubeEvent {
+    pub fn kind(&self) -> CubeEventKind {
+        match self {
+            CubeEvent::Id(_) => CubeEventKind::Id,
+            CubeEvent::Sensor(_) => CubeEventKind::Sensor,
+            CubeEvent::Motor(_) => CubeEventKind::Motor,
+            CubeEvent::Button(_) => CubeEventKind::Button,
+            CubeEvent::Battery(_) => CubeEventKind::Battery,
+            CubeEvent::Configuration(_) => CubeEventKind::Configuration,
+            CubeEvent::Unknown { .. } => CubeEventKind::Unknown,
+        }
+    }
+
+    /// decode the payload with the decoder of the characteristic
+    pub fn decode(uuid: Uuid, payload: &[u8]) -> Self {
+        let event = match CoreCubeUuid::from_uuid(uuid) {
+            Some(CoreCubeUuid::IdInfo) => IdInformation::from_payload(payload).map(CubeEvent::Id),
+            Some(CoreCubeUuid::SensorInfo) => {
+                SensorInformation::from_payload(payload).map(CubeEvent::Sensor)
+            }
+            Some(CoreCubeUuid::MotorCtrl) => {
+                MotorInformation::from_payload(payload).map(CubeEvent::Motor)
+            }
+            Some(CoreCubeUuid::ButtonInfo) => {
+                ButtonInformation::from_payload(payload).map(CubeEvent::Button)
+            }
+            Some(CoreCubeUuid::BatteryInfo) => {
+                BatteryInformation::from_payload(payload).map(CubeEvent::Battery)
+            }
+            Some(CoreCubeUuid::Config) => {
+                ConfigurationInformation::from_payload(payload).map(CubeEvent::Configuration)
+            }
+            _ => None,
+        };
+        event.unwrap_or_else(|| CubeEvent::Unknown {
+            uuid,
+            value: payload.to_vec(),
+        })
+    }
+}
+
+impl From<NotificationData> for CubeEvent {
+    fn from(data: NotificationData) -> Self {
+        CubeEvent::decode(data.uuid, &data.value)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::characteristic::button::ButtonState;
+
+    fn _setup() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    #[test]
+    fn cube_event1() {
+        _setup();
+
+        let event = CubeEvent::decode(CoreCubeUuid::IdInfo.uuid(), &[0x03]);
+        assert_eq!(event, CubeEvent::Id(IdInformation::PositionIdMissed));
+
+        match CubeEvent::decode(CoreCubeUuid::ButtonInfo.uuid(), &[0x01, 0x80]) {
+            CubeEvent::Button(button) => assert_eq!(button.state, ButtonState::Pressed),
+            _ => unreachable!(),
+        }
+
+        let event = CubeEvent::from(NotificationData {
+            uuid: CoreCubeUuid::BatteryInfo.uuid(),
+            value: vec![0x50],
+        });
+        assert_eq!(event, CubeEvent::Battery(BatteryInformation { level: 80 }));
+        assert_eq!(event.kind(), CubeEventKind::Battery);
+    }
+
+    #[test]
+    fn cube_event2() {
+        _setup();
+
+        // same payload is decoded by the characteristic
+        let payload: [u8; 3] = [0x01, 0x00, 0x00];
+        assert!(matches!(
+            CubeEvent::decode(CoreCubeUuid::SensorInfo.uuid(), &payload),
+            CubeEvent::Unknown { .. }
+        ));
+        assert!(matches!(
+            CubeEvent::decode(CoreCubeUuid::IdInfo.uuid(), &payload),
+            CubeEvent::Unknown { .. }
+        ));
+        assert_eq!(
+            CubeEvent::decode(CoreCubeUuid::LightCtrl.uuid(), &payload),
+            CubeEvent::Unknown {
+                uuid: CoreCubeUuid::LightCtrl.uuid(),
+                value: payload.to_vec(),
+            }
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use crate::payload::ToPayload;
+use crate::payload::{check_length, DecodeError, ToPayload, TryFromPayload};
 use crate::position::CubeLocation;
 
 /// Position ID
@@ -19,6 +19,16 @@ impl ToPayload<Vec<u8>> for PositionIdData {
     }
 }
 
+impl TryFromPayload<&[u8]> for PositionIdData {
+    fn try_from_payload(payload: &[u8]) -> Result<Self, DecodeError> {
+        check_length(payload, 12)?;
+        Ok(Self {
+            center: CubeLocation::try_from_payload(&payload[0..6])?,
+            sensor: CubeLocation::try_from_payload(&payload[6..12])?,
+        })
+    }
+}
+
 /// Standard ID
 /// ref:<https://toio.github.io/toio-spec/en/docs/ble_id#standard-id>
 
@@ -36,3 +46,13 @@ impl ToPayload<Vec<u8>> for StandardIdData {
         payload
     }
 }
+
+impl TryFromPayload<&[u8]> for StandardIdData {
+    fn try_from_payload(payload: &[u8]) -> Result<Self, DecodeError> {
+        check_length(payload, 6)?;
+        Ok(Self {
+            value: u32::from_le_bytes([payload[0], payload[1], payload[2], payload[3]]),
+            angle: u16::from_le_bytes([payload[4], payload[5]]),
+        })
+    }
+}
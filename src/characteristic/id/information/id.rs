@@ -1,8 +1,5 @@
 use super::super::def::id_type::{PositionIdData, StandardIdData};
-use crate::{
-    payload::FromPayload,
-    position::{CubeLocation, Point},
-};
+use crate::payload::{check_length, decode_message, DecodeError, ToPayload, TryFromPayload};
 
 /// Id information
 /// Position ID
@@ -24,66 +21,38 @@ impl From<IdInformation> for u8 {
         match id_information {
             IdInformation::PositionId(_) => 0x01u8,
             IdInformation::StandardId(_) => 0x02u8,
-            IdInformation::StandardIdMissed => 0x03u8,
-            IdInformation::PositionIdMissed => 0x04u8,
+            IdInformation::PositionIdMissed => 0x03u8,
+            IdInformation::StandardIdMissed => 0x04u8,
             IdInformation::Unknown(x) => x,
         }
     }
 }
 
-impl FromPayload<&[u8]> for IdInformation {
-    fn from_payload(payload: &[u8]) -> Option<Self>
-    where
-        Self: Sized,
-    {
-        if payload.is_empty() {
-            return None;
+impl ToPayload<Vec<u8>> for IdInformation {
+    fn to_payload(self) -> Vec<u8> {
+        let mut payload: Vec<u8> = vec![self.into()];
+        match self {
+            IdInformation::PositionId(position_id) => payload.extend(position_id.to_payload()),
+            IdInformation::StandardId(standard_id) => payload.extend(standard_id.to_payload()),
+            _ => (),
         }
+        payload
+    }
+}
+
+impl TryFromPayload<&[u8]> for IdInformation {
+    fn try_from_payload(payload: &[u8]) -> Result<Self, DecodeError> {
+        check_length(payload, 1)?;
         match payload[0] {
-            0x01u8 => {
-                if payload.len() >= 13 {
-                    Some(IdInformation::PositionId(PositionIdData {
-                        center: CubeLocation {
-                            point: Point {
-                                x: isize::from_le_bytes([payload[1], payload[2], 0, 0, 0, 0, 0, 0]),
-                                y: isize::from_le_bytes([payload[3], payload[4], 0, 0, 0, 0, 0, 0]),
-                            },
-                            angle: u16::from_le_bytes([payload[5], payload[6]]),
-                        },
-                        sensor: CubeLocation {
-                            point: Point {
-                                x: isize::from_le_bytes([payload[7], payload[8], 0, 0, 0, 0, 0, 0]),
-                                y: isize::from_le_bytes([
-                                    payload[9],
-                                    payload[10],
-                                    0,
-                                    0,
-                                    0,
-                                    0,
-                                    0,
-                                    0,
-                                ]),
-                            },
-                            angle: u16::from_le_bytes([payload[11], payload[12]]),
-                        },
-                    }))
-                } else {
-                    None
-                }
-            }
-            0x02u8 => {
-                if payload.len() >= 4 {
-                    Some(IdInformation::StandardId(StandardIdData {
-                        value: u32::from_le_bytes([payload[1], payload[2], payload[3], payload[4]]),
-                        angle: u16::from_le_bytes([payload[5], payload[6]]),
-                    }))
-                } else {
-                    None
-                }
-            }
-            0x03u8 => Some(IdInformation::PositionIdMissed),
-            0x04u8 => Some(IdInformation::StandardIdMissed),
-            _ => None,
+            0x01u8 => decode_message(payload, 0x01u8, 13, || {
+                PositionIdData::try_from_payload(&payload[1..]).map(IdInformation::PositionId)
+            }),
+            0x02u8 => decode_message(payload, 0x02u8, 7, || {
+                StandardIdData::try_from_payload(&payload[1..]).map(IdInformation::StandardId)
+            }),
+            0x03u8 => Ok(IdInformation::PositionIdMissed),
+            0x04u8 => Ok(IdInformation::StandardIdMissed),
+            opcode => Err(DecodeError::UnknownOpcode(opcode)),
         }
     }
 }
@@ -91,16 +60,35 @@ impl FromPayload<&[u8]> for IdInformation {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::payload::{assert_random_round_trip, assert_round_trip, FromPayload};
+    use crate::position::{CubeLocation, Point};
+    use rand::rngs::StdRng;
+    use rand::Rng;
 
     fn _setup() {
         let _ = env_logger::builder().is_test(true).try_init();
     }
 
+    fn random_id_information(rng: &mut StdRng) -> IdInformation {
+        match rng.gen_range(0..4) {
+            0 => IdInformation::PositionId(PositionIdData {
+                center: CubeLocation::random(rng),
+                sensor: CubeLocation::random(rng),
+            }),
+            1 => IdInformation::StandardId(StandardIdData {
+                value: rng.gen(),
+                angle: rng.gen(),
+            }),
+            2 => IdInformation::PositionIdMissed,
+            _ => IdInformation::StandardIdMissed,
+        }
+    }
+
     #[test]
     fn id_information1() {
         _setup();
 
-        let _res = IdInformation::PositionId(PositionIdData {
+        let res = IdInformation::PositionId(PositionIdData {
             center: CubeLocation {
                 point: Point { x: 1, y: 2 },
                 angle: 3,
@@ -110,6 +98,23 @@ mod test {
                 angle: 6,
             },
         });
+        assert_round_trip(res);
+        assert_random_round_trip(random_id_information);
+    }
+
+    #[test]
+    fn id_information2() {
+        _setup();
+
+        assert_eq!(u8::from(IdInformation::PositionIdMissed), 0x03u8);
+        assert_eq!(u8::from(IdInformation::StandardIdMissed), 0x04u8);
+        let payload = IdInformation::StandardId(StandardIdData {
+            value: 3670016,
+            angle: 90,
+        })
+        .to_payload();
+        assert_eq!(payload, vec![0x02, 0x00, 0x00, 0x38, 0x00, 0x5a, 0x00]);
+        assert_eq!(IdInformation::from_payload(&payload[..6]), None);
+        assert_eq!(IdInformation::from_payload(&[0x01u8; 12]), None);
     }
 }
- 
\ No newline at end of file
@@ -1,6 +1,6 @@
 //! Official Specification: <https://toio.github.io/toio-spec/en/docs/ble_light>
 
-use crate::payload::ToPayload;
+use crate::payload::{check_length, decode_message, DecodeError, ToPayload, TryFromPayload};
 
 use super::super::def::command_id::CommandId;
 use super::super::def::params::IndicatorParam;
@@ -9,7 +9,7 @@ use crate::integer_converter::u_to_u8;
 /// TurningOnAndOff
 /// ref:<https://toio.github.io/toio-spec/en/docs/ble_light#turning-the-indicator-on-and-off>
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct TurningOnAndOff {
     pub command: CommandId,
     pub param: IndicatorParam,
@@ -32,10 +32,21 @@ impl ToPayload<Vec<u8>> for TurningOnAndOff {
     }
 }
 
+impl TryFromPayload<&[u8]> for TurningOnAndOff {
+    fn try_from_payload(payload: &[u8]) -> Result<Self, DecodeError> {
+        decode_message(payload, u8::from(CommandId::TurningOnAndOff), 7, || {
+            Ok(Self {
+                command: CommandId::TurningOnAndOff,
+                param: IndicatorParam::try_from_payload(&payload[1..7])?,
+            })
+        })
+    }
+}
+
 /// RepeatedTuringOnAndOff
 /// ref:<https://toio.github.io/toio-spec/en/docs/ble_light#repeated-turning-on-and-off-of-indicator>
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct RepeatedTuringOnAndOff {
     pub command: CommandId,
     pub repeat: u8,
@@ -63,10 +74,33 @@ impl ToPayload<Vec<u8>> for RepeatedTuringOnAndOff {
     }
 }
 
+impl TryFromPayload<&[u8]> for RepeatedTuringOnAndOff {
+    fn try_from_payload(payload: &[u8]) -> Result<Self, DecodeError> {
+        decode_message(
+            payload,
+            u8::from(CommandId::RepeatedTuringOnAndOff),
+            3,
+            || {
+                // header (3 bytes) and the parameters (6 bytes each)
+                let length = 3 + usize::from(payload[2]) * 6;
+                check_length(payload, length)?;
+                Ok(Self {
+                    command: CommandId::RepeatedTuringOnAndOff,
+                    repeat: payload[1],
+                    param_list: payload[3..length]
+                        .chunks(6)
+                        .map(IndicatorParam::try_from_payload)
+                        .collect::<Result<Vec<IndicatorParam>, DecodeError>>()?,
+                })
+            },
+        )
+    }
+}
+
 /// TurnOffAll
 /// ref:<https://toio.github.io/toio-spec/en/docs/ble_light#turn-off-all-indicators>
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct TurnOffAll {
     pub command: CommandId,
 }
@@ -86,10 +120,20 @@ impl ToPayload<Vec<u8>> for TurnOffAll {
     }
 }
 
+impl TryFromPayload<&[u8]> for TurnOffAll {
+    fn try_from_payload(payload: &[u8]) -> Result<Self, DecodeError> {
+        decode_message(payload, u8::from(CommandId::TurnOffAll), 1, || {
+            Ok(Self {
+                command: CommandId::TurnOffAll,
+            })
+        })
+    }
+}
+
 /// TurnOff
 /// ref:<https://toio.github.io/toio-spec/en/docs/ble_light#turn-off-a-specific-indicator>
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct TurnOff {
     pub command: CommandId,
     pub id: u8,
@@ -110,3 +154,73 @@ impl ToPayload<Vec<u8>> for TurnOff {
         payload
     }
 }
+
+impl TryFromPayload<&[u8]> for TurnOff {
+    fn try_from_payload(payload: &[u8]) -> Result<Self, DecodeError> {
+        decode_message(payload, u8::from(CommandId::TurnOff), 2, || {
+            Ok(Self {
+                command: CommandId::TurnOff,
+                id: payload[1],
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::characteristic::indicator::def::params::{Color, Period};
+    use crate::payload::{assert_random_round_trip, assert_round_trip};
+    use rand::rngs::StdRng;
+    use rand::Rng;
+
+    fn _setup() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    fn random_indicator_param(rng: &mut StdRng) -> IndicatorParam {
+        IndicatorParam {
+            duration: Period::from(rng.gen::<u8>()),
+            color: Color {
+                r: rng.gen(),
+                g: rng.gen(),
+                b: rng.gen(),
+            },
+        }
+    }
+
+    fn random_turning_on_and_off(rng: &mut StdRng) -> TurningOnAndOff {
+        TurningOnAndOff {
+            param: random_indicator_param(rng),
+            ..TurningOnAndOff::default()
+        }
+    }
+
+    fn random_repeated_turning_on_and_off(rng: &mut StdRng) -> RepeatedTuringOnAndOff {
+        RepeatedTuringOnAndOff {
+            repeat: rng.gen(),
+            param_list: (0..rng.gen_range(1..=29))
+                .map(|_| random_indicator_param(rng))
+                .collect(),
+            ..RepeatedTuringOnAndOff::default()
+        }
+    }
+
+    fn random_turn_off(rng: &mut StdRng) -> TurnOff {
+        TurnOff {
+            id: rng.gen(),
+            ..TurnOff::default()
+        }
+    }
+
+    #[test]
+    fn indicator_command1() {
+        _setup();
+
+        assert_round_trip(TurningOnAndOff::default());
+        assert_random_round_trip(random_turning_on_and_off);
+        assert_random_round_trip(random_repeated_turning_on_and_off);
+        assert_random_round_trip(random_turn_off);
+        assert_round_trip(TurnOffAll::default());
+    }
+}
@@ -1,6 +1,6 @@
 /// Indicator color
 pub use crate::characteristic::motor::def::params::Period;
-use crate::payload::ToPayload;
+use crate::payload::{check_length, DecodeError, ToPayload, TryFromPayload};
 
 #[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
 pub struct Color {
@@ -38,7 +38,7 @@ impl ToPayload<Vec<u8>> for Color {
 
 /// Indicator parameter
 
-#[derive(Default, Debug, Copy, Clone)]
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
 pub struct IndicatorParam {
     pub duration: Period,
     pub color: Color,
@@ -54,3 +54,14 @@ impl ToPayload<Vec<u8>> for IndicatorParam {
         payload
     }
 }
+
+impl TryFromPayload<&[u8]> for IndicatorParam {
+    fn try_from_payload(payload: &[u8]) -> Result<Self, DecodeError> {
+        // (duration, number of indicators, indicator id, r, g, b)
+        check_length(payload, 6)?;
+        Ok(Self {
+            duration: Period::from(payload[0]),
+            color: Color::from(&payload[3..6]),
+        })
+    }
+}
@@ -2,11 +2,11 @@ use crate::characteristic::motor::def::acceleration::{
     Acceleration, AngleVelocity, MovingDirection, Priority,
 };
 use crate::characteristic::motor::def::{CommandId, Period};
-use crate::payload::ToPayload;
+use crate::payload::{decode_field, decode_message, DecodeError, ToPayload, TryFromPayload};
 
 /// Byte-string representation of <https://toio.github.io/toio-spec/en/docs/ble_motor/#motor-control-with-acceleration-specified>
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct MotorControlAcceleration {
     pub command: CommandId,
     pub acceleration: Acceleration,
@@ -42,17 +42,52 @@ impl ToPayload<Vec<u8>> for MotorControlAcceleration {
     }
 }
 
+impl TryFromPayload<&[u8]> for MotorControlAcceleration {
+    fn try_from_payload(payload: &[u8]) -> Result<Self, DecodeError> {
+        decode_message(payload, u8::from(CommandId::Acceleration), 9, || {
+            Ok(Self {
+                command: CommandId::Acceleration,
+                acceleration: Acceleration::try_from_payload(&payload[1..3])?,
+                angle_velocity: AngleVelocity::try_from_payload(&payload[3..6])?,
+                moving_direction: decode_field("moving direction", payload[6])?,
+                priority: decode_field("priority", payload[7])?,
+                period: Period::from(payload[8]),
+            })
+        })
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
     use crate::characteristic::motor::def::acceleration::{
         Acceleration, AngleVelocity, MovingDirection, Priority, RotationDirection,
     };
+    use crate::payload::{assert_random_round_trip, assert_round_trip};
+    use rand::rngs::StdRng;
+    use rand::Rng;
 
     fn _setup() {
         let _ = env_logger::builder().is_test(true).try_init();
     }
 
+    fn random_motor_control_acceleration(rng: &mut StdRng) -> MotorControlAcceleration {
+        MotorControlAcceleration {
+            acceleration: Acceleration {
+                translational_speed: rng.gen(),
+                acceleration: rng.gen(),
+            },
+            angle_velocity: AngleVelocity {
+                rotation_velocity: rng.gen(),
+                rotation_direction: RotationDirection::try_from(rng.gen_range(0..2)).unwrap(),
+            },
+            moving_direction: MovingDirection::try_from(rng.gen_range(0..2)).unwrap(),
+            priority: Priority::try_from(rng.gen_range(0..2)).unwrap(),
+            period: Period::from(rng.gen::<u8>()),
+            ..MotorControlAcceleration::default()
+        }
+    }
+
     #[test]
     fn motor_acceleration1() {
         _setup();
@@ -61,6 +96,7 @@ mod test {
         let payload = st.to_payload();
         println!("len: {:2} payload:{:?}", payload.len(), payload);
         assert_eq!(payload.len(), 9);
+        assert_round_trip(st);
 
         let st = MotorControlAcceleration {
             acceleration: Acceleration {
@@ -79,5 +115,7 @@ mod test {
         let payload = st.to_payload();
         println!("len: {:2} payload:{:?}", payload.len(), payload);
         assert_eq!(payload.len(), 9);
+        assert_round_trip(st);
+        assert_random_round_trip(random_motor_control_acceleration);
     }
 }
@@ -1,13 +1,13 @@
 use crate::characteristic::motor::def::{
     CommandId, MotorDriveParameter, MotorId, Period, Velocity,
 };
-use crate::payload::ToPayload;
-use std::error::Error;
+use crate::payload::{decode_message, DecodeError, ToPayload, TryFromPayload};
+use crate::CoreCubeError;
 
 /// Motor control
 /// <https://toio.github.io/toio-spec/en/docs/ble_motor/#motor-control>
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct MotorControl {
     pub command: CommandId,
     pub left: MotorDriveParameter,
@@ -26,10 +26,7 @@ impl Default for MotorControl {
 
 impl MotorControl {
     /// create new struct from primitive type parameters
-    pub fn set_value(
-        left: i16,
-        right: i16,
-    ) -> Result<Self, Box<dyn Error + Send + Sync + 'static>> {
+    pub fn set_value(left: i16, right: i16) -> Result<Self, CoreCubeError> {
         let left = MotorDriveParameter::new(MotorId::Left, Velocity::set_value(left)?)?;
         let right = MotorDriveParameter::new(MotorId::Right, Velocity::set_value(right)?)?;
         Ok(Self {
@@ -51,10 +48,22 @@ impl ToPayload<Vec<u8>> for MotorControl {
     }
 }
 
+impl TryFromPayload<&[u8]> for MotorControl {
+    fn try_from_payload(payload: &[u8]) -> Result<Self, DecodeError> {
+        decode_message(payload, u8::from(CommandId::Run), 7, || {
+            Ok(Self {
+                command: CommandId::Run,
+                left: MotorDriveParameter::try_from_payload(&payload[1..4])?,
+                right: MotorDriveParameter::try_from_payload(&payload[4..7])?,
+            })
+        })
+    }
+}
+
 /// Motor control with specified duration
 /// <https://toio.github.io/toio-spec/en/docs/ble_motor/#motor-control-with-specified-duration>
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct MotorControlWithSpecifiedDuration {
     pub command: CommandId,
     pub left: MotorDriveParameter,
@@ -75,11 +84,7 @@ impl Default for MotorControlWithSpecifiedDuration {
 
 impl MotorControlWithSpecifiedDuration {
     /// create new struct from primitive type parameters
-    pub fn set_value(
-        left: i16,
-        right: i16,
-        period_ms: usize,
-    ) -> Result<Self, Box<dyn Error + Send + Sync + 'static>> {
+    pub fn set_value(left: i16, right: i16, period_ms: usize) -> Result<Self, CoreCubeError> {
         let left = MotorDriveParameter::new(MotorId::Left, Velocity::set_value(left)?)?;
         let right = MotorDriveParameter::new(MotorId::Right, Velocity::set_value(right)?)?;
         let period = Period::from_millis(period_ms);
@@ -103,15 +108,60 @@ impl ToPayload<Vec<u8>> for MotorControlWithSpecifiedDuration {
     }
 }
 
+impl TryFromPayload<&[u8]> for MotorControlWithSpecifiedDuration {
+    fn try_from_payload(payload: &[u8]) -> Result<Self, DecodeError> {
+        decode_message(payload, u8::from(CommandId::Period), 8, || {
+            Ok(Self {
+                command: CommandId::Period,
+                left: MotorDriveParameter::try_from_payload(&payload[1..4])?,
+                right: MotorDriveParameter::try_from_payload(&payload[4..7])?,
+                period: Period::from(payload[7]),
+            })
+        })
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::characteristic::motor::def::{MotorId, Velocity};
+    use crate::characteristic::motor::def::{MotorDirection, MotorId, Velocity};
+    use crate::payload::{assert_random_round_trip, assert_round_trip};
+    use rand::rngs::StdRng;
+    use rand::Rng;
 
     fn _setup() {
         let _ = env_logger::builder().is_test(true).try_init();
     }
 
+    fn random_motor_drive(rng: &mut StdRng, id: MotorId) -> MotorDriveParameter {
+        MotorDriveParameter {
+            id,
+            velocity: Velocity {
+                direction: MotorDirection::try_from(rng.gen_range(1..=2)).unwrap(),
+                speed: rng.gen(),
+            },
+        }
+    }
+
+    fn random_motor_control(rng: &mut StdRng) -> MotorControl {
+        MotorControl {
+            left: random_motor_drive(rng, MotorId::Left),
+            right: random_motor_drive(rng, MotorId::Right),
+            ..MotorControl::default()
+        }
+    }
+
+    fn random_motor_control_with_specified_duration(
+        rng: &mut StdRng,
+    ) -> MotorControlWithSpecifiedDuration {
+        MotorControlWithSpecifiedDuration {
+            left: random_motor_drive(rng, MotorId::Left),
+            right: random_motor_drive(rng, MotorId::Right),
+            period: Period::from(rng.gen::<u8>()),
+            ..MotorControlWithSpecifiedDuration::default()
+        }
+    }
+
     #[test]
     fn motor_control_test1() {
         _setup();
@@ -120,6 +170,8 @@ mod test {
         let payload = run_default.to_payload();
         println!("length: {:2} payload: {:?}", payload.len(), payload);
         assert_eq!(payload.len(), 7);
+        assert_round_trip(run_default);
+        assert_random_round_trip(random_motor_control);
     }
 
     #[test]
@@ -130,6 +182,8 @@ mod test {
         let payload = run_default.to_payload();
         println!("length: {:2} payload: {:?}", payload.len(), payload);
         assert_eq!(payload.len(), 8);
+        assert_round_trip(run_default);
+        assert_random_round_trip(random_motor_control_with_specified_duration);
     }
 
     #[test]
@@ -140,6 +194,7 @@ mod test {
         let payload = run_default.to_payload();
         println!("length: {:2} payload: {:?}", payload.len(), payload);
         assert_eq!(payload.len(), 7);
+        assert_round_trip(run_default);
     }
 
     #[test]
@@ -156,5 +211,6 @@ mod test {
         let payload = run_default.to_payload();
         println!("length: {:2} payload: {:?}", payload.len(), payload);
         assert_eq!(payload.len(), 7);
+        assert_round_trip(run_default);
     }
 }
@@ -2,11 +2,13 @@ use crate::characteristic::motor::def::target_def::{
     MovementType, Speed, TargetPosition, WriteMode,
 };
 use crate::characteristic::motor::def::{CommandId, RequestId, Timeout};
-use crate::payload::ToPayload;
+use crate::payload::{
+    check_length, decode_field, decode_message, DecodeError, ToPayload, TryFromPayload,
+};
 
 /// Byte-string representation of <https://toio.github.io/toio-spec/en/docs/ble_motor/#motor-control-with-target-specified>
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct MotorControlTarget {
     pub command: CommandId,
     pub id: RequestId,
@@ -17,11 +19,14 @@ pub struct MotorControlTarget {
     pub target: TargetPosition,
 }
 
-impl Default for MotorControlTarget {
-    fn default() -> Self {
+impl MotorControlTarget {
+    /// command with the default parameters (`id` should be allocated by [`RequestIdAllocator`])
+    ///
+    /// [`RequestIdAllocator`]: crate::characteristic::motor::RequestIdAllocator
+    pub fn new(id: RequestId) -> Self {
         Self {
             command: CommandId::TargetPosition,
-            id: RequestId::new(),
+            id,
             timeout: Timeout::default(),
             movement_type: MovementType::default(),
             speed: Speed::default(),
@@ -45,9 +50,25 @@ impl ToPayload<Vec<u8>> for MotorControlTarget {
     }
 }
 
+impl TryFromPayload<&[u8]> for MotorControlTarget {
+    fn try_from_payload(payload: &[u8]) -> Result<Self, DecodeError> {
+        decode_message(payload, u8::from(CommandId::TargetPosition), 13, || {
+            Ok(Self {
+                command: CommandId::TargetPosition,
+                id: RequestId::received(payload[1]),
+                timeout: Timeout::from(payload[2]),
+                movement_type: decode_field("movement type", payload[3])?,
+                speed: Speed::try_from_payload(&payload[4..6])?,
+                _reserved_1: payload[6],
+                target: TargetPosition::try_from_payload(&payload[7..13])?,
+            })
+        })
+    }
+}
+
 /// Byte-string representation of <https://toio.github.io/toio-spec/en/docs/ble_motor/#motor-control-with-multiple-targets-specified>
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct MotorControlMultipleTargets {
     pub command: CommandId,
     pub id: RequestId,
@@ -59,11 +80,14 @@ pub struct MotorControlMultipleTargets {
     pub target_list: Vec<TargetPosition>,
 }
 
-impl Default for MotorControlMultipleTargets {
-    fn default() -> Self {
+impl MotorControlMultipleTargets {
+    /// command with the default parameters (`id` should be allocated by [`RequestIdAllocator`])
+    ///
+    /// [`RequestIdAllocator`]: crate::characteristic::motor::RequestIdAllocator
+    pub fn new(id: RequestId) -> Self {
         Self {
             command: CommandId::MultiTargetPositions,
-            id: RequestId::new(),
+            id,
             timeout: Timeout::default(),
             movement_type: MovementType::default(),
             speed: Speed::default(),
@@ -91,25 +115,103 @@ impl ToPayload<Vec<u8>> for MotorControlMultipleTargets {
     }
 }
 
+impl TryFromPayload<&[u8]> for MotorControlMultipleTargets {
+    fn try_from_payload(payload: &[u8]) -> Result<Self, DecodeError> {
+        decode_message(
+            payload,
+            u8::from(CommandId::MultiTargetPositions),
+            14,
+            || {
+                // header (8 bytes) and one or more targets (6 bytes each)
+                let partial = (payload.len() - 8) % 6;
+                if partial != 0 {
+                    check_length(payload, payload.len() + 6 - partial)?;
+                }
+                Ok(Self {
+                    command: CommandId::MultiTargetPositions,
+                    id: RequestId::received(payload[1]),
+                    timeout: Timeout::from(payload[2]),
+                    movement_type: decode_field("movement type", payload[3])?,
+                    speed: Speed::try_from_payload(&payload[4..6])?,
+                    _reserved_1: payload[6],
+                    write_mode: decode_field("write mode", payload[7])?,
+                    target_list: payload[8..]
+                        .chunks(6)
+                        .map(TargetPosition::try_from_payload)
+                        .collect::<Result<Vec<TargetPosition>, DecodeError>>()?,
+                })
+            },
+        )
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
     use crate::characteristic::motor::def::target_def::{
-        MovementType, Speed, SpeedChangeType, TargetPosition, WriteMode,
+        MovementType, RotationOption, Speed, SpeedChangeType, TargetPosition, WriteMode,
     };
+    use crate::payload::{assert_random_round_trip, assert_round_trip};
+    use crate::position::CubeLocation;
+    use rand::rngs::StdRng;
+    use rand::Rng;
 
     fn _setup() {
         let _ = env_logger::builder().is_test(true).try_init();
     }
 
+    fn random_speed(rng: &mut StdRng) -> Speed {
+        Speed {
+            max: rng.gen(),
+            speed_change_type: SpeedChangeType::try_from(rng.gen_range(0..4)).unwrap(),
+        }
+    }
+
+    fn random_target_position(rng: &mut StdRng) -> TargetPosition {
+        TargetPosition {
+            cube_location: CubeLocation {
+                // the upper 3 bits of the angle are the type of the angle
+                angle: rng.gen_range(0..0x2000),
+                ..CubeLocation::random(rng)
+            },
+            rotation_option: RotationOption::try_from(rng.gen_range(0..7)).unwrap(),
+        }
+    }
+
+    fn random_motor_control_target(rng: &mut StdRng) -> MotorControlTarget {
+        MotorControlTarget {
+            timeout: Timeout::from(rng.gen::<u8>()),
+            movement_type: MovementType::try_from(rng.gen_range(0..3)).unwrap(),
+            speed: random_speed(rng),
+            _reserved_1: rng.gen(),
+            target: random_target_position(rng),
+            ..MotorControlTarget::new(RequestId::received(rng.gen()))
+        }
+    }
+
+    fn random_motor_control_multiple_targets(rng: &mut StdRng) -> MotorControlMultipleTargets {
+        MotorControlMultipleTargets {
+            timeout: Timeout::from(rng.gen::<u8>()),
+            movement_type: MovementType::try_from(rng.gen_range(0..3)).unwrap(),
+            speed: random_speed(rng),
+            _reserved_1: rng.gen(),
+            write_mode: WriteMode::try_from(rng.gen_range(0..2)).unwrap(),
+            target_list: (0..rng.gen_range(1..=29))
+                .map(|_| random_target_position(rng))
+                .collect(),
+            ..MotorControlMultipleTargets::new(RequestId::received(rng.gen()))
+        }
+    }
+
     #[test]
     fn motor_target1() {
         _setup();
 
-        let st = MotorControlTarget::default();
+        let st = MotorControlTarget::new(RequestId::received(0));
         let payload = st.to_payload();
         println!("len: {:2} payload:{:?}", payload.len(), payload);
         assert_eq!(payload.len(), 13);
+        assert_round_trip(st);
 
         let st = MotorControlTarget {
             timeout: Timeout::Second(10),
@@ -119,21 +221,24 @@ mod test {
                 speed_change_type: SpeedChangeType::Acceleration,
             },
             _reserved_1: 0xff,
-            ..MotorControlTarget::default()
+            ..MotorControlTarget::new(RequestId::received(0))
         };
         let payload = st.to_payload();
         println!("len: {:2} payload:{:?}", payload.len(), payload);
         assert_eq!(payload.len(), 13);
+        assert_round_trip(st);
+        assert_random_round_trip(random_motor_control_target);
     }
 
     #[test]
     fn motor_target2() {
         _setup();
 
-        let st = MotorControlMultipleTargets::default();
-        let payload = st.to_payload();
+        let st = MotorControlMultipleTargets::new(RequestId::received(0));
+        let payload = st.clone().to_payload();
         println!("len: {:2} payload:{:?}", payload.len(), payload);
         assert_eq!(payload.len(), 14);
+        assert_round_trip(st);
 
         let st = MotorControlMultipleTargets {
             timeout: Timeout::default(),
@@ -148,9 +253,12 @@ mod test {
                 TargetPosition::default(),
                 TargetPosition::default(),
             ],
-            ..MotorControlMultipleTargets::default()
+            ..MotorControlMultipleTargets::new(RequestId::received(0))
         };
-        let payload = st.to_payload();
+        let payload = st.clone().to_payload();
         println!("len: {:2} payload:{:?}", payload.len(), payload);
+        assert_eq!(payload.len(), 14 + 2 * 6);
+        assert_round_trip(st);
+        assert_random_round_trip(random_motor_control_multiple_targets);
     }
 }
@@ -1,15 +1,18 @@
 pub(crate) mod acceleration;
 pub(crate) mod command_id;
 pub(crate) mod params;
+pub(crate) mod request_id;
 pub(crate) mod target_def;
 
 pub use self::command_id::CommandId;
 
 pub use self::params::{
-    MotorDirection, MotorDriveParameter, MotorError, MotorId, Period, RequestId, ResponseCode,
-    Timeout, Velocity,
+    MotorDirection, MotorDriveParameter, MotorError, MotorId, Period, ResponseCode, Timeout,
+    Velocity,
 };
 
+pub use self::request_id::{InFlightRequestId, RequestId, RequestIdAllocator};
+
 pub use self::target_def::{
     MovementType, RotationOption, Speed, SpeedChangeType, TargetPosition, WriteMode,
 };
@@ -1,4 +1,4 @@
-use crate::payload::ToPayload;
+use crate::payload::{check_length, decode_field, DecodeError, ToPayload, TryFromPayload};
 
 /// Acceleration
 
@@ -15,6 +15,16 @@ impl ToPayload<Vec<u8>> for Acceleration {
     }
 }
 
+impl TryFromPayload<&[u8]> for Acceleration {
+    fn try_from_payload(payload: &[u8]) -> Result<Self, DecodeError> {
+        check_length(payload, 2)?;
+        Ok(Self {
+            translational_speed: payload[0],
+            acceleration: payload[1],
+        })
+    }
+}
+
 /// Angle velocity
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -41,6 +51,16 @@ impl ToPayload<Vec<u8>> for AngleVelocity {
     }
 }
 
+impl TryFromPayload<&[u8]> for AngleVelocity {
+    fn try_from_payload(payload: &[u8]) -> Result<Self, DecodeError> {
+        check_length(payload, 3)?;
+        Ok(Self {
+            rotation_velocity: u16::from_le_bytes([payload[0], payload[1]]),
+            rotation_direction: decode_field("rotation direction", payload[2])?,
+        })
+    }
+}
+
 /// Rotation direction
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -58,6 +78,18 @@ impl From<RotationDirection> for u8 {
     }
 }
 
+impl TryFrom<u8> for RotationDirection {
+    type Error = ();
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0u8 => Ok(RotationDirection::Positive),
+            1u8 => Ok(RotationDirection::Negative),
+            _ => Err(()),
+        }
+    }
+}
+
 impl ToPayload<Vec<u8>> for RotationDirection {
     fn to_payload(self) -> Vec<u8> {
         let payload: Vec<u8> = vec![self.into()];
@@ -82,6 +114,18 @@ impl From<MovingDirection> for u8 {
     }
 }
 
+impl TryFrom<u8> for MovingDirection {
+    type Error = ();
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0u8 => Ok(MovingDirection::Forward),
+            1u8 => Ok(MovingDirection::Backward),
+            _ => Err(()),
+        }
+    }
+}
+
 impl ToPayload<Vec<u8>> for MovingDirection {
     fn to_payload(self) -> Vec<u8> {
         let payload: Vec<u8> = vec![self.into()];
@@ -106,6 +150,18 @@ impl From<Priority> for u8 {
     }
 }
 
+impl TryFrom<u8> for Priority {
+    type Error = ();
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0u8 => Ok(Priority::TranslationalSpeed),
+            1u8 => Ok(Priority::RotationVelocity),
+            _ => Err(()),
+        }
+    }
+}
+
 impl ToPayload<Vec<u8>> for Priority {
     fn to_payload(self) -> Vec<u8> {
         let payload: Vec<u8> = vec![self.into()];
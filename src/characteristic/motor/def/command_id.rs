@@ -2,7 +2,7 @@ use crate::payload::ToPayload;
 
 /// Command
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum CommandId {
     Run,
     Period,
@@ -1,7 +1,5 @@
-use crate::payload::ToPayload;
-use once_cell::sync::OnceCell;
-use std::error::Error;
-use std::sync::Mutex;
+use crate::payload::{check_length, decode_field, DecodeError, ToPayload, TryFromPayload};
+use crate::CoreCubeError;
 use thiserror::Error;
 
 /// Response code from cube
@@ -43,11 +41,11 @@ impl From<u8> for ResponseCode {
             0 => ResponseCode::Success,
             1 => ResponseCode::ErrorTimeout,
             2 => ResponseCode::ErrorIdMissed,
-            4 => ResponseCode::ErrorInvalidParameter,
-            5 => ResponseCode::ErrorInvalidCubeState,
-            6 => ResponseCode::SuccessWithOverwrite,
-            7 => ResponseCode::ErrorNotSupported,
-            8 => ResponseCode::ErrorFailToAppend,
+            3 => ResponseCode::ErrorInvalidParameter,
+            4 => ResponseCode::ErrorInvalidCubeState,
+            5 => ResponseCode::SuccessWithOverwrite,
+            6 => ResponseCode::ErrorNotSupported,
+            7 => ResponseCode::ErrorFailToAppend,
             x => ResponseCode::UnknownError(x),
         }
     }
@@ -72,59 +70,6 @@ pub enum MotorError {
     FoundBug,
 }
 
-/// Request ID
-///
-/// No default.
-
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
-pub struct RequestId {
-    pub id: u8,
-}
-
-impl From<RequestId> for u8 {
-    fn from(value: RequestId) -> Self {
-        value.id
-    }
-}
-
-impl ToPayload<Vec<u8>> for RequestId {
-    fn to_payload(self) -> Vec<u8> {
-        let payload: Vec<u8> = vec![self.into()];
-        payload
-    }
-}
-
-/// Request ID counter (global scope)
-static REQUEST_ID: OnceCell<Mutex<u8>> = OnceCell::new();
-
-impl Default for RequestId {
-    fn default() -> Self {
-        let mut request_id = REQUEST_ID.get_or_init(|| Mutex::new(0u8)).lock().unwrap();
-        let id = *request_id;
-        if *request_id == u8::MAX {
-            *request_id = 0;
-        } else {
-            *request_id += 1;
-        }
-        Self { id }
-    }
-}
-
-impl RequestId {
-    pub fn new() -> Self {
-        Self::default()
-    }
-
-    pub fn received(id: u8) -> Self {
-        Self { id }
-    }
-
-    pub fn reset() {
-        let mut request_id = REQUEST_ID.get_or_init(|| Mutex::new(0u8)).lock().unwrap();
-        *request_id = 0;
-    }
-}
-
 /// Timeout
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -140,6 +85,12 @@ impl From<Timeout> for u8 {
     }
 }
 
+impl From<u8> for Timeout {
+    fn from(second: u8) -> Timeout {
+        Timeout::Second(second)
+    }
+}
+
 impl Default for Timeout {
     fn default() -> Self {
         Timeout::Second(0)
@@ -155,7 +106,7 @@ impl ToPayload<Vec<u8>> for Timeout {
 
 /// Period for running
 
-#[derive(Default, Debug, Copy, Clone)]
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
 pub struct Period {
     pub period: u8,
 }
@@ -166,6 +117,12 @@ impl From<Period> for u8 {
     }
 }
 
+impl From<u8> for Period {
+    fn from(period: u8) -> Period {
+        Period { period }
+    }
+}
+
 impl Period {
     pub fn forever() -> Self {
         Self { period: 0 }
@@ -208,6 +165,18 @@ impl From<MotorId> for u8 {
     }
 }
 
+impl TryFrom<u8> for MotorId {
+    type Error = ();
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            1u8 => Ok(MotorId::Left),
+            2u8 => Ok(MotorId::Right),
+            _ => Err(()),
+        }
+    }
+}
+
 impl ToPayload<Vec<u8>> for MotorId {
     fn to_payload(self) -> Vec<u8> {
         let payload: Vec<u8> = vec![self.into()];
@@ -234,6 +203,18 @@ impl From<MotorDirection> for u8 {
     }
 }
 
+impl TryFrom<u8> for MotorDirection {
+    type Error = ();
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            1u8 => Ok(MotorDirection::Forward),
+            2u8 => Ok(MotorDirection::Backward),
+            _ => Err(()),
+        }
+    }
+}
+
 impl ToPayload<Vec<u8>> for MotorDirection {
     fn to_payload(self) -> Vec<u8> {
         let payload: Vec<u8> = vec![self.into()];
@@ -243,7 +224,7 @@ impl ToPayload<Vec<u8>> for MotorDirection {
 
 /// Cube speed
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct Velocity {
     pub direction: MotorDirection,
     pub speed: u8,
@@ -259,12 +240,12 @@ impl Default for Velocity {
 }
 
 impl Velocity {
-    pub fn set_value(value: i16) -> Result<Self, Box<dyn Error + Send + Sync + 'static>> {
+    pub fn set_value(value: i16) -> Result<Self, CoreCubeError> {
         if value > 0 && value > u8::MAX.into() {
-            return Err(Box::new(MotorError::InvalidParameter));
+            return Err(MotorError::InvalidParameter.into());
         }
         if 0 > value && -value > u8::MAX.into() {
-            return Err(Box::new(MotorError::InvalidParameter));
+            return Err(MotorError::InvalidParameter.into());
         }
         let direction = if value >= 0 {
             MotorDirection::Forward
@@ -280,6 +261,16 @@ impl Velocity {
     }
 }
 
+impl Velocity {
+    /// signed speed (negative: backward)
+    pub fn value(&self) -> i16 {
+        match self.direction {
+            MotorDirection::Forward => self.speed.into(),
+            MotorDirection::Backward => -i16::from(self.speed),
+        }
+    }
+}
+
 impl ToPayload<Vec<u8>> for Velocity {
     fn to_payload(self) -> Vec<u8> {
         let mut payload: Vec<u8> = Vec::new();
@@ -291,7 +282,7 @@ impl ToPayload<Vec<u8>> for Velocity {
 
 /// Motor drive parameter
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct MotorDriveParameter {
     pub id: MotorId,
     pub velocity: Velocity,
@@ -307,10 +298,7 @@ impl Default for MotorDriveParameter {
 }
 
 impl MotorDriveParameter {
-    pub fn new(
-        id: MotorId,
-        velocity: Velocity,
-    ) -> Result<Self, Box<dyn Error + Send + Sync + 'static>> {
+    pub fn new(id: MotorId, velocity: Velocity) -> Result<Self, CoreCubeError> {
         Ok(Self { id, velocity })
     }
 }
@@ -324,6 +312,19 @@ impl ToPayload<Vec<u8>> for MotorDriveParameter {
     }
 }
 
+impl TryFromPayload<&[u8]> for MotorDriveParameter {
+    fn try_from_payload(payload: &[u8]) -> Result<Self, DecodeError> {
+        check_length(payload, 3)?;
+        Ok(Self {
+            id: decode_field("motor id", payload[0])?,
+            velocity: Velocity {
+                direction: decode_field("motor direction", payload[1])?,
+                speed: payload[2],
+            },
+        })
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -333,14 +334,28 @@ mod test {
     }
 
     #[test]
-    fn motor_def_request_id() {
+    fn motor_def_response_code() {
         _setup();
 
-        RequestId::reset();
-        for ct in 0usize..=300usize {
-            let req = RequestId::new();
-            log::info!("{} {}", ct, req.id);
-            assert_eq!(req.id as usize, ct % (1 + u8::MAX as usize));
+        for code in 0u8..=u8::MAX {
+            assert_eq!(u8::from(ResponseCode::from(code)), code);
         }
+        assert_eq!(ResponseCode::from(5u8), ResponseCode::SuccessWithOverwrite);
+        assert_eq!(ResponseCode::from(8u8), ResponseCode::UnknownError(8));
+    }
+
+    #[test]
+    fn motor_def_velocity() {
+        _setup();
+
+        assert_eq!(Velocity::set_value(-255).unwrap().speed, 255);
+        assert!(matches!(
+            Velocity::set_value(256),
+            Err(CoreCubeError::Motor(MotorError::InvalidParameter))
+        ));
+        assert!(matches!(
+            Velocity::set_value(-256),
+            Err(CoreCubeError::Motor(MotorError::InvalidParameter))
+        ));
     }
 }
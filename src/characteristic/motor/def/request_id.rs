@@ -0,0 +1,162 @@
+use crate::payload::ToPayload;
+use std::sync::{Arc, Mutex};
+
+/// Request ID
+///
+/// Use [`RequestIdAllocator`] of the cube to get an ID which is not in flight.
+/// There is no default ID, so a command is never sent with an ID the allocator does not know.
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct RequestId {
+    pub id: u8,
+}
+
+impl From<RequestId> for u8 {
+    fn from(value: RequestId) -> Self {
+        value.id
+    }
+}
+
+impl ToPayload<Vec<u8>> for RequestId {
+    fn to_payload(self) -> Vec<u8> {
+        let payload: Vec<u8> = vec![self.into()];
+        payload
+    }
+}
+
+impl RequestId {
+    pub fn received(id: u8) -> Self {
+        Self { id }
+    }
+}
+
+struct RequestIdState {
+    next: u8,
+    in_flight: [bool; 256],
+}
+
+/// Request ID sequence of a cube
+///
+/// Clones share the same sequence. IDs are allocated in order and wrap around after 255,
+/// skipping the IDs which are still in flight.
+
+#[derive(Clone)]
+pub struct RequestIdAllocator {
+    state: Arc<Mutex<RequestIdState>>,
+}
+
+impl Default for RequestIdAllocator {
+    fn default() -> Self {
+        Self {
+            state: Arc::new(Mutex::new(RequestIdState {
+                next: 0,
+                in_flight: [false; 256],
+            })),
+        }
+    }
+}
+
+impl RequestIdAllocator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// allocate an ID (None: all IDs are in flight)
+    pub fn allocate(&self) -> Option<InFlightRequestId> {
+        let mut state = self.state.lock().unwrap();
+        let start = state.next;
+        let id = (0..=u8::MAX)
+            .map(|offset| start.wrapping_add(offset))
+            .find(|id| !state.in_flight[*id as usize])?;
+        state.in_flight[id as usize] = true;
+        state.next = id.wrapping_add(1);
+        Some(InFlightRequestId {
+            request_id: RequestId { id },
+            allocator: self.clone(),
+        })
+    }
+
+    pub fn is_in_flight(&self, request_id: RequestId) -> bool {
+        self.state.lock().unwrap().in_flight[request_id.id as usize]
+    }
+
+    pub fn in_flight_count(&self) -> usize {
+        self.state
+            .lock()
+            .unwrap()
+            .in_flight
+            .iter()
+            .filter(|in_flight| **in_flight)
+            .count()
+    }
+
+    fn release(&self, request_id: RequestId) {
+        self.state.lock().unwrap().in_flight[request_id.id as usize] = false;
+    }
+}
+
+/// Request ID waiting for the response
+///
+/// The ID is released when this is dropped.
+pub struct InFlightRequestId {
+    request_id: RequestId,
+    allocator: RequestIdAllocator,
+}
+
+impl InFlightRequestId {
+    pub fn request_id(&self) -> RequestId {
+        self.request_id
+    }
+}
+
+impl Drop for InFlightRequestId {
+    fn drop(&mut self) {
+        self.allocator.release(self.request_id);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn _setup() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    #[test]
+    fn motor_def_request_id() {
+        _setup();
+
+        let allocator = RequestIdAllocator::new();
+        for ct in 0usize..=300usize {
+            let req = allocator.allocate().unwrap();
+            log::info!("{} {}", ct, req.request_id().id);
+            assert_eq!(req.request_id().id as usize, ct % (1 + u8::MAX as usize));
+        }
+        assert_eq!(allocator.in_flight_count(), 0);
+    }
+
+    #[test]
+    fn motor_def_request_id_in_flight() {
+        _setup();
+
+        let allocator = RequestIdAllocator::new();
+        let first = allocator.allocate().unwrap();
+        let mut others: Vec<InFlightRequestId> = (1..=u8::MAX)
+            .map(|_| allocator.allocate().unwrap())
+            .collect();
+        assert_eq!(allocator.in_flight_count(), 256);
+        assert!(allocator.allocate().is_none());
+
+        // wraps around and skips IDs in flight
+        let released = others.remove(10).request_id();
+        assert!(!allocator.is_in_flight(released));
+        assert_eq!(allocator.allocate().unwrap().request_id(), released);
+        drop(first);
+        assert_eq!(allocator.allocate().unwrap().request_id().id, 0);
+
+        // each cube has its own sequence
+        let another = RequestIdAllocator::new();
+        assert_eq!(another.allocate().unwrap().request_id().id, 0);
+    }
+}
@@ -1,5 +1,5 @@
-use crate::payload::ToPayload;
-use crate::position::CubeLocation;
+use crate::payload::{check_length, decode_field, DecodeError, ToPayload, TryFromPayload};
+use crate::position::{CubeLocation, Point};
 
 /// Movement type
 
@@ -21,6 +21,19 @@ impl From<MovementType> for u8 {
     }
 }
 
+impl TryFrom<u8> for MovementType {
+    type Error = ();
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0u8 => Ok(MovementType::Curve),
+            1u8 => Ok(MovementType::CurveWithoutReverse),
+            2u8 => Ok(MovementType::Linear),
+            _ => Err(()),
+        }
+    }
+}
+
 impl ToPayload<Vec<u8>> for MovementType {
     fn to_payload(self) -> Vec<u8> {
         let payload: Vec<u8> = vec![self.into()];
@@ -45,6 +58,16 @@ impl ToPayload<Vec<u8>> for Speed {
     }
 }
 
+impl TryFromPayload<&[u8]> for Speed {
+    fn try_from_payload(payload: &[u8]) -> Result<Self, DecodeError> {
+        check_length(payload, 2)?;
+        Ok(Self {
+            max: payload[0],
+            speed_change_type: decode_field("speed change type", payload[1])?,
+        })
+    }
+}
+
 /// Speed change type
 
 #[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
@@ -67,6 +90,20 @@ impl From<SpeedChangeType> for u8 {
     }
 }
 
+impl TryFrom<u8> for SpeedChangeType {
+    type Error = ();
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0u8 => Ok(SpeedChangeType::Constant),
+            1u8 => Ok(SpeedChangeType::Acceleration),
+            2u8 => Ok(SpeedChangeType::Deceleration),
+            3u8 => Ok(SpeedChangeType::AccelerationAndDeceleration),
+            _ => Err(()),
+        }
+    }
+}
+
 impl ToPayload<Vec<u8>> for SpeedChangeType {
     fn to_payload(self) -> Vec<u8> {
         let payload: Vec<u8> = vec![self.into()];
@@ -102,6 +139,23 @@ impl From<RotationOption> for u8 {
     }
 }
 
+impl TryFrom<u8> for RotationOption {
+    type Error = ();
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0u8 => Ok(RotationOption::AbsoluteOptimal),
+            1u8 => Ok(RotationOption::AbsolutePositive),
+            2u8 => Ok(RotationOption::AbsoluteNegative),
+            3u8 => Ok(RotationOption::RelativePositive),
+            4u8 => Ok(RotationOption::RelativeNegative),
+            5u8 => Ok(RotationOption::WithoutRotation),
+            6u8 => Ok(RotationOption::SameAsAtWriting),
+            _ => Err(()),
+        }
+    }
+}
+
 impl ToPayload<Vec<u8>> for RotationOption {
     fn to_payload(self) -> Vec<u8> {
         let payload: Vec<u8> = vec![self.into()];
@@ -111,7 +165,7 @@ impl ToPayload<Vec<u8>> for RotationOption {
 
 /// Target to go
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct TargetPosition {
     pub cube_location: CubeLocation,
     pub rotation_option: RotationOption,
@@ -131,15 +185,26 @@ impl ToPayload<Vec<u8>> for TargetPosition {
         let mut payload: Vec<u8> = Vec::new();
         let rotation_option: u16 = (self.cube_location.angle & 0b0001_1111_1111_1111)
             | ((self.rotation_option as u16) << 13);
-        let target_x: u16 = self.cube_location.point.x.try_into().unwrap();
-        let target_y: u16 = self.cube_location.point.y.try_into().unwrap();
-        payload.extend(target_x.to_le_bytes().to_vec());
-        payload.extend(target_y.to_le_bytes().to_vec());
+        payload.extend(self.cube_location.point.to_payload());
         payload.extend(rotation_option.to_le_bytes().to_vec());
         payload
     }
 }
 
+impl TryFromPayload<&[u8]> for TargetPosition {
+    fn try_from_payload(payload: &[u8]) -> Result<Self, DecodeError> {
+        check_length(payload, 6)?;
+        let rotation_option = u16::from_le_bytes([payload[4], payload[5]]);
+        Ok(Self {
+            cube_location: CubeLocation {
+                point: Point::try_from_payload(payload)?,
+                angle: rotation_option & 0b0001_1111_1111_1111,
+            },
+            rotation_option: decode_field("rotation option", (rotation_option >> 13) as u8)?,
+        })
+    }
+}
+
 /// Write mode (MotorCommandId::MultiTargetPositions)
 
 #[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
@@ -158,6 +223,18 @@ impl From<WriteMode> for u8 {
     }
 }
 
+impl TryFrom<u8> for WriteMode {
+    type Error = ();
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0u8 => Ok(WriteMode::Overwrite),
+            1u8 => Ok(WriteMode::Append),
+            _ => Err(()),
+        }
+    }
+}
+
 impl ToPayload<Vec<u8>> for WriteMode {
     fn to_payload(self) -> Vec<u8> {
         let payload: Vec<u8> = vec![self.into()];
@@ -169,7 +246,7 @@ impl ToPayload<Vec<u8>> for WriteMode {
 mod test {
     use super::*;
     use crate::characteristic::motor::command::{MotorControlMultipleTargets, MotorControlTarget};
-    use crate::characteristic::motor::def::Timeout;
+    use crate::characteristic::motor::def::{RequestId, Timeout};
 
     fn _setup() {
         let _ = env_logger::builder().is_test(true).try_init();
@@ -179,7 +256,7 @@ mod test {
     fn motor_target1() {
         _setup();
 
-        let st = MotorControlTarget::default();
+        let st = MotorControlTarget::new(RequestId::received(0));
         let payload = st.to_payload();
         println!("len: {:2} payload:{:?}", payload.len(), payload);
         assert_eq!(payload.len(), 13);
@@ -192,7 +269,7 @@ mod test {
                 speed_change_type: SpeedChangeType::Acceleration,
             },
             _reserved_1: 0xff,
-            ..MotorControlTarget::default()
+            ..MotorControlTarget::new(RequestId::received(0))
         };
         let payload = st.to_payload();
         println!("len: {:2} payload:{:?}", payload.len(), payload);
@@ -203,7 +280,7 @@ mod test {
     fn motor_target2() {
         _setup();
 
-        let st = MotorControlMultipleTargets::default();
+        let st = MotorControlMultipleTargets::new(RequestId::received(0));
         let payload = st.to_payload();
         println!("len: {:2} payload:{:?}", payload.len(), payload);
         assert_eq!(payload.len(), 14);
@@ -221,9 +298,27 @@ mod test {
                 TargetPosition::default(),
                 TargetPosition::default(),
             ],
-            ..MotorControlMultipleTargets::default()
+            ..MotorControlMultipleTargets::new(RequestId::received(0))
         };
         let payload = st.to_payload();
         println!("len: {:2} payload:{:?}", payload.len(), payload);
     }
+
+    #[test]
+    fn motor_target3() {
+        _setup();
+
+        for option in 0u8..=7u8 {
+            match RotationOption::try_from(option) {
+                Ok(rotation_option) => assert_eq!(u8::from(rotation_option), option),
+                Err(_) => assert_eq!(option, 7),
+            }
+        }
+        assert_eq!(MovementType::try_from(2u8), Ok(MovementType::Linear));
+        assert_eq!(
+            SpeedChangeType::try_from(3u8),
+            Ok(SpeedChangeType::AccelerationAndDeceleration)
+        );
+        assert_eq!(WriteMode::try_from(2u8), Err(()));
+    }
 }
@@ -1,8 +1,11 @@
 mod speed;
 mod target;
 
+pub use self::speed::MotorSpeedInformation;
+pub use self::target::{ResponseMotorControlMultipleTargets, ResponseMotorControlTarget};
+
 use crate::characteristic::motor::def::CommandId;
-use crate::payload::FromPayload;
+use crate::payload::{check_length, DecodeError, ToPayload, TryFromPayload};
 
 /// Combined motor response
 
@@ -13,26 +16,34 @@ pub enum MotorInformation {
     MotorSpeed(speed::MotorSpeedInformation),
 }
 
-impl FromPayload<&[u8]> for MotorInformation {
-    fn from_payload(payload: &[u8]) -> Option<Self>
-    where
-        Self: Sized,
-    {
-        if payload.is_empty() {
-            return None;
-        }
-        if let Some(response_data) = target::ResponseMotorControlTarget::from_payload(payload) {
-            return Some(MotorInformation::MotorControlTarget(response_data));
-        }
-        if let Some(response_data) =
-            target::ResponseMotorControlMultipleTargets::from_payload(payload)
-        {
-            return Some(MotorInformation::MotorControlMultipleTargets(response_data));
+impl TryFromPayload<&[u8]> for MotorInformation {
+    fn try_from_payload(payload: &[u8]) -> Result<Self, DecodeError> {
+        check_length(payload, 1)?;
+        let opcode = payload[0];
+        if opcode == CommandId::TargetPosition.response() {
+            target::ResponseMotorControlTarget::try_from_payload(payload)
+                .map(MotorInformation::MotorControlTarget)
+        } else if opcode == CommandId::MultiTargetPositions.response() {
+            target::ResponseMotorControlMultipleTargets::try_from_payload(payload)
+                .map(MotorInformation::MotorControlMultipleTargets)
+        } else if opcode == CommandId::ObtainSpeed.response() {
+            speed::MotorSpeedInformation::try_from_payload(payload)
+                .map(MotorInformation::MotorSpeed)
+        } else {
+            Err(DecodeError::UnknownOpcode(opcode))
         }
-        if let Some(response_data) = speed::MotorSpeedInformation::from_payload(payload) {
-            return Some(MotorInformation::MotorSpeed(response_data));
+    }
+}
+
+impl ToPayload<Vec<u8>> for MotorInformation {
+    fn to_payload(self) -> Vec<u8> {
+        match self {
+            MotorInformation::MotorControlTarget(response_data) => response_data.to_payload(),
+            MotorInformation::MotorControlMultipleTargets(response_data) => {
+                response_data.to_payload()
+            }
+            MotorInformation::MotorSpeed(response_data) => response_data.to_payload(),
         }
-        None
     }
 }
 
@@ -50,14 +61,37 @@ impl From<MotorInformation> for u8 {
 
 #[cfg(test)]
 mod test {
-    use crate::characteristic::motor::def::ResponseCode;
-    use crate::characteristic::motor::information::MotorInformation;
-    use crate::payload::FromPayload;
+    use crate::characteristic::motor::def::{RequestId, ResponseCode};
+    use crate::characteristic::motor::information::*;
+    use crate::payload::{assert_random_round_trip, assert_round_trip, FromPayload};
+    use rand::rngs::StdRng;
+    use rand::Rng;
 
     fn _setup() {
         let _ = env_logger::builder().is_test(true).try_init();
     }
 
+    fn random_motor_information(rng: &mut StdRng) -> MotorInformation {
+        let request_id = RequestId::received(rng.gen());
+        let response_code = ResponseCode::from(rng.gen::<u8>());
+        match rng.gen_range(0..3) {
+            0 => MotorInformation::MotorControlTarget(ResponseMotorControlTarget {
+                request_id,
+                response_code,
+            }),
+            1 => {
+                MotorInformation::MotorControlMultipleTargets(ResponseMotorControlMultipleTargets {
+                    request_id,
+                    response_code,
+                })
+            }
+            _ => MotorInformation::MotorSpeed(MotorSpeedInformation {
+                left: rng.gen(),
+                right: rng.gen(),
+            }),
+        }
+    }
+
     #[test]
     fn motor_response1() {
         _setup();
@@ -71,6 +105,8 @@ mod test {
         } else {
             unreachable!();
         }
+        assert_round_trip(m.unwrap());
+        assert_random_round_trip(random_motor_information);
     }
 
     #[test]
@@ -85,6 +121,7 @@ mod test {
         } else {
             unreachable!();
         }
+        assert_round_trip(m.unwrap());
     }
 
     #[test]
@@ -99,5 +136,6 @@ mod test {
         } else {
             unreachable!();
         }
+        assert_round_trip(m.unwrap());
     }
 }
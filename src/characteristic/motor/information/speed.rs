@@ -1,5 +1,5 @@
 use crate::characteristic::motor::def::CommandId;
-use crate::payload::FromPayload;
+use crate::payload::{decode_message, DecodeError, ToPayload, TryFromPayload};
 
 /// Motor Speed information
 /// ref:<https://toio.github.io/toio-spec/en/docs/ble_motor/#obtaining-motor-speed-information>
@@ -10,21 +10,19 @@ pub struct MotorSpeedInformation {
     pub right: u8,
 }
 
-impl FromPayload<&[u8]> for MotorSpeedInformation {
-    fn from_payload(payload: &[u8]) -> Option<Self>
-    where
-        Self: Sized,
-    {
-        if payload.len() < 3 {
-            return None;
-        }
-        if payload[0] == CommandId::ObtainSpeed.response() {
-            Some(Self {
+impl TryFromPayload<&[u8]> for MotorSpeedInformation {
+    fn try_from_payload(payload: &[u8]) -> Result<Self, DecodeError> {
+        decode_message(payload, CommandId::ObtainSpeed.response(), 3, || {
+            Ok(Self {
                 left: payload[1],
                 right: payload[2],
             })
-        } else {
-            None
-        }
+        })
+    }
+}
+
+impl ToPayload<Vec<u8>> for MotorSpeedInformation {
+    fn to_payload(self) -> Vec<u8> {
+        vec![CommandId::ObtainSpeed.response(), self.left, self.right]
     }
 }
@@ -1,5 +1,5 @@
 use crate::characteristic::motor::def::{CommandId, RequestId, ResponseCode};
-use crate::payload::FromPayload;
+use crate::payload::{decode_message, DecodeError, ToPayload, TryFromPayload};
 
 /// Response to motor control with target specified
 /// ref:<https://toio.github.io/toio-spec/en/docs/ble_motor/#responses-to-motor-control-with-target-specified>
@@ -10,22 +10,24 @@ pub struct ResponseMotorControlTarget {
     pub response_code: ResponseCode,
 }
 
-impl FromPayload<&[u8]> for ResponseMotorControlTarget {
-    fn from_payload(payload: &[u8]) -> Option<Self>
-    where
-        Self: Sized,
-    {
-        if payload.len() < 3 {
-            return None;
-        }
-        if payload[0] == CommandId::TargetPosition.response() {
-            Some(Self {
+impl TryFromPayload<&[u8]> for ResponseMotorControlTarget {
+    fn try_from_payload(payload: &[u8]) -> Result<Self, DecodeError> {
+        decode_message(payload, CommandId::TargetPosition.response(), 3, || {
+            Ok(Self {
                 request_id: RequestId::received(payload[1]),
                 response_code: ResponseCode::from(payload[2]),
             })
-        } else {
-            None
-        }
+        })
+    }
+}
+
+impl ToPayload<Vec<u8>> for ResponseMotorControlTarget {
+    fn to_payload(self) -> Vec<u8> {
+        vec![
+            CommandId::TargetPosition.response(),
+            self.request_id.into(),
+            self.response_code.into(),
+        ]
     }
 }
 
@@ -38,22 +40,29 @@ pub struct ResponseMotorControlMultipleTargets {
     pub response_code: ResponseCode,
 }
 
-impl FromPayload<&[u8]> for ResponseMotorControlMultipleTargets {
-    fn from_payload(payload: &[u8]) -> Option<Self>
-    where
-        Self: Sized,
-    {
-        if payload.len() < 3 {
-            return None;
-        }
-        if payload[0] == CommandId::MultiTargetPositions.response() {
-            Some(Self {
-                request_id: RequestId::received(payload[1]),
-                response_code: ResponseCode::from(payload[2]),
-            })
-        } else {
-            None
-        }
+impl TryFromPayload<&[u8]> for ResponseMotorControlMultipleTargets {
+    fn try_from_payload(payload: &[u8]) -> Result<Self, DecodeError> {
+        decode_message(
+            payload,
+            CommandId::MultiTargetPositions.response(),
+            3,
+            || {
+                Ok(Self {
+                    request_id: RequestId::received(payload[1]),
+                    response_code: ResponseCode::from(payload[2]),
+                })
+            },
+        )
+    }
+}
+
+impl ToPayload<Vec<u8>> for ResponseMotorControlMultipleTargets {
+    fn to_payload(self) -> Vec<u8> {
+        vec![
+            CommandId::MultiTargetPositions.response(),
+            self.request_id.into(),
+            self.response_code.into(),
+        ]
     }
 }
 
@@ -71,7 +80,7 @@ mod test {
     fn motor_target1() {
         _setup();
 
-        let st = MotorControlTarget::default();
+        let st = MotorControlTarget::new(RequestId::received(0));
         let payload = st.to_payload();
         println!("len: {:2} payload:{:?}", payload.len(), payload);
         assert_eq!(payload.len(), 13);
@@ -84,7 +93,7 @@ mod test {
                 speed_change_type: SpeedChangeType::Acceleration,
             },
             _reserved_1: 0xff,
-            ..MotorControlTarget::default()
+            ..MotorControlTarget::new(RequestId::received(0))
         };
         let payload = st.to_payload();
         println!("len: {:2} payload:{:?}", payload.len(), payload);
@@ -95,7 +104,7 @@ mod test {
     fn motor_target2() {
         _setup();
 
-        let st = MotorControlMultipleTargets::default();
+        let st = MotorControlMultipleTargets::new(RequestId::received(0));
         let payload = st.to_payload();
         println!("len: {:2} payload:{:?}", payload.len(), payload);
         assert_eq!(payload.len(), 14);
@@ -113,7 +122,7 @@ mod test {
                 TargetPosition::default(),
                 TargetPosition::default(),
             ],
-            ..MotorControlMultipleTargets::default()
+            ..MotorControlMultipleTargets::new(RequestId::received(0))
         };
         let payload = st.to_payload();
         println!("len: {:2} payload:{:?}", payload.len(), payload);
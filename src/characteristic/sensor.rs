@@ -8,6 +8,9 @@ pub use self::information::*;
 
 #[cfg(test)]
 mod test {
+    use super::*;
+    use crate::payload::assert_round_trip;
+
     fn _setup() {
         let _ = env_logger::builder().is_test(true).try_init();
     }
@@ -16,4 +19,18 @@ mod test {
     fn sensor() {
         _setup();
     }
+
+    #[test]
+    fn sensor_command_round_trip1() {
+        _setup();
+
+        assert_round_trip(RequestMotionDetection::default());
+        assert_round_trip(RequestMagneticSensor::default());
+        for data_type in [PostureDataType::Euler, PostureDataType::Quaternions] {
+            assert_round_trip(RequestPostureAngleDetection {
+                data_type,
+                ..RequestPostureAngleDetection::default()
+            });
+        }
+    }
 }
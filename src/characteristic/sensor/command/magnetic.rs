@@ -1,6 +1,5 @@
 use super::super::def::command_id::CommandId;
-use crate::payload::ToPayload;
-use std::u8;
+use crate::payload::{decode_message, DecodeError, ToPayload, TryFromPayload};
 
 /// Request posture angle information
 /// ref:<https://toio.github.io/toio-spec/en/docs/ble_high_precision_tilt_sensor#requesting-posture-angle-detection>
@@ -25,6 +24,16 @@ impl ToPayload<Vec<u8>> for RequestMagneticSensor {
     }
 }
 
+impl TryFromPayload<&[u8]> for RequestMagneticSensor {
+    fn try_from_payload(payload: &[u8]) -> Result<Self, DecodeError> {
+        decode_message(payload, u8::from(CommandId::MagneticSensor), 1, || {
+            Ok(Self {
+                command: CommandId::MagneticSensor,
+            })
+        })
+    }
+}
+
 #[cfg(test)]
 mod test {
     use crate::characteristic::sensor::SensorInformation;
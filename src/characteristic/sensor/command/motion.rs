@@ -1,5 +1,5 @@
 use super::super::def::command_id::CommandId;
-use crate::payload::ToPayload;
+use crate::payload::{decode_message, DecodeError, ToPayload, TryFromPayload};
 
 /// Request motion sensor information
 /// ref:<https://toio.github.io/toio-spec/en/docs/ble_sensor#requesting-motion-detection-information>
@@ -23,3 +23,13 @@ impl ToPayload<Vec<u8>> for RequestMotionDetection {
         payload
     }
 }
+
+impl TryFromPayload<&[u8]> for RequestMotionDetection {
+    fn try_from_payload(payload: &[u8]) -> Result<Self, DecodeError> {
+        decode_message(payload, u8::from(CommandId::Motion), 1, || {
+            Ok(Self {
+                command: CommandId::Motion,
+            })
+        })
+    }
+}
@@ -1,6 +1,6 @@
 use super::super::def::command_id::CommandId;
 use crate::characteristic::sensor::def::posture_angle::PostureDataType;
-use crate::payload::ToPayload;
+use crate::payload::{decode_field, decode_message, DecodeError, ToPayload, TryFromPayload};
 
 /// Request posture angle information
 /// ref:<https://toio.github.io/toio-spec/en/docs/ble_high_precision_tilt_sensor#requesting-posture-angle-detection>
@@ -29,6 +29,17 @@ impl ToPayload<Vec<u8>> for RequestPostureAngleDetection {
     }
 }
 
+impl TryFromPayload<&[u8]> for RequestPostureAngleDetection {
+    fn try_from_payload(payload: &[u8]) -> Result<Self, DecodeError> {
+        decode_message(payload, u8::from(CommandId::PostureAngle), 2, || {
+            Ok(Self {
+                command: CommandId::PostureAngle,
+                data_type: decode_field("posture data type", payload[1])?,
+            })
+        })
+    }
+}
+
 #[cfg(test)]
 mod test {
     use crate::characteristic::sensor::information::SensorInformation;
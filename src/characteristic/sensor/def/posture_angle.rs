@@ -18,6 +18,18 @@ impl From<PostureDataType> for u8 {
     }
 }
 
+impl TryFrom<u8> for PostureDataType {
+    type Error = ();
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            1u8 => Ok(PostureDataType::Euler),
+            2u8 => Ok(PostureDataType::Quaternions),
+            _ => Err(()),
+        }
+    }
+}
+
 impl ToPayload<Vec<u8>> for PostureDataType {
     fn to_payload(self) -> Vec<u8> {
         let payload: Vec<u8> = vec![self.into()];
@@ -2,8 +2,14 @@ mod magnetic;
 mod motion;
 mod posture_angle;
 
-use crate::characteristic::sensor::def::CommandId;
-use crate::payload::FromPayload;
+pub use self::magnetic::MagneticSensorInformation;
+pub use self::motion::MotionDetectionInformation;
+pub use self::posture_angle::{PostureAngleEulerInformation, PostureAngleQuaternionsInformation};
+
+use crate::characteristic::sensor::def::{CommandId, PostureDataType};
+use crate::payload::{
+    check_length, decode_field, decode_message, DecodeError, ToPayload, TryFromPayload,
+};
 
 /// Sensor response
 
@@ -15,31 +21,43 @@ pub enum SensorInformation {
     MagneticSensor(magnetic::MagneticSensorInformation),
 }
 
-impl FromPayload<&[u8]> for SensorInformation {
-    fn from_payload(payload: &[u8]) -> Option<Self>
-    where
-        Self: Sized,
-    {
-        if payload.is_empty() {
-            return None;
-        }
-        if let Some(response_data) = motion::MotionDetectionInformation::from_payload(payload) {
-            return Some(SensorInformation::MotionDetection(response_data));
-        }
-        if let Some(response_data) = magnetic::MagneticSensorInformation::from_payload(payload) {
-            return Some(SensorInformation::MagneticSensor(response_data));
+impl TryFromPayload<&[u8]> for SensorInformation {
+    fn try_from_payload(payload: &[u8]) -> Result<Self, DecodeError> {
+        check_length(payload, 1)?;
+        let opcode = payload[0];
+        if opcode == CommandId::Motion.response() {
+            motion::MotionDetectionInformation::try_from_payload(payload)
+                .map(SensorInformation::MotionDetection)
+        } else if opcode == CommandId::MagneticSensor.response() {
+            magnetic::MagneticSensorInformation::try_from_payload(payload)
+                .map(SensorInformation::MagneticSensor)
+        } else if opcode == CommandId::PostureAngle.response() {
+            decode_message(payload, opcode, 2, || {
+                match decode_field("posture data type", payload[1])? {
+                    PostureDataType::Euler => {
+                        posture_angle::PostureAngleEulerInformation::try_from_payload(payload)
+                            .map(SensorInformation::PostureAngleEuler)
+                    }
+                    PostureDataType::Quaternions => {
+                        posture_angle::PostureAngleQuaternionsInformation::try_from_payload(payload)
+                            .map(SensorInformation::PostureAngleQuaternion)
+                    }
+                }
+            })
+        } else {
+            Err(DecodeError::UnknownOpcode(opcode))
         }
-        if let Some(response_data) =
-            posture_angle::PostureAngleEulerInformation::from_payload(payload)
-        {
-            return Some(SensorInformation::PostureAngleEuler(response_data));
-        }
-        if let Some(response_data) =
-            posture_angle::PostureAngleQuaternionsInformation::from_payload(payload)
-        {
-            return Some(SensorInformation::PostureAngleQuaternion(response_data));
+    }
+}
+
+impl ToPayload<Vec<u8>> for SensorInformation {
+    fn to_payload(self) -> Vec<u8> {
+        match self {
+            SensorInformation::MotionDetection(response_data) => response_data.to_payload(),
+            SensorInformation::PostureAngleEuler(response_data) => response_data.to_payload(),
+            SensorInformation::PostureAngleQuaternion(response_data) => response_data.to_payload(),
+            SensorInformation::MagneticSensor(response_data) => response_data.to_payload(),
         }
-        None
     }
 }
 
@@ -53,3 +71,65 @@ impl From<SensorInformation> for u8 {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::characteristic::sensor::def::Posture;
+    use crate::payload::{assert_random_round_trip, assert_round_trip};
+    use rand::rngs::StdRng;
+    use rand::Rng;
+
+    fn _setup() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    fn random_sensor_information(rng: &mut StdRng) -> SensorInformation {
+        match rng.gen_range(0..4) {
+            0 => SensorInformation::MotionDetection(MotionDetectionInformation {
+                horizontal: rng.gen(),
+                collision: rng.gen(),
+                double_tap: rng.gen(),
+                posture: Posture::from(rng.gen_range(0..7u8)),
+                shake: rng.gen(),
+            }),
+            1 => SensorInformation::PostureAngleEuler(PostureAngleEulerInformation {
+                roll: rng.gen(),
+                pitch: rng.gen(),
+                yaw: rng.gen(),
+            }),
+            2 => SensorInformation::PostureAngleQuaternion(PostureAngleQuaternionsInformation {
+                w: rng.gen(),
+                x: rng.gen(),
+                y: rng.gen(),
+                z: rng.gen(),
+            }),
+            _ => SensorInformation::MagneticSensor(MagneticSensorInformation {
+                state: rng.gen(),
+                strength: rng.gen(),
+                x: rng.gen(),
+                y: rng.gen(),
+                z: rng.gen(),
+            }),
+        }
+    }
+
+    #[test]
+    fn sensor_information1() {
+        _setup();
+
+        let magnetic = MagneticSensorInformation {
+            state: 1,
+            strength: 10,
+            x: -1,
+            y: 2,
+            z: -3,
+        };
+        assert_eq!(
+            magnetic.to_payload(),
+            vec![0x02, 0x01, 0x0a, 0xff, 0x02, 0xfd]
+        );
+        assert_round_trip(SensorInformation::MagneticSensor(magnetic));
+        assert_random_round_trip(random_sensor_information);
+    }
+}
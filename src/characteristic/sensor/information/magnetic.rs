@@ -1,7 +1,5 @@
 use super::super::def::command_id::CommandId;
-use crate::payload::FromPayload;
-use std::i8;
-use std::u8;
+use crate::payload::{decode_message, DecodeError, ToPayload, TryFromPayload};
 
 /// Posture angle information (euler)
 /// ref:<https://toio.github.io/toio-spec/en/docs/ble_high_precision_tilt_sensor#obtaining-posture-angle-information-notifications-in-euler-angles>
@@ -15,24 +13,30 @@ pub struct MagneticSensorInformation {
     pub z: i8,
 }
 
-impl FromPayload<&[u8]> for MagneticSensorInformation {
-    fn from_payload(payload: &[u8]) -> Option<Self>
-    where
-        Self: Sized,
-    {
-        if payload.len() < 6 {
-            return None;
-        }
-        if payload[0] == CommandId::MagneticSensor.response() {
-            Some(Self {
+impl TryFromPayload<&[u8]> for MagneticSensorInformation {
+    fn try_from_payload(payload: &[u8]) -> Result<Self, DecodeError> {
+        decode_message(payload, CommandId::MagneticSensor.response(), 6, || {
+            Ok(Self {
                 state: payload[1],
                 strength: payload[2],
                 x: i8::from_le_bytes([payload[3]]),
                 y: i8::from_le_bytes([payload[4]]),
                 z: i8::from_le_bytes([payload[5]]),
             })
-        } else {
-            None
-        }
+        })
+    }
+}
+
+impl ToPayload<Vec<u8>> for MagneticSensorInformation {
+    fn to_payload(self) -> Vec<u8> {
+        let mut payload: Vec<u8> = vec![
+            CommandId::MagneticSensor.response(),
+            self.state,
+            self.strength,
+        ];
+        payload.extend(self.x.to_le_bytes());
+        payload.extend(self.y.to_le_bytes());
+        payload.extend(self.z.to_le_bytes());
+        payload
     }
 }
@@ -1,6 +1,6 @@
 use super::super::def::command_id::CommandId;
 use super::super::def::motion::Posture;
-use crate::payload::FromPayload;
+use crate::payload::{decode_message, DecodeError, ToPayload, TryFromPayload};
 
 /// Motion detection information
 /// ref:<https://toio.github.io/toio-spec/en/docs/ble_sensor#obtaining-motion-detection-information>
@@ -33,24 +33,29 @@ impl MotionDetectionInformation {
     }
 }
 
-impl FromPayload<&[u8]> for MotionDetectionInformation {
-    fn from_payload(payload: &[u8]) -> Option<Self>
-    where
-        Self: Sized,
-    {
-        if payload.len() < 6 {
-            return None;
-        }
-        if payload[0] == CommandId::Motion.response() {
-            Some(Self {
+impl TryFromPayload<&[u8]> for MotionDetectionInformation {
+    fn try_from_payload(payload: &[u8]) -> Result<Self, DecodeError> {
+        decode_message(payload, CommandId::Motion.response(), 6, || {
+            Ok(Self {
                 horizontal: payload[1] != 0,
                 collision: payload[2] != 0,
                 double_tap: payload[3] != 0,
                 posture: Posture::from(payload[4]),
                 shake: payload[5],
             })
-        } else {
-            None
-        }
+        })
+    }
+}
+
+impl ToPayload<Vec<u8>> for MotionDetectionInformation {
+    fn to_payload(self) -> Vec<u8> {
+        vec![
+            CommandId::Motion.response(),
+            self.horizontal.into(),
+            self.collision.into(),
+            self.double_tap.into(),
+            self.posture.into(),
+            self.shake,
+        ]
     }
 }
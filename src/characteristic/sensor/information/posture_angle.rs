@@ -1,6 +1,17 @@
 use super::super::def::command_id::CommandId;
 use super::super::def::posture_angle::PostureDataType;
-use crate::payload::FromPayload;
+use crate::payload::{decode_message, DecodeError, ToPayload, TryFromPayload};
+
+fn check_data_type(value: u8, data_type: PostureDataType) -> Result<(), DecodeError> {
+    if value != u8::from(data_type) {
+        return Err(DecodeError::InvalidField {
+            opcode: None,
+            field: "posture data type",
+            value,
+        });
+    }
+    Ok(())
+}
 
 /// Posture angle information (euler)
 /// ref:<https://toio.github.io/toio-spec/en/docs/ble_high_precision_tilt_sensor#obtaining-posture-angle-information-notifications-in-euler-angles>
@@ -12,25 +23,29 @@ pub struct PostureAngleEulerInformation {
     pub yaw: i16,
 }
 
-impl FromPayload<&[u8]> for PostureAngleEulerInformation {
-    fn from_payload(payload: &[u8]) -> Option<Self>
-    where
-        Self: Sized,
-    {
-        if payload.len() < 8 {
-            return None;
-        }
-        if (payload[0] == CommandId::PostureAngle.response())
-            && (payload[1] == u8::from(PostureDataType::Euler))
-        {
-            Some(PostureAngleEulerInformation {
+impl TryFromPayload<&[u8]> for PostureAngleEulerInformation {
+    fn try_from_payload(payload: &[u8]) -> Result<Self, DecodeError> {
+        decode_message(payload, CommandId::PostureAngle.response(), 8, || {
+            check_data_type(payload[1], PostureDataType::Euler)?;
+            Ok(PostureAngleEulerInformation {
                 roll: i16::from_le_bytes([payload[2], payload[3]]),
                 pitch: i16::from_le_bytes([payload[4], payload[5]]),
                 yaw: i16::from_le_bytes([payload[6], payload[7]]),
             })
-        } else {
-            None
-        }
+        })
+    }
+}
+
+impl ToPayload<Vec<u8>> for PostureAngleEulerInformation {
+    fn to_payload(self) -> Vec<u8> {
+        let mut payload: Vec<u8> = vec![
+            CommandId::PostureAngle.response(),
+            PostureDataType::Euler.into(),
+        ];
+        payload.extend(self.roll.to_le_bytes());
+        payload.extend(self.pitch.to_le_bytes());
+        payload.extend(self.yaw.to_le_bytes());
+        payload
     }
 }
 
@@ -45,25 +60,30 @@ pub struct PostureAngleQuaternionsInformation {
     pub z: i16,
 }
 
-impl FromPayload<&[u8]> for PostureAngleQuaternionsInformation {
-    fn from_payload(payload: &[u8]) -> Option<Self>
-    where
-        Self: Sized,
-    {
-        if payload.len() < 8 {
-            return None;
-        }
-        if (payload[0] == CommandId::PostureAngle.response())
-            && (payload[1] == u8::from(PostureDataType::Quaternions))
-        {
-            Some(PostureAngleQuaternionsInformation {
+impl TryFromPayload<&[u8]> for PostureAngleQuaternionsInformation {
+    fn try_from_payload(payload: &[u8]) -> Result<Self, DecodeError> {
+        decode_message(payload, CommandId::PostureAngle.response(), 10, || {
+            check_data_type(payload[1], PostureDataType::Quaternions)?;
+            Ok(PostureAngleQuaternionsInformation {
                 w: i16::from_le_bytes([payload[2], payload[3]]),
                 x: i16::from_le_bytes([payload[4], payload[5]]),
                 y: i16::from_le_bytes([payload[6], payload[7]]),
                 z: i16::from_le_bytes([payload[8], payload[9]]),
             })
-        } else {
-            None
-        }
+        })
+    }
+}
+
+impl ToPayload<Vec<u8>> for PostureAngleQuaternionsInformation {
+    fn to_payload(self) -> Vec<u8> {
+        let mut payload: Vec<u8> = vec![
+            CommandId::PostureAngle.response(),
+            PostureDataType::Quaternions.into(),
+        ];
+        payload.extend(self.w.to_le_bytes());
+        payload.extend(self.x.to_le_bytes());
+        payload.extend(self.y.to_le_bytes());
+        payload.extend(self.z.to_le_bytes());
+        payload
     }
 }
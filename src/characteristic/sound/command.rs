@@ -1 +1,3 @@
 mod sound;
+
+pub use self::sound::{PlayMidiNote, PlaySoundEffect, StopSound};
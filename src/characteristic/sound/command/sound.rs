@@ -1,6 +1,8 @@
 //! Official Specification: <https://toio.github.io/toio-spec/docs/ble_sound>
 
-use crate::payload::ToPayload;
+use crate::payload::{
+    check_length, decode_field, decode_message, DecodeError, ToPayload, TryFromPayload,
+};
 use std::cmp;
 
 use super::super::def::command_id::CommandId;
@@ -10,7 +12,7 @@ use super::super::def::sound_effect_id::SoundEffectId;
 // PlaySoundEffect
 // ref:<https://toio.github.io/toio-spec/en/docs/ble_sound/#playing-sound-effects>
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct PlaySoundEffect {
     pub command: CommandId,
     pub id: SoundEffectId,
@@ -34,10 +36,22 @@ impl ToPayload<Vec<u8>> for PlaySoundEffect {
     }
 }
 
+impl TryFromPayload<&[u8]> for PlaySoundEffect {
+    fn try_from_payload(payload: &[u8]) -> Result<Self, DecodeError> {
+        decode_message(payload, u8::from(CommandId::PlaySoundEffect), 3, || {
+            Ok(Self {
+                command: CommandId::PlaySoundEffect,
+                id: decode_field("sound effect id", payload[1])?,
+                volume: payload[2],
+            })
+        })
+    }
+}
+
 // PlayMidiNote
 // ref:<https://toio.github.io/toio-spec/en/docs/ble_sound/#playing-the-midi-note-numbers>
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct PlayMidiNote {
     pub command: CommandId,
     pub repeat: u8,
@@ -68,6 +82,24 @@ impl ToPayload<Vec<u8>> for PlayMidiNote {
     }
 }
 
+impl TryFromPayload<&[u8]> for PlayMidiNote {
+    fn try_from_payload(payload: &[u8]) -> Result<Self, DecodeError> {
+        decode_message(payload, u8::from(CommandId::PlayMidiNotes), 3, || {
+            // header (3 bytes) and the notes (3 bytes each)
+            let length = 3 + usize::from(payload[2]) * 3;
+            check_length(payload, length)?;
+            Ok(Self {
+                command: CommandId::PlayMidiNotes,
+                repeat: payload[1],
+                midi_notes: payload[3..length]
+                    .chunks(3)
+                    .map(MidiNote::try_from_payload)
+                    .collect::<Result<Vec<MidiNote>, DecodeError>>()?,
+            })
+        })
+    }
+}
+
 // StopSound
 // ref:<https://toio.github.io/toio-spec/en/docs/ble_sound/#stop-playing>
 
@@ -90,3 +122,58 @@ impl ToPayload<Vec<u8>> for StopSound {
         payload
     }
 }
+
+impl TryFromPayload<&[u8]> for StopSound {
+    fn try_from_payload(payload: &[u8]) -> Result<Self, DecodeError> {
+        decode_message(payload, u8::from(CommandId::StopSound), 1, || {
+            Ok(Self {
+                command: CommandId::StopSound,
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::characteristic::sound::def::midi_note::{Note, Period};
+    use crate::payload::{assert_random_round_trip, assert_round_trip};
+    use rand::rngs::StdRng;
+    use rand::Rng;
+
+    fn _setup() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    fn random_play_sound_effect(rng: &mut StdRng) -> PlaySoundEffect {
+        PlaySoundEffect {
+            id: SoundEffectId::try_from(rng.gen_range(0..=10)).unwrap(),
+            volume: rng.gen(),
+            ..PlaySoundEffect::default()
+        }
+    }
+
+    fn random_play_midi_note(rng: &mut StdRng) -> PlayMidiNote {
+        PlayMidiNote {
+            repeat: rng.gen(),
+            midi_notes: (0..rng.gen_range(1..=59))
+                .map(|_| MidiNote {
+                    volume: rng.gen(),
+                    duration: Period::from(rng.gen::<u8>()),
+                    note: Note::try_from(rng.gen_range(0..=128)).unwrap(),
+                })
+                .collect(),
+            ..PlayMidiNote::default()
+        }
+    }
+
+    #[test]
+    fn sound_command1() {
+        _setup();
+
+        assert_round_trip(PlaySoundEffect::default());
+        assert_random_round_trip(random_play_sound_effect);
+        assert_random_round_trip(random_play_midi_note);
+        assert_round_trip(StopSound::default());
+    }
+}
@@ -3,5 +3,5 @@ pub(crate) mod midi_note;
 pub(crate) mod sound_effect_id;
 
 pub use self::command_id::CommandId;
-pub use self::midi_note::MidiNote;
+pub use self::midi_note::{MidiNote, Note};
 pub use self::sound_effect_id::SoundEffectId;
@@ -1,7 +1,7 @@
 //! Official Specification:  <https://toio.github.io/toio-spec/en/docs/ble_sound#midi-note-number-and-note-name>
 
 pub use crate::characteristic::motor::def::params::Period;
-use crate::payload::ToPayload;
+use crate::payload::{check_length, decode_field, DecodeError, ToPayload, TryFromPayload};
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum Note {
@@ -272,7 +272,146 @@ impl From<Note> for u8 {
     }
 }
 
-#[derive(Debug, Copy, Clone)]
+impl TryFrom<u8> for Note {
+    type Error = ();
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0u8 => Ok(Note::C0),
+            1u8 => Ok(Note::CS0),
+            2u8 => Ok(Note::D0),
+            3u8 => Ok(Note::DS0),
+            4u8 => Ok(Note::E0),
+            5u8 => Ok(Note::F0),
+            6u8 => Ok(Note::FS0),
+            7u8 => Ok(Note::G0),
+            8u8 => Ok(Note::GS0),
+            9u8 => Ok(Note::A0),
+            10u8 => Ok(Note::AS0),
+            11u8 => Ok(Note::B0),
+            12u8 => Ok(Note::C1),
+            13u8 => Ok(Note::CS1),
+            14u8 => Ok(Note::D1),
+            15u8 => Ok(Note::DS1),
+            16u8 => Ok(Note::E1),
+            17u8 => Ok(Note::F1),
+            18u8 => Ok(Note::FS1),
+            19u8 => Ok(Note::G1),
+            20u8 => Ok(Note::GS1),
+            21u8 => Ok(Note::A1),
+            22u8 => Ok(Note::AS1),
+            23u8 => Ok(Note::B1),
+            24u8 => Ok(Note::C2),
+            25u8 => Ok(Note::CS2),
+            26u8 => Ok(Note::D2),
+            27u8 => Ok(Note::DS2),
+            28u8 => Ok(Note::E2),
+            29u8 => Ok(Note::F2),
+            30u8 => Ok(Note::FS2),
+            31u8 => Ok(Note::G2),
+            32u8 => Ok(Note::GS2),
+            33u8 => Ok(Note::A2),
+            34u8 => Ok(Note::AS2),
+            35u8 => Ok(Note::B2),
+            36u8 => Ok(Note::C3),
+            37u8 => Ok(Note::CS3),
+            38u8 => Ok(Note::D3),
+            39u8 => Ok(Note::DS3),
+            40u8 => Ok(Note::E3),
+            41u8 => Ok(Note::F3),
+            42u8 => Ok(Note::FS3),
+            43u8 => Ok(Note::G3),
+            44u8 => Ok(Note::GS3),
+            45u8 => Ok(Note::A3),
+            46u8 => Ok(Note::AS3),
+            47u8 => Ok(Note::B3),
+            48u8 => Ok(Note::C4),
+            49u8 => Ok(Note::CS4),
+            50u8 => Ok(Note::D4),
+            51u8 => Ok(Note::DS4),
+            52u8 => Ok(Note::E4),
+            53u8 => Ok(Note::F4),
+            54u8 => Ok(Note::FS4),
+            55u8 => Ok(Note::G4),
+            56u8 => Ok(Note::GS4),
+            57u8 => Ok(Note::A4),
+            58u8 => Ok(Note::AS4),
+            59u8 => Ok(Note::B4),
+            60u8 => Ok(Note::C5),
+            61u8 => Ok(Note::CS5),
+            62u8 => Ok(Note::D5),
+            63u8 => Ok(Note::DS5),
+            64u8 => Ok(Note::E5),
+            65u8 => Ok(Note::F5),
+            66u8 => Ok(Note::FS5),
+            67u8 => Ok(Note::G5),
+            68u8 => Ok(Note::GS5),
+            69u8 => Ok(Note::A5),
+            70u8 => Ok(Note::AS5),
+            71u8 => Ok(Note::B5),
+            72u8 => Ok(Note::C6),
+            73u8 => Ok(Note::CS6),
+            74u8 => Ok(Note::D6),
+            75u8 => Ok(Note::DS6),
+            76u8 => Ok(Note::E6),
+            77u8 => Ok(Note::F6),
+            78u8 => Ok(Note::FS6),
+            79u8 => Ok(Note::G6),
+            80u8 => Ok(Note::GS6),
+            81u8 => Ok(Note::A6),
+            82u8 => Ok(Note::AS6),
+            83u8 => Ok(Note::B6),
+            84u8 => Ok(Note::C7),
+            85u8 => Ok(Note::CS7),
+            86u8 => Ok(Note::D7),
+            87u8 => Ok(Note::DS7),
+            88u8 => Ok(Note::E7),
+            89u8 => Ok(Note::F7),
+            90u8 => Ok(Note::FS7),
+            91u8 => Ok(Note::G7),
+            92u8 => Ok(Note::GS7),
+            93u8 => Ok(Note::A7),
+            94u8 => Ok(Note::AS7),
+            95u8 => Ok(Note::B7),
+            96u8 => Ok(Note::C8),
+            97u8 => Ok(Note::CS8),
+            98u8 => Ok(Note::D8),
+            99u8 => Ok(Note::DS8),
+            100u8 => Ok(Note::E8),
+            101u8 => Ok(Note::F8),
+            102u8 => Ok(Note::FS8),
+            103u8 => Ok(Note::G8),
+            104u8 => Ok(Note::GS8),
+            105u8 => Ok(Note::A8),
+            106u8 => Ok(Note::AS8),
+            107u8 => Ok(Note::B8),
+            108u8 => Ok(Note::C9),
+            109u8 => Ok(Note::CS9),
+            110u8 => Ok(Note::D9),
+            111u8 => Ok(Note::DS9),
+            112u8 => Ok(Note::E9),
+            113u8 => Ok(Note::F9),
+            114u8 => Ok(Note::FS9),
+            115u8 => Ok(Note::G9),
+            116u8 => Ok(Note::GS9),
+            117u8 => Ok(Note::A9),
+            118u8 => Ok(Note::AS9),
+            119u8 => Ok(Note::B9),
+            120u8 => Ok(Note::C10),
+            121u8 => Ok(Note::CS10),
+            122u8 => Ok(Note::D10),
+            123u8 => Ok(Note::DS10),
+            124u8 => Ok(Note::E10),
+            125u8 => Ok(Note::F10),
+            126u8 => Ok(Note::FS10),
+            127u8 => Ok(Note::G10),
+            128u8 => Ok(Note::NoSound),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct MidiNote {
     pub volume: u8,
     pub duration: Period,
@@ -282,9 +421,20 @@ pub struct MidiNote {
 impl ToPayload<Vec<u8>> for MidiNote {
     fn to_payload(self) -> Vec<u8> {
         let mut payload: Vec<u8> = Vec::new();
-        payload.push(self.volume);
         payload.extend(self.duration.to_payload());
         payload.push(self.note.into());
+        payload.push(self.volume);
         payload
     }
 }
+
+impl TryFromPayload<&[u8]> for MidiNote {
+    fn try_from_payload(payload: &[u8]) -> Result<Self, DecodeError> {
+        check_length(payload, 3)?;
+        Ok(Self {
+            duration: Period::from(payload[0]),
+            note: decode_field("note", payload[1])?,
+            volume: payload[2],
+        })
+    }
+}
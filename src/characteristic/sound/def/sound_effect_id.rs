@@ -30,3 +30,24 @@ impl From<SoundEffectId> for u8 {
         }
     }
 }
+
+impl TryFrom<u8> for SoundEffectId {
+    type Error = ();
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0u8 => Ok(SoundEffectId::Enter),
+            1u8 => Ok(SoundEffectId::Selected),
+            2u8 => Ok(SoundEffectId::Cancel),
+            3u8 => Ok(SoundEffectId::Cursor),
+            4u8 => Ok(SoundEffectId::MatIn),
+            5u8 => Ok(SoundEffectId::MatOut),
+            6u8 => Ok(SoundEffectId::Get1),
+            7u8 => Ok(SoundEffectId::Get2),
+            8u8 => Ok(SoundEffectId::Get3),
+            9u8 => Ok(SoundEffectId::Effect1),
+            10u8 => Ok(SoundEffectId::Effect2),
+            _ => Err(()),
+        }
+    }
+}
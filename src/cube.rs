@@ -0,0 +1,282 @@
+//! Cube handle which owns the connection and keeps the latest state of the cube
+
+use crate::characteristic::battery::BatteryInformation;
+use crate::characteristic::button::ButtonState;
+use crate::characteristic::id::{IdInformation, PositionIdData, StandardIdData};
+use crate::characteristic::motor::{MotorInformation, MotorSpeedInformation};
+use crate::characteristic::sensor::{Posture, SensorInformation};
+use crate::characteristic::CubeEvent;
+use crate::interface::{CubeEventStream, CubeInterface};
+use crate::CoreCubeError;
+use futures::stream::StreamExt;
+use log::debug;
+use std::sync::Arc;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+
+/// Latest readings notified by the cube
+///
+/// `None` until the cube notifies it (position and standard ID also turn `None`
+/// when the cube loses them).
+struct CubeState {
+    position: watch::Sender<Option<PositionIdData>>,
+    standard_id: watch::Sender<Option<StandardIdData>>,
+    posture: watch::Sender<Option<Posture>>,
+    button: watch::Sender<Option<ButtonState>>,
+    battery: watch::Sender<Option<BatteryInformation>>,
+    motor_speed: watch::Sender<Option<MotorSpeedInformation>>,
+}
+
+impl Default for CubeState {
+    fn default() -> Self {
+        Self {
+            position: watch::channel(None).0,
+            standard_id: watch::channel(None).0,
+            posture: watch::channel(None).0,
+            button: watch::channel(None).0,
+            battery: watch::channel(None).0,
+            motor_speed: watch::channel(None).0,
+        }
+    }
+}
+
+impl CubeState {
+    fn update(&self, event: &CubeEvent) {
+        match event {
+            CubeEvent::Id(IdInformation::PositionId(position)) => {
+                self.position.send_replace(Some(*position));
+                self.standard_id.send_replace(None);
+            }
+            CubeEvent::Id(IdInformation::StandardId(standard_id)) => {
+                self.standard_id.send_replace(Some(*standard_id));
+                self.position.send_replace(None);
+            }
+            CubeEvent::Id(IdInformation::PositionIdMissed) => {
+                self.position.send_replace(None);
+            }
+            CubeEvent::Id(IdInformation::StandardIdMissed) => {
+                self.standard_id.send_replace(None);
+            }
+            CubeEvent::Sensor(SensorInformation::MotionDetection(motion)) => {
+                self.posture.send_replace(Some(motion.posture));
+            }
+            CubeEvent::Button(button) => {
+                self.button.send_replace(Some(button.state));
+            }
+            CubeEvent::Battery(battery) => {
+                self.battery.send_replace(Some(*battery));
+            }
+            CubeEvent::Motor(MotorInformation::MotorSpeed(motor_speed)) => {
+                self.motor_speed.send_replace(Some(*motor_speed));
+            }
+            _ => {}
+        }
+    }
+}
+
+async fn cube_event_loop(mut events: CubeEventStream, state: Arc<CubeState>) {
+    while let Some(event) = events.next().await {
+        state.update(&event);
+    }
+    debug!("cube event loop is finished");
+}
+
+/// Connected cube
+///
+/// Runs the notification loop internally and caches the latest readings.
+/// Getters return the cached value, and `watch_*` returns a receiver which is
+/// notified each time the reading changes.
+pub struct Cube {
+    interface: Box<dyn CubeInterface + Send + Sync + 'static>,
+    state: Arc<CubeState>,
+    event_loop: Option<JoinHandle<()>>,
+}
+
+impl Cube {
+    pub fn new(interface: Box<dyn CubeInterface + Send + Sync + 'static>) -> Self {
+        Self {
+            interface,
+            state: Arc::new(CubeState::default()),
+            event_loop: None,
+        }
+    }
+
+    /// connect to the cube and start the notification loop
+    pub async fn connect(&mut self) -> Result<(), CoreCubeError> {
+        self.interface.connect().await?;
+        let events = self.interface.event_stream().await?;
+        self.stop_event_loop();
+        self.event_loop = Some(tokio::spawn(cube_event_loop(events, self.state.clone())));
+        // battery level is notified only every 5 seconds
+        let battery = self.interface.read_battery().await?;
+        self.state.battery.send_replace(Some(battery));
+        Ok(())
+    }
+
+    pub async fn disconnect(&mut self) -> Result<(), CoreCubeError> {
+        self.stop_event_loop();
+        self.interface.disconnect().await
+    }
+
+    fn stop_event_loop(&mut self) {
+        if let Some(event_loop) = self.event_loop.take() {
+            event_loop.abort();
+        }
+    }
+
+    /// interface to send commands (e.g. with `api::Simple`)
+    pub fn interface(&self) -> &(dyn CubeInterface + Send + Sync + 'static) {
+        self.interface.as_ref()
+    }
+
+    pub async fn position(&self) -> Option<PositionIdData> {
+        *self.state.position.borrow()
+    }
+
+    pub async fn standard_id(&self) -> Option<StandardIdData> {
+        *self.state.standard_id.borrow()
+    }
+
+    pub async fn posture(&self) -> Option<Posture> {
+        *self.state.posture.borrow()
+    }
+
+    pub async fn button(&self) -> Option<ButtonState> {
+        *self.state.button.borrow()
+    }
+
+    pub async fn battery(&self) -> Option<BatteryInformation> {
+        *self.state.battery.borrow()
+    }
+
+    pub async fn motor_speed(&self) -> Option<MotorSpeedInformation> {
+        *self.state.motor_speed.borrow()
+    }
+
+    pub fn watch_position(&self) -> watch::Receiver<Option<PositionIdData>> {
+        self.state.position.subscribe()
+    }
+
+    pub fn watch_standard_id(&self) -> watch::Receiver<Option<StandardIdData>> {
+        self.state.standard_id.subscribe()
+    }
+
+    pub fn watch_posture(&self) -> watch::Receiver<Option<Posture>> {
+        self.state.posture.subscribe()
+    }
+
+    pub fn watch_button(&self) -> watch::Receiver<Option<ButtonState>> {
+        self.state.button.subscribe()
+    }
+
+    pub fn watch_battery(&self) -> watch::Receiver<Option<BatteryInformation>> {
+        self.state.battery.subscribe()
+    }
+
+    pub fn watch_motor_speed(&self) -> watch::Receiver<Option<MotorSpeedInformation>> {
+        self.state.motor_speed.subscribe()
+    }
+}
+
+impl Drop for Cube {
+    fn drop(&mut self) {
+        self.stop_event_loop();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::characteristic::configuration::EnableMotorSpeed;
+    use crate::characteristic::motor::MotorControl;
+    use crate::characteristic::sensor::MotionDetectionInformation;
+    use crate::characteristic::CoreCubeUuid;
+    use crate::interface::virtual_cube::VirtualCube;
+    use crate::payload::ToPayload;
+    use crate::position::{CubeLocation, Point};
+    use std::time::Duration;
+
+    fn _setup() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    async fn settle() {
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+
+    #[tokio::test]
+    async fn cube1() {
+        _setup();
+        let virtual_cube = VirtualCube::default();
+        virtual_cube.set_battery_level(80);
+        let mut cube = Cube::new(Box::new(virtual_cube.clone()));
+        cube.connect().await.unwrap();
+        assert_eq!(cube.battery().await, Some(BatteryInformation { level: 80 }));
+        assert_eq!(cube.position().await, None);
+
+        let mut position = cube.watch_position();
+        virtual_cube.place(CubeLocation {
+            point: Point { x: 200, y: 300 },
+            angle: 90,
+        });
+        virtual_cube.advance(Duration::from_millis(10));
+        tokio::time::timeout(Duration::from_secs(1), position.changed())
+            .await
+            .unwrap()
+            .unwrap();
+        let center = position.borrow().unwrap().center;
+        assert_eq!(center.point, Point { x: 200, y: 300 });
+        assert_eq!(cube.position().await.unwrap().center, center);
+
+        virtual_cube.lift();
+        virtual_cube.advance(Duration::from_millis(10));
+        settle().await;
+        assert_eq!(cube.position().await, None);
+
+        virtual_cube.press_button();
+        virtual_cube.set_battery_level(50);
+        virtual_cube.set_motion(MotionDetectionInformation {
+            posture: Posture::Bottom,
+            ..MotionDetectionInformation::default()
+        });
+        settle().await;
+        assert_eq!(cube.button().await, Some(ButtonState::Pressed));
+        assert_eq!(cube.battery().await, Some(BatteryInformation { level: 50 }));
+        assert_eq!(cube.posture().await, Some(Posture::Bottom));
+
+        cube.disconnect().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn cube_motor_speed1() {
+        _setup();
+        let mut cube = Cube::new(Box::new(VirtualCube::default()));
+        cube.connect().await.unwrap();
+        let mut motor_speed = cube.watch_motor_speed();
+
+        let interface = cube.interface();
+        interface
+            .write(
+                CoreCubeUuid::Config.uuid(),
+                &EnableMotorSpeed::new(true).to_payload(),
+            )
+            .await
+            .unwrap();
+        let motor = MotorControl::set_value(30, 40).unwrap();
+        interface
+            .write(CoreCubeUuid::MotorCtrl.uuid(), &motor.to_payload())
+            .await
+            .unwrap();
+        tokio::time::timeout(Duration::from_secs(1), motor_speed.changed())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            cube.motor_speed().await,
+            Some(MotorSpeedInformation {
+                left: 30,
+                right: 40
+            })
+        );
+    }
+}
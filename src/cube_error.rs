@@ -1,15 +1,42 @@
+use crate::characteristic::motor::MotorError;
+use crate::notification_manager::NotificationManagerError;
 use thiserror::Error;
+use uuid::Uuid;
 
-#[derive(Error, Debug, PartialEq, Eq)]
+#[derive(Error, Debug)]
 pub enum CoreCubeError {
     #[error("toio core cube is not found")]
     CubeNotFound,
-    #[error("no bluetooth peripherals")]
-    WrongParameter,
     #[error("wrong parameter")]
+    WrongParameter,
+    #[error("no bluetooth peripherals")]
     NoBlePeripherals,
+    #[error("no bluetooth adapters")]
+    NoBleAdapter,
     #[error("inteface is not defined")]
     NoInterface,
+    #[error("toio core cube is not connected")]
+    NotConnected,
+    #[error("toio core cube is disconnected")]
+    Disconnected,
+    #[error("no response from toio core cube")]
+    Timeout,
+    #[error("all request ids are in flight")]
+    RequestIdExhausted,
+    #[error("characteristic {0} is not found")]
+    CharacteristicNotFound(Uuid),
+    #[error("failed to decode data from {uuid}: {data:02x?}")]
+    Decode { uuid: Uuid, data: Vec<u8> },
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("broken session file: {0}")]
+    SessionFile(#[from] serde_json::Error),
+    #[error("bluetooth error: {0}")]
+    Transport(#[from] btleplug::Error),
+    #[error(transparent)]
+    Motor(#[from] MotorError),
+    #[error(transparent)]
+    NotificationManager(#[from] NotificationManagerError),
     #[error("internal error of cube.rs")]
     FoundBug,
 }
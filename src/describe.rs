@@ -0,0 +1,482 @@
+//! Human readable description of toio payloads (for logs and debugging)
+//!
+//! ```text
+//! MotorCtrl: TargetPosition id=3 timeout=0s Linear speed=50 -> (360,170) 180° AbsoluteOptimal
+//! IdInfo: PositionId center=(360,170) 180° sensor=(362,170) 180°
+//! ```
+
+use crate::characteristic::configuration::{
+    ConfigurationInformation, EnableMotorSpeed, RequestBleProtocolVersion,
+    SetCollisionDetectionThreshold, SetDoubleTapDetectionTimeInterval,
+    SetHorizontalDetectionThreshold, SetIdSensorMissedNotification, SetIdSensorNotification,
+    SetMagneticSensor, SetPostureAngleDetection,
+};
+use crate::characteristic::id::IdInformation;
+use crate::characteristic::indicator::{
+    Color, IndicatorParam, RepeatedTuringOnAndOff, TurnOff, TurnOffAll, TurningOnAndOff,
+};
+use crate::characteristic::motor::{
+    self, MotorControl, MotorControlAcceleration, MotorControlMultipleTargets, MotorControlTarget,
+    MotorControlWithSpecifiedDuration, MotorInformation, Period, Speed, SpeedChangeType,
+    TargetPosition, Timeout,
+};
+use crate::characteristic::sensor::{
+    RequestMagneticSensor, RequestMotionDetection, RequestPostureAngleDetection, SensorInformation,
+};
+use crate::characteristic::sound::{PlayMidiNote, PlaySoundEffect, StopSound};
+use crate::characteristic::{CoreCubeUuid, CubeEvent};
+use crate::payload::FromPayload;
+use crate::position::CubeLocation;
+use uuid::Uuid;
+
+/// describe the payload written to (or notified from) the characteristic
+///
+/// Payloads which can not be decoded are shown as bytes.
+pub fn describe(uuid: Uuid, bytes: &[u8]) -> String {
+    let Some(characteristic) = CoreCubeUuid::from_uuid(uuid) else {
+        return format!("{}: {:02x?}", uuid, bytes);
+    };
+    let description = match CubeEvent::decode(uuid, bytes) {
+        CubeEvent::Unknown { .. } => describe_command(characteristic, bytes),
+        event => describe_event(&event),
+    };
+    match description {
+        Some(description) => format!("{}: {}", characteristic.name(), description),
+        None => format!("{}: {:02x?}", characteristic.name(), bytes),
+    }
+}
+
+fn describe_event(event: &CubeEvent) -> Option<String> {
+    let description = match event {
+        CubeEvent::Id(IdInformation::PositionId(position)) => format!(
+            "PositionId center={} sensor={}",
+            location(&position.center),
+            location(&position.sensor)
+        ),
+        CubeEvent::Id(IdInformation::StandardId(standard_id)) => format!(
+            "StandardId value={} angle={}°",
+            standard_id.value, standard_id.angle
+        ),
+        CubeEvent::Id(IdInformation::PositionIdMissed) => "PositionIdMissed".to_string(),
+        CubeEvent::Id(IdInformation::StandardIdMissed) => "StandardIdMissed".to_string(),
+        CubeEvent::Id(IdInformation::Unknown(_)) => return None,
+        CubeEvent::Sensor(SensorInformation::MotionDetection(motion)) => format!(
+            "MotionDetection horizontal={} collision={} double_tap={} posture={:?} shake={}",
+            motion.horizontal, motion.collision, motion.double_tap, motion.posture, motion.shake
+        ),
+        CubeEvent::Sensor(SensorInformation::PostureAngleEuler(euler)) => format!(
+            "PostureAngleEuler roll={} pitch={} yaw={}",
+            euler.roll, euler.pitch, euler.yaw
+        ),
+        CubeEvent::Sensor(SensorInformation::PostureAngleQuaternion(quaternion)) => format!(
+            "PostureAngleQuaternion w={} x={} y={} z={}",
+            quaternion.w, quaternion.x, quaternion.y, quaternion.z
+        ),
+        CubeEvent::Sensor(SensorInformation::MagneticSensor(magnetic)) => format!(
+            "MagneticSensor state={} strength={} x={} y={} z={}",
+            magnetic.state, magnetic.strength, magnetic.x, magnetic.y, magnetic.z
+        ),
+        CubeEvent::Motor(MotorInformation::MotorControlTarget(response)) => format!(
+            "TargetPositionResponse id={} {:?}",
+            response.request_id.id, response.response_code
+        ),
+        CubeEvent::Motor(MotorInformation::MotorControlMultipleTargets(response)) => format!(
+            "MultiTargetPositionsResponse id={} {:?}",
+            response.request_id.id, response.response_code
+        ),
+        CubeEvent::Motor(MotorInformation::MotorSpeed(speed)) => {
+            format!("MotorSpeed left={} right={}", speed.left, speed.right)
+        }
+        CubeEvent::Button(button) => format!("Button {:?}", button.state),
+        CubeEvent::Battery(battery) => format!("Battery {}%", battery.level),
+        CubeEvent::Configuration(configuration) => describe_configuration_response(configuration),
+        CubeEvent::Unknown { .. } => return None,
+    };
+    Some(description)
+}
+
+fn describe_configuration_response(configuration: &ConfigurationInformation) -> String {
+    let (name, succeeded) = match configuration {
+        ConfigurationInformation::BleProtocolVersion(version) => {
+            return format!(
+                "BleProtocolVersionResponse {}",
+                String::from_utf8_lossy(&version.version).trim_end_matches('\0')
+            );
+        }
+        ConfigurationInformation::IdSensorNotification(response) => {
+            ("IdSensorNotificationResponse", response.result)
+        }
+        ConfigurationInformation::IdSensorMissedNotification(response) => {
+            ("IdSensorMissedNotificationResponse", response.result)
+        }
+        ConfigurationInformation::MagneticSensor(response) => {
+            ("MagneticSensorResponse", response.result)
+        }
+        ConfigurationInformation::MotorSpeed(response) => ("MotorSpeedResponse", response.result),
+        ConfigurationInformation::PostureAngleDetection(response) => {
+            ("PostureAngleDetectionResponse", response.result)
+        }
+    };
+    format!("{} {}", name, if succeeded { "ok" } else { "failed" })
+}
+
+fn describe_command(characteristic: CoreCubeUuid, bytes: &[u8]) -> Option<String> {
+    match characteristic {
+        CoreCubeUuid::MotorCtrl => describe_motor_command(bytes),
+        CoreCubeUuid::LightCtrl => describe_light_command(bytes),
+        CoreCubeUuid::SoundCtrl => describe_sound_command(bytes),
+        CoreCubeUuid::SensorInfo => describe_sensor_command(bytes),
+        CoreCubeUuid::Config => describe_configuration_command(bytes),
+        _ => None,
+    }
+}
+
+fn describe_motor_command(bytes: &[u8]) -> Option<String> {
+    if let Some(command) = MotorControl::from_payload(bytes) {
+        return Some(format!(
+            "Run left={} right={}",
+            command.left.velocity.value(),
+            command.right.velocity.value()
+        ));
+    }
+    if let Some(command) = MotorControlWithSpecifiedDuration::from_payload(bytes) {
+        return Some(format!(
+            "Period left={} right={} duration={}",
+            command.left.velocity.value(),
+            command.right.velocity.value(),
+            period(command.period)
+        ));
+    }
+    if let Some(command) = MotorControlTarget::from_payload(bytes) {
+        return Some(format!(
+            "TargetPosition id={} timeout={} {:?} {} -> {}",
+            command.id.id,
+            timeout(command.timeout),
+            command.movement_type,
+            speed(command.speed),
+            target(&command.target)
+        ));
+    }
+    if let Some(command) = MotorControlMultipleTargets::from_payload(bytes) {
+        let targets: Vec<String> = command.target_list.iter().map(target).collect();
+        return Some(format!(
+            "MultiTargetPositions id={} timeout={} {:?} {} {:?} -> {}",
+            command.id.id,
+            timeout(command.timeout),
+            command.movement_type,
+            speed(command.speed),
+            command.write_mode,
+            targets.join(", ")
+        ));
+    }
+    if let Some(command) = MotorControlAcceleration::from_payload(bytes) {
+        return Some(format!(
+            "Acceleration speed={} acceleration={} rotation={}°/s {:?} {:?} priority={:?} duration={}",
+            command.acceleration.translational_speed,
+            command.acceleration.acceleration,
+            command.angle_velocity.rotation_velocity,
+            command.angle_velocity.rotation_direction,
+            command.moving_direction,
+            command.priority,
+            period(command.period)
+        ));
+    }
+    if bytes == [u8::from(motor::CommandId::ObtainSpeed)] {
+        return Some("ObtainSpeed".to_string());
+    }
+    None
+}
+
+fn describe_light_command(bytes: &[u8]) -> Option<String> {
+    if let Some(command) = TurningOnAndOff::from_payload(bytes) {
+        return Some(format!("TurningOnAndOff {}", indicator(&command.param)));
+    }
+    if let Some(command) = RepeatedTuringOnAndOff::from_payload(bytes) {
+        let params: Vec<String> = command.param_list.iter().map(indicator).collect();
+        return Some(format!(
+            "RepeatedTurningOnAndOff repeat={} [{}]",
+            command.repeat,
+            params.join(", ")
+        ));
+    }
+    if TurnOffAll::from_payload(bytes).is_some() {
+        return Some("TurnOffAll".to_string());
+    }
+    if let Some(command) = TurnOff::from_payload(bytes) {
+        return Some(format!("TurnOff id={}", command.id));
+    }
+    None
+}
+
+fn describe_sound_command(bytes: &[u8]) -> Option<String> {
+    if let Some(command) = PlaySoundEffect::from_payload(bytes) {
+        return Some(format!(
+            "PlaySoundEffect {:?} volume={}",
+            command.id, command.volume
+        ));
+    }
+    if let Some(command) = PlayMidiNote::from_payload(bytes) {
+        let notes: Vec<String> = command
+            .midi_notes
+            .iter()
+            .map(|note| {
+                format!(
+                    "{:?} {} volume={}",
+                    note.note,
+                    period(note.duration),
+                    note.volume
+                )
+            })
+            .collect();
+        return Some(format!(
+            "PlayMidiNote repeat={} [{}]",
+            command.repeat,
+            notes.join(", ")
+        ));
+    }
+    if StopSound::from_payload(bytes).is_some() {
+        return Some("StopSound".to_string());
+    }
+    None
+}
+
+fn describe_sensor_command(bytes: &[u8]) -> Option<String> {
+    if RequestMotionDetection::from_payload(bytes).is_some() {
+        return Some("RequestMotionDetection".to_string());
+    }
+    if RequestMagneticSensor::from_payload(bytes).is_some() {
+        return Some("RequestMagneticSensor".to_string());
+    }
+    if let Some(command) = RequestPostureAngleDetection::from_payload(bytes) {
+        return Some(format!(
+            "RequestPostureAngleDetection {:?}",
+            command.data_type
+        ));
+    }
+    None
+}
+
+fn describe_configuration_command(bytes: &[u8]) -> Option<String> {
+    if RequestBleProtocolVersion::from_payload(bytes).is_some() {
+        return Some("RequestBleProtocolVersion".to_string());
+    }
+    if let Some(command) = SetHorizontalDetectionThreshold::from_payload(bytes) {
+        return Some(format!(
+            "SetHorizontalDetectionThreshold threshold={}",
+            command.threshold
+        ));
+    }
+    if let Some(command) = SetCollisionDetectionThreshold::from_payload(bytes) {
+        return Some(format!(
+            "SetCollisionDetectionThreshold threshold={}",
+            command.threshold
+        ));
+    }
+    if let Some(command) = SetDoubleTapDetectionTimeInterval::from_payload(bytes) {
+        return Some(format!(
+            "SetDoubleTapDetectionTimeInterval interval={}",
+            command.interval
+        ));
+    }
+    if let Some(command) = SetIdSensorNotification::from_payload(bytes) {
+        return Some(format!(
+            "SetIdSensorNotification minimum_interval={} {:?}",
+            command.minimum_interval, command.condition
+        ));
+    }
+    if let Some(command) = SetIdSensorMissedNotification::from_payload(bytes) {
+        return Some(format!(
+            "SetIdSensorMissedNotification sensitivity={}",
+            command.sensitivity
+        ));
+    }
+    if let Some(command) = SetMagneticSensor::from_payload(bytes) {
+        return Some(format!(
+            "SetMagneticSensor {:?} interval={} {:?}",
+            command.function_type, command.interval, command.condition
+        ));
+    }
+    if let Some(command) = EnableMotorSpeed::from_payload(bytes) {
+        return Some(format!("EnableMotorSpeed enable={}", command.enable));
+    }
+    if let Some(command) = SetPostureAngleDetection::from_payload(bytes) {
+        return Some(format!(
+            "SetPostureAngleDetection {:?} interval={} {:?}",
+            command.data_type, command.interval, command.condition
+        ));
+    }
+    None
+}
+
+fn location(location: &CubeLocation) -> String {
+    format!(
+        "({},{}) {}°",
+        location.point.x, location.point.y, location.angle
+    )
+}
+
+fn target(target: &TargetPosition) -> String {
+    format!(
+        "{} {:?}",
+        location(&target.cube_location),
+        target.rotation_option
+    )
+}
+
+fn speed(speed: Speed) -> String {
+    match speed.speed_change_type {
+        SpeedChangeType::Constant => format!("speed={}", speed.max),
+        change => format!("speed={} {:?}", speed.max, change),
+    }
+}
+
+fn timeout(timeout: Timeout) -> String {
+    match timeout {
+        Timeout::Second(second) => format!("{}s", second),
+    }
+}
+
+fn period(period: Period) -> String {
+    match period.period {
+        0 => "forever".to_string(),
+        period => format!("{}ms", usize::from(period) * 10),
+    }
+}
+
+fn indicator(param: &IndicatorParam) -> String {
+    format!("{} {}", period(param.duration), color(param.color))
+}
+
+fn color(color: Color) -> String {
+    format!("#{:02x}{:02x}{:02x}", color.r, color.g, color.b)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::characteristic::motor::{MovementType, RequestId, RotationOption};
+    use crate::characteristic::sound::{MidiNote, Note};
+    use crate::payload::{test_rng, ToPayload};
+    use crate::position::Point;
+    use rand::Rng;
+
+    fn _setup() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    #[test]
+    fn describe_command1() {
+        _setup();
+        let command = MotorControlTarget {
+            movement_type: MovementType::Linear,
+            speed: Speed {
+                max: 50,
+                speed_change_type: SpeedChangeType::Constant,
+            },
+            target: TargetPosition {
+                cube_location: CubeLocation {
+                    point: Point { x: 360, y: 170 },
+                    angle: 180,
+                },
+                rotation_option: RotationOption::AbsoluteOptimal,
+            },
+            ..MotorControlTarget::new(RequestId::received(3))
+        };
+        assert_eq!(
+            describe(CoreCubeUuid::MotorCtrl.uuid(), &command.to_payload()),
+            "MotorCtrl: TargetPosition id=3 timeout=0s Linear speed=50 -> (360,170) 180° AbsoluteOptimal"
+        );
+        assert_eq!(
+            describe(
+                CoreCubeUuid::MotorCtrl.uuid(),
+                &MotorControl::set_value(10, -20).unwrap().to_payload()
+            ),
+            "MotorCtrl: Run left=10 right=-20"
+        );
+
+        let command = PlayMidiNote {
+            midi_notes: vec![MidiNote {
+                volume: 0xff,
+                duration: Period::from_millis(500),
+                note: Note::C5,
+            }],
+            ..PlayMidiNote::default()
+        };
+        assert_eq!(
+            describe(CoreCubeUuid::SoundCtrl.uuid(), &command.to_payload()),
+            "SoundCtrl: PlayMidiNote repeat=1 [C5 500ms volume=255]"
+        );
+        assert_eq!(
+            describe(
+                CoreCubeUuid::Config.uuid(),
+                &EnableMotorSpeed::new(true).to_payload()
+            ),
+            "Config: EnableMotorSpeed enable=true"
+        );
+    }
+
+    #[test]
+    fn describe_notification1() {
+        _setup();
+        assert_eq!(
+            describe(CoreCubeUuid::BatteryInfo.uuid(), &[80]),
+            "BatteryInfo: Battery 80%"
+        );
+        assert_eq!(
+            describe(CoreCubeUuid::MotorCtrl.uuid(), &[0x83, 0x03, 0x00]),
+            "MotorCtrl: TargetPositionResponse id=3 Success"
+        );
+        assert_eq!(
+            describe(
+                CoreCubeUuid::IdInfo.uuid(),
+                &[0x01, 0x68, 0x01, 0xaa, 0x00, 0xb4, 0x00, 0x6a, 0x01, 0xaa, 0x00, 0xb4, 0x00]
+            ),
+            "IdInfo: PositionId center=(360,170) 180° sensor=(362,170) 180°"
+        );
+        assert_eq!(
+            describe(
+                CoreCubeUuid::Config.uuid(),
+                &[0x81, 0x00, b'2', b'.', b'4', b'.', b'0']
+            ),
+            "Config: BleProtocolVersionResponse 2.4.0"
+        );
+    }
+
+    #[test]
+    fn describe_unknown1() {
+        _setup();
+        assert_eq!(
+            describe(CoreCubeUuid::LightCtrl.uuid(), &[0x7f, 0x01]),
+            "LightCtrl: [7f, 01]"
+        );
+        assert_eq!(
+            describe(Uuid::nil(), &[0x01]),
+            "00000000-0000-0000-0000-000000000000: [01]"
+        );
+    }
+
+    #[test]
+    fn describe_random_payload1() {
+        _setup();
+
+        let characteristics = [
+            CoreCubeUuid::IdInfo,
+            CoreCubeUuid::SensorInfo,
+            CoreCubeUuid::ButtonInfo,
+            CoreCubeUuid::BatteryInfo,
+            CoreCubeUuid::MotorCtrl,
+            CoreCubeUuid::LightCtrl,
+            CoreCubeUuid::SoundCtrl,
+            CoreCubeUuid::Config,
+        ];
+        let mut rng = test_rng();
+        for _ in 0..10000 {
+            let length = rng.gen_range(0..24);
+            let bytes: Vec<u8> = (0..length).map(|_| rng.gen_range(0..4)).collect();
+            for characteristic in characteristics {
+                let description = describe(characteristic.uuid(), &bytes);
+                assert!(description.starts_with(characteristic.name()));
+            }
+        }
+    }
+}
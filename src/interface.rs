@@ -1,54 +1,397 @@
 pub mod ble;
+pub mod recorder;
+pub mod replay;
+pub mod virtual_cube;
 
-use crate::characteristic::NotificationData;
-use crate::notification_manager::HandlerFunction;
+use self::ble::AdapterInfo;
+use crate::api::PendingResponse;
+use crate::characteristic::battery::BatteryInformation;
+use crate::characteristic::button::ButtonInformation;
+use crate::characteristic::configuration::{
+    CommandId as ConfigurationCommandId, ConfigurationInformation, RequestBleProtocolVersion,
+    ResponseBleProtocolVersionData,
+};
+use crate::characteristic::id::IdInformation;
+use crate::characteristic::motor::RequestIdAllocator;
+use crate::characteristic::sensor::SensorInformation;
+use crate::characteristic::{CoreCubeUuid, CubeEvent, NotificationData};
+use crate::notification_manager::{
+    DeliveryPolicy, HandlerFunction, NotificationFilter, NotificationManager, NotificationReceiver,
+};
+use crate::payload::{FromPayload, ToPayload};
+use crate::CoreCubeError;
 use async_trait::async_trait;
+use futures::stream::{Stream, StreamExt};
+use log::{debug, error};
+use std::collections::VecDeque;
 use std::future::Future;
 use std::pin::Pin;
+use std::sync::Arc;
 use std::time::Duration;
 use std::vec::Vec;
+use tokio::sync::mpsc;
 use uuid::Uuid;
 
 use btleplug::api::BDAddr;
 
+/// Stream of raw notifications
+pub type NotificationStream = Pin<Box<dyn Stream<Item = NotificationData> + Send>>;
+
+/// Stream of decoded notifications
+pub type CubeEventStream = Pin<Box<dyn Stream<Item = CubeEvent> + Send>>;
+
+/// Options of `CubeInterface::connect_with()`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConnectOptions {
+    /// characteristics to subscribe (default: all characteristics which notify)
+    pub notifications: Vec<CoreCubeUuid>,
+}
+
+impl Default for ConnectOptions {
+    fn default() -> Self {
+        Self {
+            notifications: NOTIFY_CHARACTERISTICS.to_vec(),
+        }
+    }
+}
+
+impl ConnectOptions {
+    pub fn new(notifications: &[CoreCubeUuid]) -> Self {
+        Self {
+            notifications: notifications.to_vec(),
+        }
+    }
+}
+
+/// characteristics which have NOTIFY property
+pub const NOTIFY_CHARACTERISTICS: [CoreCubeUuid; 6] = [
+    CoreCubeUuid::IdInfo,
+    CoreCubeUuid::SensorInfo,
+    CoreCubeUuid::ButtonInfo,
+    CoreCubeUuid::BatteryInfo,
+    CoreCubeUuid::MotorCtrl,
+    CoreCubeUuid::Config,
+];
+
+/// Control message of the notification receiver
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum CoreCubeNotificationControl {
+    /// dispatch notifications (and the notifications kept while paused)
     Run,
+    /// stop dispatching notifications (see `PausePolicy`)
     Pause,
+    /// finish the receiver
     Quit,
 }
 
+/// Notifications received while the receiver is paused
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PausePolicy {
+    Drop,
+    /// keep the latest notifications up to the capacity and dispatch them when resumed
+    Buffer(usize),
+}
+
+/// receiver which runs the raw receiver created by `create` following the control messages
+///
+/// The raw receiver passes the notifications through a channel of one notification, and
+/// they are dispatched to `nf_manager` inline. So the raw receiver waits while a handler
+/// waits (e.g. the full channel of `DeliveryPolicy::Block`), instead of buffering notifications.
+fn controlled_receiver(
+    create: impl FnOnce(
+        Arc<NotificationManager<NotificationData>>,
+    ) -> Pin<Box<dyn Future<Output = ()> + Send>>,
+    nf_manager: Arc<NotificationManager<NotificationData>>,
+    control: mpsc::Receiver<CoreCubeNotificationControl>,
+    pause: PausePolicy,
+) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+    let raw_manager = Arc::new(NotificationManager::<NotificationData>::new());
+    let notifications =
+        match raw_manager.register_channel(NotificationFilter::All, 1, DeliveryPolicy::Block) {
+            Ok((_, notifications)) => notifications,
+            Err(err) => {
+                error!("notification handler: {}", err);
+                return Box::pin(async {});
+            }
+        };
+    // the channel is closed with raw_manager when the raw receiver is finished
+    let receiver = create(raw_manager);
+    Box::pin(run_with_control(
+        receiver,
+        notifications,
+        nf_manager,
+        control,
+        pause,
+    ))
+}
+
+/// dispatch notifications from the raw receiver following the control messages
+async fn run_with_control(
+    receiver: Pin<Box<dyn Future<Output = ()> + Send>>,
+    mut notifications: NotificationReceiver<NotificationData>,
+    nf_manager: Arc<NotificationManager<NotificationData>>,
+    mut control: mpsc::Receiver<CoreCubeNotificationControl>,
+    pause: PausePolicy,
+) {
+    // the raw receiver is driven here (not spawned), so it stops when this future is dropped
+    let mut receiver = receiver;
+    let mut receiver_running = true;
+    let mut running = true;
+    let mut control_open = true;
+    let mut paused: VecDeque<NotificationData> = VecDeque::new();
+    loop {
+        tokio::select! {
+            _ = &mut receiver, if receiver_running => receiver_running = false,
+            command = control.recv(), if control_open => match command {
+                Some(CoreCubeNotificationControl::Run) => {
+                    running = true;
+                    while let Some(data) = paused.pop_front() {
+                        let _ = nf_manager.dispatch(data).await;
+                    }
+                }
+                Some(CoreCubeNotificationControl::Pause) => running = false,
+                Some(CoreCubeNotificationControl::Quit) => break,
+                // nobody controls the receiver any more
+                None => control_open = false,
+            },
+            data = notifications.recv() => match data {
+                Some(data) if running => {
+                    let _ = nf_manager.dispatch(data).await;
+                }
+                Some(data) => {
+                    if let PausePolicy::Buffer(capacity) = pause {
+                        if paused.len() >= capacity {
+                            paused.pop_front();
+                        }
+                        if capacity > 0 {
+                            paused.push_back(data);
+                        }
+                    }
+                }
+                // the receiver is finished (e.g. the cube is disconnected)
+                None => break,
+            },
+        }
+    }
+    debug!("notification receiver is finished");
+}
+
 #[async_trait]
 pub trait CubeInterface {
-    async fn connect(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>>;
+    // connect and subscribe to all characteristics which notify
+    async fn connect(&mut self) -> Result<(), CoreCubeError> {
+        self.connect_with(&ConnectOptions::default()).await
+    }
 
-    async fn disconnect(
-        &mut self,
-    ) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>>;
+    // connect and subscribe to the characteristics listed in the options
+    async fn connect_with(&mut self, options: &ConnectOptions) -> Result<(), CoreCubeError>;
+
+    async fn disconnect(&mut self) -> Result<(), CoreCubeError>;
 
     // read data from specified characteristic
-    async fn read(
-        &self,
-        uuid: Uuid,
-    ) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync + 'static>>;
+    async fn read(&self, uuid: Uuid) -> Result<Vec<u8>, CoreCubeError>;
 
     // write data to specified characteristic (without response)
-    async fn write(
-        &self,
-        uuid: Uuid,
-        bytes: &[u8],
-    ) -> Result<bool, Box<dyn std::error::Error + Send + Sync + 'static>>;
+    async fn write(&self, uuid: Uuid, bytes: &[u8]) -> Result<bool, CoreCubeError>;
 
     // write data to specified characteristic (with response)
-    async fn write_with_response(
-        &self,
-        uuid: Uuid,
-        bytes: &[u8],
-    ) -> Result<bool, Box<dyn std::error::Error + Send + Sync + 'static>>;
+    async fn write_with_response(&self, uuid: Uuid, bytes: &[u8]) -> Result<bool, CoreCubeError>;
+
+    // start notification of the characteristic
+    async fn subscribe(&self, characteristic: CoreCubeUuid) -> Result<(), CoreCubeError>;
+
+    // stop notification of the characteristic
+    async fn unsubscribe(&self, characteristic: CoreCubeUuid) -> Result<(), CoreCubeError>;
 
+    // characteristics subscribed now
+    fn notification_enabled(&self) -> Vec<CoreCubeUuid>;
+
+    // (the handlers are boxed for compatibility)
+    #[allow(clippy::boxed_local)]
     fn create_notification_receiver(
         &self,
         handlers: Box<Vec<HandlerFunction<NotificationData>>>,
+    ) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        self.create_filtered_notification_receiver(
+            handlers
+                .into_iter()
+                .map(|handler| (NotificationFilter::All, handler))
+                .collect(),
+        )
+    }
+
+    // each handler receives only the notifications which match its filter
+    fn create_filtered_notification_receiver(
+        &self,
+        handlers: Vec<(NotificationFilter, HandlerFunction<NotificationData>)>,
+    ) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        // nobody sends control messages, so the receiver runs until the cube is disconnected
+        let (_, control) = mpsc::channel(1);
+        self.create_controlled_notification_receiver(handlers, control, PausePolicy::Drop)
+    }
+
+    // receiver which is controlled by the messages (handlers are unregistered when it finishes)
+    fn create_controlled_notification_receiver(
+        &self,
+        handlers: Vec<(NotificationFilter, HandlerFunction<NotificationData>)>,
+        control: mpsc::Receiver<CoreCubeNotificationControl>,
+        pause: PausePolicy,
+    ) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        let nf_manager = Arc::new(NotificationManager::<NotificationData>::new());
+        let mut registered_handlers: Vec<Uuid> = vec![];
+        for (filter, notification_handler) in handlers {
+            match nf_manager.register_with_filter(notification_handler, filter) {
+                Ok(handler_uuid) => registered_handlers.push(handler_uuid),
+                Err(err) => error!("notification handler: {}", err),
+            }
+        }
+        let receiver =
+            self.create_notification_receiver_with_control(nf_manager.clone(), control, pause);
+        Box::pin(async move {
+            receiver.await;
+            for handler_uuid in registered_handlers {
+                if let Err(err) = nf_manager.unregister(handler_uuid) {
+                    error!("notification handler: {}", err);
+                }
+            }
+        })
+    }
+
+    // receiver of the shared manager which is controlled by the messages
+    //
+    // It finishes by `CoreCubeNotificationControl::Quit` or when the cube is disconnected.
+    fn create_notification_receiver_with_control(
+        &self,
+        nf_manager: Arc<NotificationManager<NotificationData>>,
+        control: mpsc::Receiver<CoreCubeNotificationControl>,
+        pause: PausePolicy,
+    ) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        controlled_receiver(
+            |raw_manager| self.create_notification_receiver_with_manager(raw_manager),
+            nf_manager,
+            control,
+            pause,
+        )
+    }
+
+    // dispatch notifications to the handlers of the shared manager
+    //
+    // Handlers can be registered to (and unregistered from) the manager while the receiver runs.
+    fn create_notification_receiver_with_manager(
+        &self,
+        nf_manager: Arc<NotificationManager<NotificationData>>,
     ) -> Pin<Box<dyn Future<Output = ()> + Send>>;
+
+    // request ID sequence of this cube
+    fn request_id_allocator(&self) -> RequestIdAllocator;
+
+    // Bluetooth adapter which found this cube (None: the cube is not found by a scanner)
+    fn adapter(&self) -> Option<AdapterInfo> {
+        None
+    }
+
+    // notifications from all subscribed characteristics (ends when the cube is disconnected)
+    async fn notification_stream(&self) -> Result<NotificationStream, CoreCubeError>;
+
+    // notifications decoded by the characteristic
+    async fn event_stream(&self) -> Result<CubeEventStream, CoreCubeError> {
+        let notification_stream = self.notification_stream().await?;
+        Ok(Box::pin(notification_stream.map(CubeEvent::from)))
+    }
+
+    // read ID information
+    async fn read_id(&self) -> Result<IdInformation, CoreCubeError> {
+        read_as(
+            self.read(CoreCubeUuid::IdInfo.uuid()).await?,
+            CoreCubeUuid::IdInfo,
+        )
+    }
+
+    // read motion detection information
+    async fn read_sensor(&self) -> Result<SensorInformation, CoreCubeError> {
+        read_as(
+            self.read(CoreCubeUuid::SensorInfo.uuid()).await?,
+            CoreCubeUuid::SensorInfo,
+        )
+    }
+
+    // read button state
+    async fn read_button(&self) -> Result<ButtonInformation, CoreCubeError> {
+        read_as(
+            self.read(CoreCubeUuid::ButtonInfo.uuid()).await?,
+            CoreCubeUuid::ButtonInfo,
+        )
+    }
+
+    // read battery level
+    async fn read_battery(&self) -> Result<BatteryInformation, CoreCubeError> {
+        read_as(
+            self.read(CoreCubeUuid::BatteryInfo.uuid()).await?,
+            CoreCubeUuid::BatteryInfo,
+        )
+    }
+
+    // write configuration command which has a response
+    // (the returned response resolves with it; commands which have no response are WrongParameter)
+    async fn request_configuration(
+        &self,
+        bytes: &[u8],
+    ) -> Result<PendingResponse<ConfigurationInformation>, CoreCubeError> {
+        let command = configuration_command(bytes)?;
+        if !command.has_response() {
+            return Err(CoreCubeError::WrongParameter);
+        }
+        let events = self.event_stream().await?;
+        self.write(CoreCubeUuid::Config.uuid(), bytes).await?;
+        Ok(PendingResponse::new(
+            events,
+            Box::new(move |event| match event {
+                CubeEvent::Configuration(response) if u8::from(*response) == command.response() => {
+                    Some(*response)
+                }
+                _ => None,
+            }),
+        ))
+    }
+
+    // write configuration command which has no response
+    // (commands which have a response are WrongParameter, use request_configuration)
+    async fn write_configuration(&self, bytes: &[u8]) -> Result<(), CoreCubeError> {
+        if configuration_command(bytes)?.has_response() {
+            return Err(CoreCubeError::WrongParameter);
+        }
+        self.write(CoreCubeUuid::Config.uuid(), bytes).await?;
+        Ok(())
+    }
+
+    // read BLE protocol version
+    async fn read_ble_protocol_version(
+        &self,
+    ) -> Result<ResponseBleProtocolVersionData, CoreCubeError> {
+        let request = RequestBleProtocolVersion::new().to_payload();
+        match self.request_configuration(&request).await?.await? {
+            ConfigurationInformation::BleProtocolVersion(version) => Ok(version),
+            _ => Err(CoreCubeError::FoundBug),
+        }
+    }
+}
+
+/// command ID of the configuration command (unknown commands are WrongParameter)
+fn configuration_command(bytes: &[u8]) -> Result<ConfigurationCommandId, CoreCubeError> {
+    bytes
+        .first()
+        .and_then(|command| ConfigurationCommandId::try_from(*command).ok())
+        .ok_or(CoreCubeError::WrongParameter)
+}
+
+fn read_as<T>(data: Vec<u8>, characteristic: CoreCubeUuid) -> Result<T, CoreCubeError>
+where
+    T: for<'a> FromPayload<&'a [u8]>,
+{
+    T::from_payload(&data).ok_or(CoreCubeError::Decode {
+        uuid: characteristic.uuid(),
+        data,
+    })
 }
 
 #[async_trait]
@@ -57,26 +400,94 @@ pub trait CubeScanner {
         &self,
         num: usize,
         wait: Duration,
-    ) -> Result<
-        Vec<Box<dyn CubeInterface + Send + Sync + 'static>>,
-        Box<dyn std::error::Error + Send + Sync + 'static>,
-    >;
+    ) -> Result<Vec<Box<dyn CubeInterface + Send + Sync + 'static>>, CoreCubeError>;
 
     async fn scan_with_address(
         &self,
         address_list: &[BDAddr],
         wait: Duration,
-    ) -> Result<
-        Vec<Box<dyn CubeInterface + Send + Sync + 'static>>,
-        Box<dyn std::error::Error + Send + Sync + 'static>,
-    >;
+    ) -> Result<Vec<Box<dyn CubeInterface + Send + Sync + 'static>>, CoreCubeError>;
 
     async fn scan_with_name(
         &self,
         name_list: &[&str],
         wait: Duration,
-    ) -> Result<
-        Vec<Box<dyn CubeInterface + Send + Sync + 'static>>,
-        Box<dyn std::error::Error + Send + Sync + 'static>,
-    >;
+    ) -> Result<Vec<Box<dyn CubeInterface + Send + Sync + 'static>>, CoreCubeError>;
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::sync::oneshot;
+
+    fn _setup() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    #[tokio::test]
+    async fn run_with_control_abort1() {
+        _setup();
+
+        // the raw receiver holds `alive` until it is dropped
+        let (alive, stopped) = oneshot::channel::<()>();
+        let receiver: Pin<Box<dyn Future<Output = ()> + Send>> = Box::pin(async move {
+            let _alive = alive;
+            futures::future::pending::<()>().await;
+        });
+        let (_control_sender, control) = mpsc::channel(1);
+        let task = tokio::spawn(controlled_receiver(
+            |_| receiver,
+            Arc::new(NotificationManager::new()),
+            control,
+            PausePolicy::Drop,
+        ));
+        tokio::task::yield_now().await;
+
+        task.abort();
+        let stopped = tokio::time::timeout(Duration::from_secs(1), stopped).await;
+        assert!(matches!(stopped, Ok(Err(_))));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn run_with_control_backpressure1() {
+        _setup();
+
+        // the source counts the notifications which the raw receiver has taken
+        let sent = Arc::new(AtomicUsize::new(0));
+        let sent_by_source = sent.clone();
+        let create = move |raw_manager: Arc<NotificationManager<NotificationData>>| {
+            Box::pin(async move {
+                for level in 0..100 {
+                    let data = NotificationData {
+                        uuid: CoreCubeUuid::BatteryInfo.uuid(),
+                        value: vec![level],
+                    };
+                    raw_manager.dispatch(data).await.unwrap();
+                    sent_by_source.fetch_add(1, Ordering::SeqCst);
+                }
+            }) as Pin<Box<dyn Future<Output = ()> + Send>>
+        };
+        let nf_manager = Arc::new(NotificationManager::new());
+        let (_, mut blocked) = nf_manager
+            .register_channel(NotificationFilter::All, 1, DeliveryPolicy::Block)
+            .unwrap();
+        let (_control_sender, control) = mpsc::channel(1);
+        let task = tokio::spawn(controlled_receiver(
+            create,
+            nf_manager,
+            control,
+            PausePolicy::Drop,
+        ));
+
+        // nobody takes notifications from the handler, so the source stalls
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        assert!(sent.load(Ordering::SeqCst) <= 3);
+
+        for level in 0..100 {
+            assert_eq!(blocked.recv().await.unwrap().value, vec![level]);
+        }
+        task.await.unwrap();
+        assert_eq!(sent.load(Ordering::SeqCst), 100);
+    }
 }
@@ -1,24 +1,37 @@
+mod reconnect;
+mod scan;
+
+pub use self::reconnect::{ConnectionState, ReconnectPolicy};
+pub use self::scan::{
+    sort_by_rssi, spread_across_adapters, AdapterInfo, AdapterSelector, DiscoveredCube,
+    DiscoveryFilter, NameFilter, ScanStream,
+};
+
+use self::reconnect::{BleSession, WeakBleSession};
+use crate::characteristic::motor::RequestIdAllocator;
 use crate::characteristic::CoreCubeUuid;
 use crate::characteristic::NotificationData;
-use crate::notification_manager::{HandlerFunction, NotificationManager};
+use crate::describe::describe;
+use crate::notification_manager::NotificationManager;
 use crate::CoreCubeError;
 use async_trait::async_trait;
 use btleplug::api::{
-    BDAddr, Central, CharPropFlags, Characteristic, Manager as _, Peripheral as _, ScanFilter,
+    BDAddr, Central, CentralEvent, CharPropFlags, Characteristic, Manager as _, Peripheral as _,
     WriteType,
 };
 use btleplug::platform::{Manager, Peripheral};
 use futures::stream::StreamExt;
-use log::{debug, error, info};
+use log::{debug, error, info, warn};
 use std::collections::HashMap;
 use std::future::Future;
 use std::pin::Pin;
+use std::sync::Arc;
 use std::time::Duration;
 use std::vec::Vec;
 use tokio::time;
 use uuid::Uuid;
 
-use super::{CubeInterface, CubeScanner};
+use super::{ConnectOptions, CubeInterface, CubeScanner, NotificationStream};
 
 type BleInterface = Peripheral;
 
@@ -26,7 +39,12 @@ type BleInterface = Peripheral;
 pub struct BleCube {
     pub ble_peripheral: BleInterface,
     pub ble_characteristics: HashMap<Uuid, Characteristic>,
-    pub notification_enabled: Vec<Uuid>,
+    pub request_id_allocator: RequestIdAllocator,
+    /// reconnect automatically when the link is lost (`None`: no reconnection)
+    pub reconnect: Option<ReconnectPolicy>,
+    session: BleSession,
+    /// adapter which found this cube (`None` if it is not found by the scanner)
+    adapter: Option<AdapterInfo>,
 }
 
 impl BleCube {
@@ -34,282 +52,486 @@ impl BleCube {
         Self {
             ble_peripheral: peripheral,
             ble_characteristics: HashMap::new(),
-            notification_enabled: Vec::new(),
+            request_id_allocator: RequestIdAllocator::new(),
+            reconnect: Some(ReconnectPolicy::default()),
+            session: BleSession::default(),
+            adapter: None,
+        }
+    }
+
+    pub fn connection_state(&self) -> ConnectionState {
+        self.session.state()
+    }
+
+    fn characteristic(&self, uuid: Uuid) -> Result<&Characteristic, CoreCubeError> {
+        find_characteristic(&self.ble_characteristics, uuid)
+    }
+}
+
+fn find_characteristic(
+    characteristics: &HashMap<Uuid, Characteristic>,
+    uuid: Uuid,
+) -> Result<&Characteristic, CoreCubeError> {
+    if characteristics.is_empty() {
+        return Err(CoreCubeError::NotConnected);
+    }
+    characteristics
+        .get(&uuid)
+        .ok_or(CoreCubeError::CharacteristicNotFound(uuid))
+}
+
+/// restore the connection: re-subscribe and replay the last configuration commands
+async fn restore(
+    peripheral: &Peripheral,
+    characteristics: &HashMap<Uuid, Characteristic>,
+    session: &BleSession,
+) -> Result<(), CoreCubeError> {
+    peripheral.connect().await?;
+    peripheral.discover_services().await?;
+    for notified in session.notification_enabled() {
+        debug!("enable notification uuid: {:?}", notified);
+        peripheral
+            .subscribe(find_characteristic(characteristics, notified)?)
+            .await?;
+    }
+    for command in session.configuration() {
+        let configuration = find_characteristic(characteristics, CoreCubeUuid::Config.uuid())?;
+        debug!(
+            "replay configuration: {}",
+            describe(CoreCubeUuid::Config.uuid(), &command)
+        );
+        peripheral
+            .write(configuration, &command, WriteType::WithResponse)
+            .await?;
+    }
+    Ok(())
+}
+
+/// watch the central events and reconnect when the cube is disconnected
+///
+/// The supervisor holds only a weak session, so it does not keep the cube alive
+/// (the session aborts it when the last clone of the cube is dropped).
+async fn supervise(
+    peripheral: Peripheral,
+    characteristics: HashMap<Uuid, Characteristic>,
+    session: WeakBleSession,
+    policy: ReconnectPolicy,
+) -> Result<(), CoreCubeError> {
+    let manager = Manager::new().await?;
+    let mut central_events = Vec::new();
+    for adapter in manager.adapters().await? {
+        central_events.push(adapter.events().await?);
+    }
+    let mut central_events = futures::stream::select_all(central_events);
+    let id = peripheral.id();
+    while let Some(event) = central_events.next().await {
+        match event {
+            CentralEvent::DeviceDisconnected(disconnected) if disconnected == id => {}
+            _ => continue,
+        }
+        let Some(current) = session.upgrade() else {
+            break;
+        };
+        if current.state() == ConnectionState::Disconnected {
+            break;
+        }
+        warn!("connection lost: {:?}", id);
+        current.set_state(ConnectionState::Reconnecting);
+        drop(current);
+        let mut attempt = 0;
+        loop {
+            let Some(delay) = policy.delay(attempt) else {
+                error!("give up reconnecting: {:?}", id);
+                if let Some(current) = session.upgrade() {
+                    current.set_state(ConnectionState::Disconnected);
+                }
+                return Ok(());
+            };
+            time::sleep(delay).await;
+            let Some(current) = session.upgrade() else {
+                return Ok(());
+            };
+            match restore(&peripheral, &characteristics, &current).await {
+                Ok(()) => {
+                    info!("reconnected: {:?}", id);
+                    current.set_state(ConnectionState::Connected);
+                    break;
+                }
+                Err(err) => warn!("reconnect attempt {} failed: {}", attempt, err),
+            }
+            attempt += 1;
         }
     }
+    Ok(())
 }
 
 pub async fn ble_notification_receiver(
     ble_peripheral: Peripheral,
     notification_manager: &NotificationManager<NotificationData>,
-) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
-    let mut notification_stream = ble_peripheral.notifications().await.unwrap();
+) -> Result<(), CoreCubeError> {
+    let mut notification_stream = ble_peripheral.notifications().await?;
     while let Some(data) = notification_stream.next().await {
-        let _ = notification_manager.invoke_all_handlers(data);
+        let _ = notification_manager.dispatch(data).await;
     }
     Ok(())
 }
 
+/// receive notifications while the session lasts (reopen the stream after reconnection)
+async fn ble_session_receiver(
+    ble_peripheral: Peripheral,
+    notification_manager: &NotificationManager<NotificationData>,
+    mut state: tokio::sync::watch::Receiver<ConnectionState>,
+) {
+    loop {
+        if let Err(err) =
+            ble_notification_receiver(ble_peripheral.clone(), notification_manager).await
+        {
+            warn!("notification stream: {}", err);
+        }
+        // the stream ends when the link is lost; wait for the result of reconnection
+        if state
+            .wait_for(|state| *state != ConnectionState::Connected)
+            .await
+            .is_err()
+        {
+            break;
+        }
+        match state
+            .wait_for(|state| *state != ConnectionState::Reconnecting)
+            .await
+            .map(|state| *state)
+        {
+            Ok(ConnectionState::Connected) => debug!("reopen notification stream"),
+            _ => break,
+        }
+    }
+}
+
 #[async_trait]
 impl CubeInterface for BleCube {
-    async fn connect(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    async fn connect_with(&mut self, options: &ConnectOptions) -> Result<(), CoreCubeError> {
+        self.session.clear_notification_enabled();
         self.ble_peripheral.connect().await?;
-        let is_connected = self.ble_peripheral.is_connected().await?;
-        assert!(is_connected);
+        if !self.ble_peripheral.is_connected().await? {
+            return Err(CoreCubeError::NotConnected);
+        }
         self.ble_peripheral.discover_services().await?;
         for service in self.ble_peripheral.services() {
             for characteristic in service.characteristics {
                 //println!("characteristic uuid: {:?}", characteristic.uuid);
-                if characteristic.properties.contains(CharPropFlags::NOTIFY) {
-                    self.notification_enabled.push(characteristic.uuid);
-                    debug!("enable notification uuid: {:?}", characteristic.uuid);
-                    self.ble_peripheral.subscribe(&characteristic).await?;
-                }
                 self.ble_characteristics
                     .insert(characteristic.uuid, characteristic);
             }
         }
+        for notified in &options.notifications {
+            self.subscribe(*notified).await?;
+        }
+        self.session.set_state(ConnectionState::Connected);
+        if let Some(policy) = self.reconnect {
+            let supervisor = supervise(
+                self.ble_peripheral.clone(),
+                self.ble_characteristics.clone(),
+                self.session.downgrade(),
+                policy,
+            );
+            self.session.start_supervisor(tokio::spawn(async move {
+                if let Err(err) = supervisor.await {
+                    error!("connection supervisor: {}", err);
+                }
+            }));
+        }
         Ok(())
     }
 
-    async fn disconnect(
-        &mut self,
-    ) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
-        for notified in &self.notification_enabled {
+    async fn disconnect(&mut self) -> Result<(), CoreCubeError> {
+        self.session.set_state(ConnectionState::Disconnected);
+        self.session.stop_supervisor();
+        for notified in self.session.notification_enabled() {
             debug!("disable notification uuid: {:?}", notified);
-            self.ble_peripheral
-                .unsubscribe(&self.ble_characteristics[notified])
-                .await?;
+            if let Some(characteristic) = self.ble_characteristics.get(&notified) {
+                self.ble_peripheral.unsubscribe(characteristic).await?;
+            }
         }
+        self.session.clear_notification_enabled();
         self.ble_peripheral.disconnect().await?;
         // windows: is_connected is not turned off when device disconnect.
         // macos: is_connected is not turned off when device disconnect.
-        if cfg!(target_os = "linux") {
-            let is_connected = self.ble_peripheral.is_connected().await?;
-            assert!(!is_connected);
+        if cfg!(target_os = "linux") && self.ble_peripheral.is_connected().await? {
+            warn!("still connected after disconnection");
         }
         self.ble_characteristics.clear();
         Ok(())
     }
 
-    async fn read(
-        &self,
-        uuid: Uuid,
-    ) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync + 'static>> {
-        let characteristic = self.ble_characteristics.get(&uuid).unwrap();
+    async fn read(&self, uuid: Uuid) -> Result<Vec<u8>, CoreCubeError> {
+        let characteristic = self.characteristic(uuid)?;
         let data = self.ble_peripheral.read(characteristic).await?;
         Ok(data)
     }
 
-    async fn write(
-        &self,
-        uuid: Uuid,
-        bytes: &[u8],
-    ) -> Result<bool, Box<dyn std::error::Error + Send + Sync + 'static>> {
-        let characteristic = self.ble_characteristics.get(&uuid).unwrap();
+    async fn write(&self, uuid: Uuid, bytes: &[u8]) -> Result<bool, CoreCubeError> {
+        let characteristic = self.characteristic(uuid)?;
+        debug!("write: {}", describe(uuid, bytes));
         self.ble_peripheral
             .write(characteristic, bytes, WriteType::WithoutResponse)
             .await?;
+        self.session.record(uuid, bytes);
         Ok(true)
     }
 
-    async fn write_with_response(
-        &self,
-        uuid: Uuid,
-        bytes: &[u8],
-    ) -> Result<bool, Box<dyn std::error::Error + Send + Sync + 'static>> {
-        let characteristic = self.ble_characteristics.get(&uuid).unwrap();
+    async fn write_with_response(&self, uuid: Uuid, bytes: &[u8]) -> Result<bool, CoreCubeError> {
+        let characteristic = self.characteristic(uuid)?;
+        debug!("write with response: {}", describe(uuid, bytes));
         self.ble_peripheral
             .write(characteristic, bytes, WriteType::WithResponse)
             .await?;
+        self.session.record(uuid, bytes);
         Ok(true)
     }
 
-    fn create_notification_receiver(
+    async fn subscribe(&self, characteristic: CoreCubeUuid) -> Result<(), CoreCubeError> {
+        let uuid = characteristic.uuid();
+        let ble_characteristic = self.characteristic(uuid)?;
+        if !ble_characteristic
+            .properties
+            .contains(CharPropFlags::NOTIFY)
+        {
+            return Err(CoreCubeError::WrongParameter);
+        }
+        if !self.session.notification_enabled().contains(&uuid) {
+            debug!("enable notification: {}", characteristic.name());
+            self.ble_peripheral.subscribe(ble_characteristic).await?;
+            self.session.set_notification_enabled(uuid, true);
+        }
+        Ok(())
+    }
+
+    async fn unsubscribe(&self, characteristic: CoreCubeUuid) -> Result<(), CoreCubeError> {
+        let uuid = characteristic.uuid();
+        if self.session.notification_enabled().contains(&uuid) {
+            debug!("disable notification: {}", characteristic.name());
+            self.ble_peripheral
+                .unsubscribe(self.characteristic(uuid)?)
+                .await?;
+            self.session.set_notification_enabled(uuid, false);
+        }
+        Ok(())
+    }
+
+    fn notification_enabled(&self) -> Vec<CoreCubeUuid> {
+        self.session
+            .notification_enabled()
+            .into_iter()
+            .filter_map(CoreCubeUuid::from_uuid)
+            .collect()
+    }
+
+    fn create_notification_receiver_with_manager(
         &self,
-        handlers: Box<Vec<HandlerFunction<NotificationData>>>,
+        nf_manager: Arc<NotificationManager<NotificationData>>,
     ) -> Pin<Box<dyn Future<Output = ()> + Send>> {
         let ble_peripheral = self.ble_peripheral.clone();
+        let reconnect = self.reconnect.is_some();
+        let state = self.session.watch_state();
+        let mut disconnected = self.session.watch_state();
         Box::pin(async move {
-            let nf_manager = NotificationManager::<NotificationData>::new();
-            let mut registered_handlers: Vec<Uuid> = vec![];
-
-            for notification_handler in *handlers {
-                let handler_uuid = nf_manager.register(Box::new(notification_handler)).unwrap();
-                registered_handlers.push(handler_uuid);
-            }
-            let _ = ble_notification_receiver(ble_peripheral, &nf_manager).await;
-            for handler_uuid in registered_handlers {
-                nf_manager.unregister(handler_uuid).unwrap();
+            let receive = async {
+                if reconnect {
+                    ble_session_receiver(ble_peripheral, &nf_manager, state).await;
+                } else if let Err(err) =
+                    ble_notification_receiver(ble_peripheral, &nf_manager).await
+                {
+                    warn!("notification stream: {}", err);
+                }
+            };
+            // the notification stream does not always end by disconnect()
+            tokio::select! {
+                _ = receive => {}
+                _ = disconnected.wait_for(|state| *state == ConnectionState::Disconnected) => {
+                    debug!("stop notification receiver (disconnected)");
+                }
             }
         })
     }
+
+    fn request_id_allocator(&self) -> RequestIdAllocator {
+        self.request_id_allocator.clone()
+    }
+
+    fn adapter(&self) -> Option<AdapterInfo> {
+        self.adapter.clone()
+    }
+
+    async fn notification_stream(&self) -> Result<NotificationStream, CoreCubeError> {
+        Ok(self.ble_peripheral.notifications().await?)
+    }
 }
 
-pub struct BleScanner;
+/// Scanner of BLE adapters
+///
+/// Scans all adapters in parallel by default.
+#[derive(Debug, Clone, Default)]
+pub struct BleScanner {
+    pub adapter: AdapterSelector,
+}
 
 impl BleScanner {
-    async fn scan_ble(
-        &self,
-        filter: ScanFilter,
-        wait: Duration,
-    ) -> Result<Vec<Box<BleCube>>, Box<dyn std::error::Error + Send + Sync + 'static>> {
+    pub fn new(adapter: AdapterSelector) -> Self {
+        Self { adapter }
+    }
+
+    /// start scanning on the selected adapters
+    pub async fn scan_stream(&self) -> Result<ScanStream, CoreCubeError> {
         let manager = Manager::new().await?;
-        let adapter_list = manager.adapters().await?;
-        let mut peripheral_list: Vec<Box<BleCube>> = Vec::new();
+        let adapter_list =
+            match scan::select_adapters(manager.adapters().await?, &self.adapter).await {
+                Ok(adapter_list) => adapter_list,
+                Err(err) => {
+                    error!("No Bluetooth adapters found ({:?})", self.adapter);
+                    return Err(err);
+                }
+            };
+        ScanStream::start(adapter_list).await
+    }
 
-        #[derive(Clone)]
-        struct RssiBle {
-            rssi: i16,
-            ble: BleInterface,
+    /// collect cubes which match the filter until `wait` elapses
+    ///
+    /// The result is sorted by RSSI (strongest first), so the first one is the nearest cube.
+    pub async fn discover(
+        &self,
+        filter: &DiscoveryFilter,
+        wait: Duration,
+    ) -> Result<Vec<DiscoveredCube>, CoreCubeError> {
+        let mut found = self.scan_until(wait, |_| true, |_| false).await?;
+        // RSSI at the first advertisement may be stale
+        for discovered in found.iter_mut() {
+            if let Err(err) = discovered.refresh().await {
+                warn!("refresh {}: {}", discovered.address, err);
+            }
         }
-        let mut rssi_peripheral_hash: HashMap<BDAddr, RssiBle> = HashMap::new();
+        found.retain(|discovered| filter.matches(discovered));
+        sort_by_rssi(&mut found);
+        Ok(found)
+    }
 
-        if adapter_list.is_empty() {
-            error!("No Bluetooth adapters found");
-            return Ok(peripheral_list);
-        }
+    /// the cube which has the strongest RSSI
+    pub async fn nearest(
+        &self,
+        filter: &DiscoveryFilter,
+        wait: Duration,
+    ) -> Result<DiscoveredCube, CoreCubeError> {
+        self.discover(filter, wait)
+            .await?
+            .into_iter()
+            .next()
+            .ok_or(CoreCubeError::CubeNotFound)
+    }
 
-        for adapter in adapter_list.iter() {
-            println!("Starting scan on {}...", adapter.adapter_info().await?);
-            adapter.start_scan(filter.clone()).await?;
-            time::sleep(wait).await;
-            adapter.stop_scan().await?;
-            for (_index, peripheral) in adapter.peripherals().await?.iter().enumerate() {
-                // debug!("{} {:?}", _index, peripheral);
-                if peripheral.is_connected().await? {
-                    debug!("skip connected device");
-                    continue;
-                }
-                let properties = peripheral.properties().await?.unwrap();
-                for service_uuid in properties.services.iter() {
-                    info!("service uuid: {}", service_uuid);
-                    if *service_uuid == CoreCubeUuid::Service.uuid() {
-                        debug!("found toio core cube: service uuid: {}", service_uuid);
-                        let rssi = peripheral
-                            .properties()
-                            .await
-                            .unwrap()
-                            .unwrap()
-                            .rssi
-                            .unwrap();
-                        let rssi_ble = RssiBle {
-                            rssi,
-                            ble: peripheral.clone(),
-                        };
-                        let ble_address = peripheral.properties().await?.unwrap().address;
-                        rssi_peripheral_hash.insert(ble_address, rssi_ble);
+    /// collect accepted cubes until `done` returns true or `wait` elapses
+    async fn scan_until(
+        &self,
+        wait: Duration,
+        accept: impl Fn(&DiscoveredCube) -> bool,
+        done: impl Fn(&[DiscoveredCube]) -> bool,
+    ) -> Result<Vec<DiscoveredCube>, CoreCubeError> {
+        let mut scan_stream = self.scan_stream().await?;
+        let mut found: Vec<DiscoveredCube> = Vec::new();
+        let collect = async {
+            while let Some(discovered) = scan_stream.next().await {
+                if accept(&discovered) {
+                    info!("found cube: {:?}", discovered.local_name);
+                    found.push(discovered);
+                    if done(&found) {
+                        break;
                     }
                 }
             }
-            let mut rssi_peripheral_list = Vec::from_iter(rssi_peripheral_hash.values().cloned());
-            rssi_peripheral_list.sort_by(|a, b| a.rssi.cmp(&b.rssi));
-            for inerface in rssi_peripheral_list.iter() {
-                peripheral_list.push(Box::new(BleCube::new(inerface.ble.clone())));
-            }
+        };
+        if time::timeout(wait, collect).await.is_err() {
+            debug!("scan: timeout");
         }
-        debug!(
-            "scan_ble: total {} peripherals found",
-            peripheral_list.len()
-        );
-        Ok(peripheral_list)
+        scan_stream.stop().await;
+        debug!("scan: total {} peripherals found", found.len());
+        Ok(found)
     }
 }
 
+/// cubes sorted strongest first
+fn into_interfaces(
+    mut found: Vec<DiscoveredCube>,
+) -> Result<Vec<Box<dyn CubeInterface + Send + Sync + 'static>>, CoreCubeError> {
+    if found.is_empty() {
+        error!("toio core cube is not found");
+        return Err(CoreCubeError::CubeNotFound);
+    }
+    sort_by_rssi(&mut found);
+    spread_across_adapters(&mut found);
+    Ok(found
+        .into_iter()
+        .map(|discovered| {
+            Box::new(discovered.cube) as Box<dyn CubeInterface + Send + Sync + 'static>
+        })
+        .collect())
+}
+
 #[async_trait]
 impl CubeScanner for BleScanner {
     async fn scan(
         &self,
         num: usize,
         wait: Duration,
-    ) -> Result<
-        Vec<Box<dyn CubeInterface + Send + Sync + 'static>>,
-        Box<dyn std::error::Error + Send + Sync + 'static>,
-    > {
-        let peripheral_list = self.scan_ble(ScanFilter::default(), wait).await.unwrap();
-        let mut matched_peripheral_list: Vec<Box<dyn CubeInterface + Send + Sync + 'static>> =
-            Vec::new();
-        for (n, cube) in peripheral_list.into_iter().enumerate() {
-            if n < num {
-                matched_peripheral_list.push(cube)
-            } else {
-                break;
-            }
+    ) -> Result<Vec<Box<dyn CubeInterface + Send + Sync + 'static>>, CoreCubeError> {
+        if num == 0 {
+            // nothing to scan
+            return Ok(Vec::new());
         }
-        if matched_peripheral_list.is_empty() {
-            error!("toio core cube is not found");
-            return Err(CoreCubeError::CubeNotFound.into());
-        }
-        debug!(
-            "scan: total {} peripherals found",
-            matched_peripheral_list.len()
-        );
-        Ok(matched_peripheral_list)
+        into_interfaces(
+            self.scan_until(wait, |_| true, |found| found.len() >= num)
+                .await?,
+        )
     }
 
     async fn scan_with_address(
         &self,
         address_list: &[BDAddr],
         wait: Duration,
-    ) -> Result<
-        Vec<Box<dyn CubeInterface + Send + Sync + 'static>>,
-        Box<dyn std::error::Error + Send + Sync + 'static>,
-    > {
-        let mut matched_peripheral_list: Vec<Box<dyn CubeInterface + Send + Sync + 'static>> =
-            Vec::new();
-        let peripheral_list = self.scan_ble(ScanFilter::default(), wait).await.unwrap();
-        for cube in peripheral_list {
-            let properties = cube.ble_peripheral.properties().await?.unwrap();
-            if address_list
-                .iter()
-                .any(|e: &BDAddr| e == &properties.address)
-            {
-                info!("found cube: '{}'", &properties.address);
-                matched_peripheral_list.push(cube);
-            }
-        }
-
-        if matched_peripheral_list.is_empty() {
-            error!("toio core cube is not found");
-            return Err(CoreCubeError::CubeNotFound.into());
-        }
-        debug!(
-            "scan_with_address: total {} peripherals found",
-            matched_peripheral_list.len()
-        );
-        Ok(matched_peripheral_list)
+    ) -> Result<Vec<Box<dyn CubeInterface + Send + Sync + 'static>>, CoreCubeError> {
+        into_interfaces(
+            self.scan_until(
+                wait,
+                |discovered| address_list.contains(&discovered.address),
+                |found| {
+                    address_list.iter().all(|address| {
+                        found
+                            .iter()
+                            .any(|discovered| discovered.address == *address)
+                    })
+                },
+            )
+            .await?,
+        )
     }
 
     async fn scan_with_name(
         &self,
         name_list: &[&str],
         wait: Duration,
-    ) -> Result<
-        Vec<Box<dyn CubeInterface + Send + Sync + 'static>>,
-        Box<dyn std::error::Error + Send + Sync + 'static>,
-    > {
-        let mut matched_peripheral_list: Vec<Box<dyn CubeInterface + Send + Sync + 'static>> =
-            Vec::new();
-        let peripheral_list = self.scan_ble(ScanFilter::default(), wait).await.unwrap();
-        for cube in peripheral_list {
-            let properties = cube.ble_peripheral.properties().await?.unwrap();
-            if let Some(local_name) = properties.local_name {
-                if name_list.iter().any(|e| e == &local_name) {
-                    info!("found cube: '{}'", &local_name);
-                    matched_peripheral_list.push(cube);
-                }
-            }
-        }
-
-        if matched_peripheral_list.is_empty() {
-            error!("toio core cube is not found");
-            return Err(CoreCubeError::CubeNotFound.into());
-        }
-        debug!(
-            "scan_with_name: total {} peripherals found",
-            matched_peripheral_list.len()
-        );
-        Ok(matched_peripheral_list)
+    ) -> Result<Vec<Box<dyn CubeInterface + Send + Sync + 'static>>, CoreCubeError> {
+        let has_name = |discovered: &DiscoveredCube, name: &str| {
+            discovered.local_name.as_deref() == Some(name)
+        };
+        into_interfaces(
+            self.scan_until(
+                wait,
+                |discovered| name_list.iter().any(|name| has_name(discovered, name)),
+                |found| {
+                    name_list
+                        .iter()
+                        .all(|name| found.iter().any(|discovered| has_name(discovered, name)))
+                },
+            )
+            .await?,
+        )
     }
 }
 
@@ -336,16 +558,25 @@ mod tests {
     #[tokio::test]
     async fn cube_scan1() {
         _setup();
-        let scanner = BleScanner;
+        let scanner = BleScanner::default();
         let interfaces = scanner.scan(1, Duration::from_secs(5)).await.unwrap();
         assert!(!interfaces.is_empty());
         _teardown();
     }
 
+    #[tokio::test]
+    async fn cube_scan_none1() {
+        _setup();
+        // returns without scanning (no adapter is needed)
+        let scanner = BleScanner::default();
+        let interfaces = scanner.scan(0, Duration::from_secs(5)).await.unwrap();
+        assert!(interfaces.is_empty());
+    }
+
     #[tokio::test]
     async fn cube_scan2() {
         _setup();
-        let scanner = BleScanner;
+        let scanner = BleScanner::default();
         let interfaces = scanner
             .scan_with_address(&[BDAddr::from(TEST_CUBE_BDADDR)], Duration::from_secs(3))
             .await
@@ -357,7 +588,7 @@ mod tests {
     #[tokio::test]
     async fn cube_scan3() {
         _setup();
-        let scanner = BleScanner;
+        let scanner = BleScanner::default();
         let interfaces = scanner
             .scan_with_name(&[TEST_CUBE_NAME], Duration::from_secs(3))
             .await
@@ -0,0 +1,283 @@
+//! Supervised connection of BleCube
+
+use crate::characteristic::configuration::CommandId;
+use crate::characteristic::CoreCubeUuid;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, Weak};
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+/// Backoff of reconnection
+///
+/// The delay before each attempt starts at `initial_delay` and is doubled up to `max_delay`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ReconnectPolicy {
+    pub initial_delay: Duration,
+    pub max_delay: Duration,
+    /// give up after this number of attempts (`None`: never give up)
+    pub max_attempts: Option<usize>,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            initial_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            max_attempts: None,
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    /// delay before the attempt (counted from 0), `None` if it should give up
+    pub fn delay(&self, attempt: usize) -> Option<Duration> {
+        if let Some(max_attempts) = self.max_attempts {
+            if attempt >= max_attempts {
+                return None;
+            }
+        }
+        let factor = 1u32.checked_shl(attempt as u32).unwrap_or(u32::MAX);
+        Some(
+            self.initial_delay
+                .checked_mul(factor)
+                .unwrap_or(self.max_delay)
+                .min(self.max_delay),
+        )
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ConnectionState {
+    Connected,
+    Reconnecting,
+    Disconnected,
+}
+
+/// State shared by all clones of a BleCube
+///
+/// The supervisor is aborted when the last clone is dropped, so it must hold only
+/// a [`WeakBleSession`].
+#[derive(Clone)]
+pub(crate) struct BleSession {
+    shared: Arc<SessionState>,
+}
+
+struct SessionState {
+    state: watch::Sender<ConnectionState>,
+    configuration: Mutex<BTreeMap<u8, Vec<u8>>>,
+    notification_enabled: Mutex<Vec<Uuid>>,
+    supervisor: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl Drop for SessionState {
+    fn drop(&mut self) {
+        // the cube is dropped without disconnect()
+        if let Ok(supervisor) = self.supervisor.get_mut() {
+            if let Some(supervisor) = supervisor.take() {
+                supervisor.abort();
+            }
+        }
+    }
+}
+
+impl Default for BleSession {
+    fn default() -> Self {
+        let (state, _) = watch::channel(ConnectionState::Disconnected);
+        Self {
+            shared: Arc::new(SessionState {
+                state,
+                configuration: Mutex::new(BTreeMap::new()),
+                notification_enabled: Mutex::new(Vec::new()),
+                supervisor: Mutex::new(None),
+            }),
+        }
+    }
+}
+
+impl BleSession {
+    pub fn state(&self) -> ConnectionState {
+        *self.shared.state.borrow()
+    }
+
+    pub fn set_state(&self, state: ConnectionState) {
+        self.shared.state.send_replace(state);
+    }
+
+    pub fn watch_state(&self) -> watch::Receiver<ConnectionState> {
+        self.shared.state.subscribe()
+    }
+
+    /// keep the configuration command to replay it after reconnection
+    ///
+    /// Only the last command of each kind is kept. Requests (e.g. BLE protocol version)
+    /// are not replayed.
+    pub fn record(&self, uuid: Uuid, bytes: &[u8]) {
+        if uuid != CoreCubeUuid::Config.uuid() {
+            return;
+        }
+        if let Some(&command) = bytes.first() {
+            if command != u8::from(CommandId::BleProtocolVersion) {
+                self.shared
+                    .configuration
+                    .lock()
+                    .unwrap()
+                    .insert(command, bytes.to_vec());
+            }
+        }
+    }
+
+    pub fn configuration(&self) -> Vec<Vec<u8>> {
+        self.shared
+            .configuration
+            .lock()
+            .unwrap()
+            .values()
+            .cloned()
+            .collect()
+    }
+
+    /// characteristics subscribed now (subscribed again after reconnection)
+    pub fn notification_enabled(&self) -> Vec<Uuid> {
+        self.shared.notification_enabled.lock().unwrap().clone()
+    }
+
+    pub fn set_notification_enabled(&self, uuid: Uuid, enabled: bool) {
+        let mut notification_enabled = self.shared.notification_enabled.lock().unwrap();
+        notification_enabled.retain(|notified| *notified != uuid);
+        if enabled {
+            notification_enabled.push(uuid);
+        }
+    }
+
+    pub fn clear_notification_enabled(&self) {
+        self.shared.notification_enabled.lock().unwrap().clear();
+    }
+
+    pub fn start_supervisor(&self, supervisor: JoinHandle<()>) {
+        if let Some(previous) = self.shared.supervisor.lock().unwrap().replace(supervisor) {
+            previous.abort();
+        }
+    }
+
+    pub fn stop_supervisor(&self) {
+        if let Some(supervisor) = self.shared.supervisor.lock().unwrap().take() {
+            supervisor.abort();
+        }
+    }
+
+    pub fn downgrade(&self) -> WeakBleSession {
+        WeakBleSession(Arc::downgrade(&self.shared))
+    }
+}
+
+/// Session which does not keep the BleCube alive
+#[derive(Clone)]
+pub(crate) struct WeakBleSession(Weak<SessionState>);
+
+impl WeakBleSession {
+    /// `None` after all clones of the BleCube are dropped
+    pub fn upgrade(&self) -> Option<BleSession> {
+        self.0.upgrade().map(|shared| BleSession { shared })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::characteristic::configuration::EnableMotorSpeed;
+    use crate::payload::ToPayload;
+    use tokio::sync::oneshot;
+
+    fn _setup() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    #[test]
+    fn reconnect_policy1() {
+        _setup();
+
+        let policy = ReconnectPolicy {
+            initial_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(1000),
+            max_attempts: Some(6),
+        };
+        let delays: Vec<Option<Duration>> = (0..7).map(|attempt| policy.delay(attempt)).collect();
+        assert_eq!(
+            delays,
+            vec![
+                Some(Duration::from_millis(100)),
+                Some(Duration::from_millis(200)),
+                Some(Duration::from_millis(400)),
+                Some(Duration::from_millis(800)),
+                Some(Duration::from_millis(1000)),
+                Some(Duration::from_millis(1000)),
+                None,
+            ]
+        );
+        assert_eq!(
+            ReconnectPolicy::default().delay(100),
+            Some(ReconnectPolicy::default().max_delay)
+        );
+    }
+
+    #[test]
+    fn reconnect_session1() {
+        _setup();
+
+        let session = BleSession::default();
+        let configuration = CoreCubeUuid::Config.uuid();
+        session.record(configuration, &[0x01, 0x00]);
+        session.record(configuration, &[0x18, 0x00, 0x01, 0x00]);
+        session.record(configuration, &[0x18, 0x00, 0x05, 0x01]);
+        session.record(CoreCubeUuid::MotorCtrl.uuid(), &[0x01, 0x01, 0x01, 0x10]);
+        let motor_speed = EnableMotorSpeed::new(true).to_payload();
+        session.record(configuration, &motor_speed);
+        assert_eq!(
+            session.configuration(),
+            vec![vec![0x18, 0x00, 0x05, 0x01], motor_speed]
+        );
+
+        session.set_notification_enabled(CoreCubeUuid::IdInfo.uuid(), true);
+        session.set_notification_enabled(CoreCubeUuid::ButtonInfo.uuid(), true);
+        session.set_notification_enabled(CoreCubeUuid::IdInfo.uuid(), true);
+        session.set_notification_enabled(CoreCubeUuid::ButtonInfo.uuid(), false);
+        assert_eq!(
+            session.notification_enabled(),
+            vec![CoreCubeUuid::IdInfo.uuid()]
+        );
+
+        let clone = session.clone();
+        let state = session.watch_state();
+        clone.set_state(ConnectionState::Reconnecting);
+        assert_eq!(*state.borrow(), ConnectionState::Reconnecting);
+        assert_eq!(session.state(), ConnectionState::Reconnecting);
+    }
+
+    #[tokio::test]
+    async fn reconnect_supervisor1() {
+        _setup();
+
+        let session = BleSession::default();
+        let weak = session.downgrade();
+        // the supervisor holds `alive` and (like the real one) only the weak session
+        let (alive, stopped) = oneshot::channel::<()>();
+        let supervised = weak.clone();
+        session.start_supervisor(tokio::spawn(async move {
+            let _alive = alive;
+            let _session = supervised;
+            futures::future::pending::<()>().await;
+        }));
+
+        let clone = session.clone();
+        drop(session);
+        assert!(weak.upgrade().is_some());
+
+        // dropped without disconnect()
+        drop(clone);
+        assert!(weak.upgrade().is_none());
+        let stopped = tokio::time::timeout(Duration::from_secs(1), stopped).await;
+        assert!(matches!(stopped, Ok(Err(_))));
+    }
+}
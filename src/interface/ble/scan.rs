@@ -0,0 +1,500 @@
+//! Scan of toio core cubes driven by the central events
+
+use super::BleCube;
+use crate::characteristic::CoreCubeUuid;
+use crate::CoreCubeError;
+use btleplug::api::{BDAddr, Central, CentralEvent, Peripheral as _, ScanFilter};
+use btleplug::platform::{Adapter, Peripheral};
+use futures::stream::{Stream, StreamExt};
+use log::{debug, info, warn};
+use regex::Regex;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+/// Bluetooth adapter which found a cube
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AdapterInfo {
+    /// index of the adapter in the list of the adapters on this host
+    pub index: usize,
+    /// description of the adapter (e.g. `hci0 (usb:v1D6Bp0246d0537)` on Linux)
+    pub info: String,
+}
+
+/// Peripherals of a cube on each adapter which received its advertisement
+type Sightings = Arc<Mutex<Vec<(AdapterInfo, Peripheral)>>>;
+
+/// Cube found by the scan with the metadata of its advertisement
+///
+/// The cube is connected through `adapter`. Other adapters which receive the advertisement
+/// later are recorded, so the cube can be moved to one of them by `select_adapter()`.
+#[derive(Clone)]
+pub struct DiscoveredCube {
+    pub local_name: Option<String>,
+    pub address: BDAddr,
+    pub rssi: Option<i16>,
+    pub tx_power_level: Option<i16>,
+    pub manufacturer_data: HashMap<u16, Vec<u8>>,
+    /// adapter which the cube is connected through
+    pub adapter: AdapterInfo,
+    pub cube: BleCube,
+    sightings: Sightings,
+}
+
+impl DiscoveredCube {
+    /// `None` if the peripheral is not a toio core cube (or its name is not received yet)
+    async fn from_peripheral(
+        peripheral: Peripheral,
+        adapter: &ScanAdapter,
+    ) -> Result<Option<Self>, CoreCubeError> {
+        let Some(properties) = peripheral.properties().await? else {
+            return Ok(None);
+        };
+        if !properties.services.contains(&CoreCubeUuid::Service.uuid())
+            || properties.local_name.is_none()
+        {
+            return Ok(None);
+        }
+        let adapter = AdapterInfo {
+            index: adapter.index,
+            info: adapter.info.clone(),
+        };
+        let sightings = Arc::new(Mutex::new(vec![(adapter.clone(), peripheral.clone())]));
+        let mut cube = BleCube::new(peripheral);
+        cube.adapter = Some(adapter.clone());
+        Ok(Some(Self {
+            local_name: properties.local_name,
+            address: properties.address,
+            rssi: properties.rssi,
+            tx_power_level: properties.tx_power_level,
+            manufacturer_data: properties.manufacturer_data,
+            adapter,
+            cube,
+            sightings,
+        }))
+    }
+
+    /// adapters which received the advertisement of this cube (in the order of reception)
+    pub fn adapters(&self) -> Vec<AdapterInfo> {
+        self.sightings
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(adapter, _)| adapter.clone())
+            .collect()
+    }
+
+    /// connect the cube through another adapter which received its advertisement
+    ///
+    /// Returns `NoBleAdapter` if the adapter has not received the advertisement.
+    /// The cube must not be connected yet.
+    pub fn select_adapter(&mut self, index: usize) -> Result<(), CoreCubeError> {
+        if self.adapter.index == index {
+            return Ok(());
+        }
+        let (adapter, peripheral) = self
+            .sightings
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|(adapter, _)| adapter.index == index)
+            .cloned()
+            .ok_or(CoreCubeError::NoBleAdapter)?;
+        self.cube = BleCube::new(peripheral);
+        self.cube.adapter = Some(adapter.clone());
+        self.adapter = adapter;
+        Ok(())
+    }
+
+    /// update RSSI, TX power and manufacturer data with the latest advertisement
+    pub async fn refresh(&mut self) -> Result<(), CoreCubeError> {
+        if let Some(properties) = self.cube.ble_peripheral.properties().await? {
+            self.rssi = properties.rssi;
+            self.tx_power_level = properties.tx_power_level;
+            self.manufacturer_data = properties.manufacturer_data;
+        }
+        Ok(())
+    }
+}
+
+/// Sort cubes by RSSI, strongest (i.e. nearest) first
+///
+/// Cubes whose RSSI is unknown come last.
+pub fn sort_by_rssi(cubes: &mut [DiscoveredCube]) {
+    cubes.sort_by_key(|discovered| strongest_first(discovered.rssi));
+}
+
+fn strongest_first(rssi: Option<i16>) -> Reverse<Option<i16>> {
+    // None is less than any Some(_), so it comes last in reverse order
+    Reverse(rssi)
+}
+
+/// Spread cubes across the adapters which received their advertisements
+///
+/// Each cube (in the order of the slice) is moved to the adapter which has the fewest cubes,
+/// to get past the limit of connections per adapter.
+pub fn spread_across_adapters(cubes: &mut [DiscoveredCube]) {
+    let candidates: Vec<Vec<usize>> = cubes
+        .iter()
+        .map(|discovered| {
+            let mut indexes = vec![discovered.adapter.index];
+            indexes.extend(discovered.adapters().iter().map(|adapter| adapter.index));
+            indexes
+        })
+        .collect();
+    for (discovered, index) in cubes.iter_mut().zip(least_loaded(&candidates)) {
+        if let Err(err) = discovered.select_adapter(index) {
+            warn!("spread {}: {}", discovered.address, err);
+        }
+    }
+}
+
+/// the adapter of each cube which has the fewest cubes assigned (the first one wins a tie)
+fn least_loaded(candidates: &[Vec<usize>]) -> Vec<usize> {
+    let mut load: HashMap<usize, usize> = HashMap::new();
+    candidates
+        .iter()
+        .filter_map(|indexes| {
+            let index = *indexes
+                .iter()
+                .min_by_key(|index| load.get(index).copied().unwrap_or(0))?;
+            *load.entry(index).or_insert(0) += 1;
+            Some(index)
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone)]
+pub enum NameFilter {
+    Prefix(String),
+    Regex(Regex),
+}
+
+impl NameFilter {
+    pub fn matches(&self, name: &str) -> bool {
+        match self {
+            NameFilter::Prefix(prefix) => name.starts_with(prefix.as_str()),
+            NameFilter::Regex(regex) => regex.is_match(name),
+        }
+    }
+}
+
+/// Adapters to scan
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum AdapterSelector {
+    /// scan all adapters in parallel
+    #[default]
+    All,
+    /// index in the list of the adapters (e.g. 0 for the first one)
+    Index(usize),
+    /// name of the adapter (e.g. `hci1` on Linux), or its whole description
+    Name(String),
+}
+
+impl AdapterSelector {
+    fn is_selected(&self, index: usize, info: &str) -> bool {
+        match self {
+            AdapterSelector::All => true,
+            AdapterSelector::Index(selected) => *selected == index,
+            // adapter_info() is like "hci0 (usb:v1D6Bp0246d0537)" on Linux
+            AdapterSelector::Name(name) => {
+                info == name || info.split_whitespace().next() == Some(name.as_str())
+            }
+        }
+    }
+}
+
+pub(crate) struct ScanAdapter {
+    index: usize,
+    info: String,
+    adapter: Adapter,
+}
+
+/// adapters which match the selector
+pub(crate) async fn select_adapters(
+    adapters: Vec<Adapter>,
+    selector: &AdapterSelector,
+) -> Result<Vec<ScanAdapter>, CoreCubeError> {
+    let mut selected = Vec::new();
+    for (index, adapter) in adapters.into_iter().enumerate() {
+        let info = adapter.adapter_info().await?;
+        if selector.is_selected(index, &info) {
+            selected.push(ScanAdapter {
+                index,
+                info,
+                adapter,
+            });
+        }
+    }
+    if selected.is_empty() {
+        return Err(CoreCubeError::NoBleAdapter);
+    }
+    Ok(selected)
+}
+
+/// Conditions of discovered cubes
+///
+/// The default filter accepts all cubes.
+#[derive(Debug, Clone, Default)]
+pub struct DiscoveryFilter {
+    pub min_rssi: Option<i16>,
+    pub name: Option<NameFilter>,
+}
+
+impl DiscoveryFilter {
+    pub fn min_rssi(mut self, rssi: i16) -> Self {
+        self.min_rssi = Some(rssi);
+        self
+    }
+
+    pub fn name_prefix(mut self, prefix: &str) -> Self {
+        self.name = Some(NameFilter::Prefix(prefix.to_string()));
+        self
+    }
+
+    pub fn name_regex(mut self, regex: Regex) -> Self {
+        self.name = Some(NameFilter::Regex(regex));
+        self
+    }
+
+    pub fn matches(&self, discovered: &DiscoveredCube) -> bool {
+        self.accepts(discovered.local_name.as_deref(), discovered.rssi)
+    }
+
+    fn accepts(&self, local_name: Option<&str>, rssi: Option<i16>) -> bool {
+        if let Some(min_rssi) = self.min_rssi {
+            match rssi {
+                Some(rssi) if rssi >= min_rssi => {}
+                // unknown RSSI does not satisfy the minimum
+                _ => return false,
+            }
+        }
+        if let Some(name) = &self.name {
+            if !local_name.is_some_and(|local_name| name.matches(local_name)) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Stream of cubes yielded as soon as their advertisements are received
+///
+/// Each cube is yielded once even if it is received by several adapters (the other adapters
+/// are recorded in `DiscoveredCube::adapters()`). The scan is stopped when this is dropped
+/// (or by `stop()`).
+pub struct ScanStream {
+    receiver: mpsc::UnboundedReceiver<DiscoveredCube>,
+    scanners: Vec<JoinHandle<()>>,
+    adapters: Vec<Adapter>,
+}
+
+impl ScanStream {
+    pub(crate) async fn start(adapters: Vec<ScanAdapter>) -> Result<Self, CoreCubeError> {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let found = Arc::new(Mutex::new(HashMap::new()));
+        let mut scan_stream = Self {
+            receiver,
+            scanners: Vec::new(),
+            adapters: Vec::new(),
+        };
+        for scan_adapter in adapters {
+            info!("Starting scan on {}...", scan_adapter.info);
+            let adapter = scan_adapter.adapter.clone();
+            let events = adapter.events().await?;
+            adapter
+                .start_scan(ScanFilter {
+                    services: vec![CoreCubeUuid::Service.uuid()],
+                })
+                .await?;
+            scan_stream.scanners.push(tokio::spawn(scan_events(
+                scan_adapter,
+                events,
+                sender.clone(),
+                found.clone(),
+            )));
+            scan_stream.adapters.push(adapter);
+        }
+        Ok(scan_stream)
+    }
+
+    pub async fn stop(mut self) {
+        for scanner in self.scanners.drain(..) {
+            scanner.abort();
+        }
+        for adapter in self.adapters.drain(..) {
+            if let Err(err) = adapter.stop_scan().await {
+                warn!("stop scan: {}", err);
+            }
+        }
+    }
+}
+
+impl Stream for ScanStream {
+    type Item = DiscoveredCube;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().receiver.poll_recv(cx)
+    }
+}
+
+impl Drop for ScanStream {
+    fn drop(&mut self) {
+        for scanner in &self.scanners {
+            scanner.abort();
+        }
+        if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+            for adapter in self.adapters.drain(..) {
+                runtime.spawn(async move {
+                    let _ = adapter.stop_scan().await;
+                });
+            }
+        }
+    }
+}
+
+async fn scan_events(
+    scan_adapter: ScanAdapter,
+    events: Pin<Box<dyn Stream<Item = CentralEvent> + Send>>,
+    sender: mpsc::UnboundedSender<DiscoveredCube>,
+    found: Arc<Mutex<HashMap<BDAddr, Sightings>>>,
+) {
+    let adapter = &scan_adapter.adapter;
+    // peripherals cached by the adapter may be powered off, so they are yielded only
+    // when their advertisement is received again (DeviceUpdated)
+    let mut ids = Box::pin(events.filter_map(|event| async move {
+        match event {
+            CentralEvent::DeviceDiscovered(id)
+            | CentralEvent::DeviceUpdated(id)
+            | CentralEvent::ServicesAdvertisement { id, .. } => Some(id),
+            _ => None,
+        }
+    }));
+    while let Some(id) = ids.next().await {
+        let Ok(peripheral) = adapter.peripheral(&id).await else {
+            continue;
+        };
+        if peripheral.is_connected().await.unwrap_or(false) {
+            continue;
+        }
+        let discovered = match DiscoveredCube::from_peripheral(peripheral, &scan_adapter).await {
+            Ok(Some(discovered)) => discovered,
+            Ok(None) => continue,
+            Err(err) => {
+                warn!("scan: {}", err);
+                continue;
+            }
+        };
+        // the first adapter which receives the advertisement yields the cube,
+        // the others are recorded to the cube already yielded
+        let first = {
+            let mut found = found.lock().unwrap();
+            match found.get(&discovered.address) {
+                Some(sightings) => {
+                    let mut sightings = sightings.lock().unwrap();
+                    if !sightings
+                        .iter()
+                        .any(|(adapter, _)| *adapter == discovered.adapter)
+                    {
+                        sightings.append(&mut discovered.sightings.lock().unwrap());
+                    }
+                    false
+                }
+                None => {
+                    found.insert(discovered.address, discovered.sightings.clone());
+                    true
+                }
+            }
+        };
+        if first {
+            debug!(
+                "found toio core cube: {:?} ({}) on {}",
+                discovered.local_name, discovered.address, discovered.adapter.info
+            );
+            if sender.send(discovered).is_err() {
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn _setup() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    #[test]
+    fn scan_filter1() {
+        _setup();
+
+        let filter = DiscoveryFilter::default();
+        assert!(filter.accepts(Some("toio Core Cube"), None));
+
+        let filter = DiscoveryFilter::default().min_rssi(-60);
+        assert!(filter.accepts(Some("toio Core Cube"), Some(-60)));
+        assert!(filter.accepts(Some("toio Core Cube"), Some(-40)));
+        assert!(!filter.accepts(Some("toio Core Cube"), Some(-61)));
+        assert!(!filter.accepts(Some("toio Core Cube"), None));
+
+        let filter = DiscoveryFilter::default().name_prefix("toio Core Cube-");
+        assert!(filter.accepts(Some("toio Core Cube-a1B"), None));
+        assert!(!filter.accepts(Some("toio Core Cube"), None));
+        assert!(!filter.accepts(None, None));
+
+        let filter = DiscoveryFilter::default()
+            .name_regex(Regex::new(r"-[a-z][0-9]").unwrap())
+            .min_rssi(-70);
+        assert!(filter.accepts(Some("toio Core Cube-a1B"), Some(-50)));
+        assert!(!filter.accepts(Some("toio Core Cube-a1B"), Some(-80)));
+        assert!(!filter.accepts(Some("toio Core Cube-11B"), Some(-50)));
+    }
+
+    #[test]
+    fn scan_adapter_selector1() {
+        _setup();
+
+        let hci0 = "hci0 (usb:v1D6Bp0246d0537)";
+        let hci1 = "hci1 (usb:v1D6Bp0246d0537)";
+        assert!(AdapterSelector::All.is_selected(0, hci0));
+        assert!(AdapterSelector::All.is_selected(1, hci1));
+        assert!(AdapterSelector::Index(1).is_selected(1, hci1));
+        assert!(!AdapterSelector::Index(1).is_selected(0, hci0));
+        let name = AdapterSelector::Name("hci1".to_string());
+        assert!(name.is_selected(1, hci1));
+        assert!(!name.is_selected(0, hci0));
+        assert!(!name.is_selected(10, "hci10 (usb:v1D6Bp0246d0537)"));
+        assert!(AdapterSelector::Name(hci0.to_string()).is_selected(0, hci0));
+    }
+
+    #[test]
+    fn scan_spread1() {
+        _setup();
+
+        // the first adapter is used unless another one has fewer cubes
+        let candidates = vec![
+            vec![0, 0, 1],
+            vec![0, 0, 1],
+            vec![1, 1],
+            vec![0, 0, 1],
+            vec![0, 0],
+        ];
+        assert_eq!(least_loaded(&candidates), vec![0, 1, 1, 0, 0]);
+        assert!(least_loaded(&[]).is_empty());
+    }
+
+    #[test]
+    fn scan_sort1() {
+        _setup();
+
+        let mut rssi = vec![Some(-80), None, Some(-40), Some(-60)];
+        rssi.sort_by_key(|rssi| strongest_first(*rssi));
+        assert_eq!(rssi, vec![Some(-40), Some(-60), Some(-80), None]);
+    }
+}
@@ -0,0 +1,375 @@
+//! Recording of the traffic between an application and a cube
+//!
+//! `RecordingCube` wraps any `CubeInterface` and appends every write, read and
+//! notification to a session file, so that a problem seen in the field can be reproduced.
+//!
+//! # Session file
+//!
+//! JSON lines: one [`RecordEntry`] per line in the order of the traffic.
+//!
+//! ```text
+//! {"time_us":0,"kind":"write","characteristic":"MotorCtrl","uuid":"10b20102-5b3b-4571-9508-cf3efcd7bbae","data":[1,1,1,50,2,1,50]}
+//! {"time_us":1520,"kind":"read","characteristic":"BatteryInfo","uuid":"10b20108-5b3b-4571-9508-cf3efcd7bbae","data":[80]}
+//! {"time_us":15320,"kind":"notification","characteristic":"IdInfo","uuid":"10b20101-5b3b-4571-9508-cf3efcd7bbae","data":[3]}
+//! ```
+//!
+//! | field            | value                                                              |
+//! |------------------|--------------------------------------------------------------------|
+//! | `time_us`        | microseconds from the start of the recording                       |
+//! | `kind`           | `write`, `write_with_response`, `read` or `notification`           |
+//! | `characteristic` | name of the characteristic (`CoreCubeUuid::name()`), or `Unknown`  |
+//! | `uuid`           | UUID of the characteristic                                         |
+//! | `data`           | raw bytes (bytes returned by the cube for `read`)                  |
+
+use crate::characteristic::motor::RequestIdAllocator;
+use crate::characteristic::{CoreCubeUuid, NotificationData};
+use crate::notification_manager::NotificationManager;
+use crate::CoreCubeError;
+use async_trait::async_trait;
+use futures::stream::StreamExt;
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::future::Future;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::mpsc::{self as std_mpsc, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+use super::{ConnectOptions, CubeInterface, NotificationStream};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordKind {
+    Write,
+    WriteWithResponse,
+    Read,
+    Notification,
+}
+
+/// Line of the session file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordEntry {
+    pub time_us: u64,
+    pub kind: RecordKind,
+    pub characteristic: String,
+    pub uuid: Uuid,
+    pub data: Vec<u8>,
+}
+
+impl RecordEntry {
+    pub fn new(time: Duration, kind: RecordKind, uuid: Uuid, data: &[u8]) -> Self {
+        let characteristic = CoreCubeUuid::from_uuid(uuid)
+            .map(|characteristic| characteristic.name().to_string())
+            .unwrap_or_else(|| "Unknown".to_string());
+        Self {
+            time_us: u64::try_from(time.as_micros()).unwrap_or(u64::MAX),
+            kind,
+            characteristic,
+            uuid,
+            data: data.to_vec(),
+        }
+    }
+
+    /// time from the start of the recording
+    pub fn time(&self) -> Duration {
+        Duration::from_micros(self.time_us)
+    }
+}
+
+/// Interval of flushing the session file while entries are recorded
+pub const FLUSH_INTERVAL: Duration = Duration::from_millis(100);
+
+enum WriterMessage {
+    Entry(RecordEntry),
+    Flush(oneshot::Sender<()>),
+}
+
+/// Writer of a session file
+///
+/// Clones write to the same file. `record()` does not block: the entries are written by
+/// a writer thread, which flushes the file every `FLUSH_INTERVAL`, on `flush()` and when
+/// all clones are dropped.
+#[derive(Clone)]
+pub struct Recorder {
+    start: Instant,
+    sender: std_mpsc::Sender<WriterMessage>,
+}
+
+impl Recorder {
+    pub fn new(writer: impl Write + Send + 'static) -> Self {
+        let (sender, receiver) = std_mpsc::channel();
+        thread::spawn(move || write_session(writer, receiver));
+        Self {
+            start: Instant::now(),
+            sender,
+        }
+    }
+
+    /// create (or truncate) the session file
+    pub fn create(path: impl AsRef<Path>) -> Result<Self, CoreCubeError> {
+        Ok(Self::new(BufWriter::new(File::create(path)?)))
+    }
+
+    pub fn record(&self, kind: RecordKind, uuid: Uuid, data: &[u8]) {
+        let entry = RecordEntry::new(self.start.elapsed(), kind, uuid, data);
+        if self.sender.send(WriterMessage::Entry(entry)).is_err() {
+            warn!("recorder: writer is finished");
+        }
+    }
+
+    /// wait until the entries recorded so far are written and flushed
+    pub async fn flush(&self) {
+        let (done, flushed) = oneshot::channel();
+        if self.sender.send(WriterMessage::Flush(done)).is_ok() {
+            let _ = flushed.await;
+        }
+    }
+}
+
+fn write_session(mut writer: impl Write, receiver: std_mpsc::Receiver<WriterMessage>) {
+    let mut unflushed = false;
+    loop {
+        match receiver.recv_timeout(FLUSH_INTERVAL) {
+            Ok(WriterMessage::Entry(entry)) => {
+                let result = serde_json::to_writer(&mut writer, &entry)
+                    .map_err(std::io::Error::from)
+                    .and_then(|_| writer.write_all(b"\n"));
+                if let Err(err) = result {
+                    warn!("recorder: {}", err);
+                }
+                unflushed = true;
+                continue;
+            }
+            Ok(WriterMessage::Flush(done)) => {
+                flush_session(&mut writer);
+                let _ = done.send(());
+            }
+            Err(RecvTimeoutError::Timeout) if unflushed => flush_session(&mut writer),
+            Err(RecvTimeoutError::Timeout) => {}
+            // all recorders are dropped
+            Err(RecvTimeoutError::Disconnected) => {
+                flush_session(&mut writer);
+                break;
+            }
+        }
+        unflushed = false;
+    }
+}
+
+fn flush_session(writer: &mut impl Write) {
+    if let Err(err) = writer.flush() {
+        warn!("recorder: {}", err);
+    }
+}
+
+/// read entries of the session file
+pub fn read_session(path: impl AsRef<Path>) -> Result<Vec<RecordEntry>, CoreCubeError> {
+    parse_session(BufReader::new(File::open(path)?))
+}
+
+/// parse entries of the session (empty lines are ignored)
+pub fn parse_session(reader: impl BufRead) -> Result<Vec<RecordEntry>, CoreCubeError> {
+    let mut entries = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        if !line.trim().is_empty() {
+            entries.push(serde_json::from_str(&line)?);
+        }
+    }
+    Ok(entries)
+}
+
+/// Cube which records the traffic of the wrapped cube
+///
+/// Notifications are recorded once by an internal subscription which lasts from
+/// `connect()` to `disconnect()`, however many receivers and streams the application uses.
+pub struct RecordingCube<C> {
+    inner: C,
+    recorder: Recorder,
+    notification_recorder: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl<C> RecordingCube<C> {
+    pub fn new(inner: C, recorder: Recorder) -> Self {
+        Self {
+            inner,
+            recorder,
+            notification_recorder: Mutex::new(None),
+        }
+    }
+
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    pub fn recorder(&self) -> &Recorder {
+        &self.recorder
+    }
+
+    fn stop_notification_recorder(&self) {
+        if let Some(task) = self.notification_recorder.lock().unwrap().take() {
+            task.abort();
+        }
+    }
+}
+
+impl<C> Drop for RecordingCube<C> {
+    fn drop(&mut self) {
+        self.stop_notification_recorder();
+    }
+}
+
+#[async_trait]
+impl<C> CubeInterface for RecordingCube<C>
+where
+    C: CubeInterface + Send + Sync,
+{
+    async fn connect_with(&mut self, options: &ConnectOptions) -> Result<(), CoreCubeError> {
+        self.inner.connect_with(options).await?;
+        let mut notifications = self.inner.notification_stream().await?;
+        let recorder = self.recorder.clone();
+        let task = tokio::spawn(async move {
+            while let Some(data) = notifications.next().await {
+                recorder.record(RecordKind::Notification, data.uuid, &data.value);
+            }
+            debug!("notification recorder is finished");
+        });
+        if let Some(previous) = self.notification_recorder.lock().unwrap().replace(task) {
+            previous.abort();
+        }
+        Ok(())
+    }
+
+    async fn disconnect(&mut self) -> Result<(), CoreCubeError> {
+        self.stop_notification_recorder();
+        self.recorder.flush().await;
+        self.inner.disconnect().await
+    }
+
+    async fn read(&self, uuid: Uuid) -> Result<Vec<u8>, CoreCubeError> {
+        let data = self.inner.read(uuid).await?;
+        self.recorder.record(RecordKind::Read, uuid, &data);
+        Ok(data)
+    }
+
+    async fn write(&self, uuid: Uuid, bytes: &[u8]) -> Result<bool, CoreCubeError> {
+        self.recorder.record(RecordKind::Write, uuid, bytes);
+        self.inner.write(uuid, bytes).await
+    }
+
+    async fn write_with_response(&self, uuid: Uuid, bytes: &[u8]) -> Result<bool, CoreCubeError> {
+        self.recorder
+            .record(RecordKind::WriteWithResponse, uuid, bytes);
+        self.inner.write_with_response(uuid, bytes).await
+    }
+
+    async fn subscribe(&self, characteristic: CoreCubeUuid) -> Result<(), CoreCubeError> {
+        self.inner.subscribe(characteristic).await
+    }
+
+    async fn unsubscribe(&self, characteristic: CoreCubeUuid) -> Result<(), CoreCubeError> {
+        self.inner.unsubscribe(characteristic).await
+    }
+
+    fn notification_enabled(&self) -> Vec<CoreCubeUuid> {
+        self.inner.notification_enabled()
+    }
+
+    fn create_notification_receiver_with_manager(
+        &self,
+        nf_manager: Arc<NotificationManager<NotificationData>>,
+    ) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        self.inner
+            .create_notification_receiver_with_manager(nf_manager)
+    }
+
+    fn request_id_allocator(&self) -> RequestIdAllocator {
+        self.inner.request_id_allocator()
+    }
+
+    async fn notification_stream(&self) -> Result<NotificationStream, CoreCubeError> {
+        self.inner.notification_stream().await
+    }
+}
+
+/// in-memory session file (for tests)
+#[cfg(test)]
+#[derive(Clone, Default)]
+pub(crate) struct SharedBuffer(pub(crate) Arc<Mutex<Vec<u8>>>);
+
+#[cfg(test)]
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::characteristic::motor::MotorControl;
+    use crate::interface::virtual_cube::VirtualCube;
+    use crate::payload::ToPayload;
+
+    fn _setup() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    #[tokio::test]
+    async fn recorder1() {
+        _setup();
+        let buffer = SharedBuffer::default();
+        let virtual_cube = VirtualCube::default();
+        let mut cube = RecordingCube::new(virtual_cube.clone(), Recorder::new(buffer.clone()));
+        cube.connect().await.unwrap();
+
+        let motor = MotorControl::set_value(10, -10).unwrap().to_payload();
+        cube.write(CoreCubeUuid::MotorCtrl.uuid(), &motor)
+            .await
+            .unwrap();
+        virtual_cube.set_battery_level(80);
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(cube.read_battery().await.unwrap().level, 80);
+        cube.disconnect().await.unwrap();
+
+        let session = buffer.0.lock().unwrap().clone();
+        let entries = parse_session(session.as_slice()).unwrap();
+        let summary: Vec<(RecordKind, &str, Vec<u8>)> = entries
+            .iter()
+            .map(|entry| {
+                (
+                    entry.kind,
+                    entry.characteristic.as_str(),
+                    entry.data.clone(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (RecordKind::Write, "MotorCtrl", motor),
+                (RecordKind::Notification, "BatteryInfo", vec![80]),
+                (RecordKind::Read, "BatteryInfo", vec![80]),
+            ]
+        );
+        assert!(entries
+            .windows(2)
+            .all(|pair| pair[0].time_us <= pair[1].time_us));
+        assert_eq!(entries[0].uuid, CoreCubeUuid::MotorCtrl.uuid());
+
+        let line = String::from_utf8(session).unwrap();
+        assert!(line.starts_with(r#"{"time_us":"#));
+        assert!(line.contains(r#""kind":"write","characteristic":"MotorCtrl","#));
+    }
+}
@@ -0,0 +1,590 @@
+//! Replay of a recorded session
+//!
+//! `ReplayCube` plays the notifications of a session file (see [`super::recorder`])
+//! with their original timing (or faster), and checks that the application writes
+//! the same commands as the recorded ones.
+//!
+//! The replay starts when the first notification receiver (or stream) is created
+//! after the connection, so no notification is sent before the application listens.
+
+use crate::characteristic::motor::RequestIdAllocator;
+use crate::characteristic::{CoreCubeUuid, NotificationData};
+use crate::describe::describe;
+use crate::notification_manager::NotificationManager;
+use crate::CoreCubeError;
+use async_trait::async_trait;
+use log::{debug, warn};
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{broadcast, watch};
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+use super::recorder::{read_session, RecordEntry, RecordKind};
+use super::{ConnectOptions, CubeInterface, NotificationStream, NOTIFY_CHARACTERISTICS};
+
+const NOTIFICATION_CAPACITY: usize = 256;
+
+/// Timing of the replayed notifications
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ReplayTiming {
+    /// same intervals as the recording
+    Original,
+    /// intervals divided by the factor (e.g. 2.0 is twice as fast)
+    Accelerated(f64),
+    /// all notifications at once
+    Immediate,
+}
+
+impl ReplayTiming {
+    fn scale(&self, time: Duration) -> Duration {
+        match self {
+            ReplayTiming::Original => time,
+            ReplayTiming::Accelerated(factor) if *factor > 0.0 => time.div_f64(*factor),
+            _ => Duration::ZERO,
+        }
+    }
+}
+
+/// Number of the recorded writes which are searched for the write of the application
+///
+/// A write which is not found in them is reported as unexpected, so one extra write
+/// does not skip the rest of the recording.
+const RESYNC_WINDOW: usize = 32;
+
+/// Difference between the writes of the application and the recording
+///
+/// `index` is the count of the writes of the application before the divergence.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Divergence {
+    /// the application writes what is not recorded here
+    Unexpected {
+        index: usize,
+        uuid: Uuid,
+        data: Vec<u8>,
+    },
+    /// the application skips the recorded write
+    Missing { index: usize, expected: RecordEntry },
+}
+
+struct ReplayInner {
+    notification_sender: Option<broadcast::Sender<NotificationData>>,
+    notification_enabled: Vec<CoreCubeUuid>,
+    player: Option<JoinHandle<()>>,
+    /// recorded writes which are not made yet
+    writes: VecDeque<RecordEntry>,
+    write_count: usize,
+    divergences: Vec<Divergence>,
+    /// recorded reads of each characteristic (the last one is repeated)
+    reads: HashMap<Uuid, VecDeque<Vec<u8>>>,
+}
+
+/// Cube which replays a recorded session
+///
+/// Clones share the same replay, so a test can keep one handle to check the
+/// divergences while the application under test owns another.
+#[derive(Clone)]
+pub struct ReplayCube {
+    notifications: Arc<Vec<RecordEntry>>,
+    timing: ReplayTiming,
+    inner: Arc<Mutex<ReplayInner>>,
+    replayed: Arc<watch::Sender<bool>>,
+    request_id_allocator: RequestIdAllocator,
+}
+
+impl ReplayCube {
+    pub fn new(entries: Vec<RecordEntry>, timing: ReplayTiming) -> Self {
+        let mut notifications = Vec::new();
+        let mut writes = VecDeque::new();
+        let mut reads: HashMap<Uuid, VecDeque<Vec<u8>>> = HashMap::new();
+        for entry in entries {
+            match entry.kind {
+                RecordKind::Notification => notifications.push(entry),
+                RecordKind::Write | RecordKind::WriteWithResponse => writes.push_back(entry),
+                RecordKind::Read => reads.entry(entry.uuid).or_default().push_back(entry.data),
+            }
+        }
+        let (replayed, _) = watch::channel(false);
+        Self {
+            notifications: Arc::new(notifications),
+            timing,
+            inner: Arc::new(Mutex::new(ReplayInner {
+                notification_sender: None,
+                notification_enabled: Vec::new(),
+                player: None,
+                writes,
+                write_count: 0,
+                divergences: Vec::new(),
+                reads,
+            })),
+            replayed: Arc::new(replayed),
+            request_id_allocator: RequestIdAllocator::new(),
+        }
+    }
+
+    /// replay the session file
+    pub fn open(path: impl AsRef<Path>, timing: ReplayTiming) -> Result<Self, CoreCubeError> {
+        Ok(Self::new(read_session(path)?, timing))
+    }
+
+    /// writes which do not match the recording so far
+    pub fn divergences(&self) -> Vec<Divergence> {
+        self.inner.lock().unwrap().divergences.clone()
+    }
+
+    /// recorded writes which the application has not made yet
+    pub fn unmatched_writes(&self) -> Vec<RecordEntry> {
+        self.inner.lock().unwrap().writes.iter().cloned().collect()
+    }
+
+    /// start the replay without waiting for a notification receiver
+    pub fn start(&self) -> Result<(), CoreCubeError> {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.notification_sender.is_none() {
+            return Err(CoreCubeError::NotConnected);
+        }
+        self.start_player(&mut inner);
+        Ok(())
+    }
+
+    /// start the replay if it is not started since the connection
+    fn start_player(&self, inner: &mut ReplayInner) {
+        if inner.player.is_some() {
+            return;
+        }
+        debug!("replay: start");
+        inner.player = Some(tokio::spawn(play(
+            self.notifications.clone(),
+            self.timing,
+            self.inner.clone(),
+            self.replayed.clone(),
+        )));
+    }
+
+    /// subscribe to the notifications and start the replay
+    fn subscribe_notifications(&self) -> Option<broadcast::Receiver<NotificationData>> {
+        let mut inner = self.inner.lock().unwrap();
+        let receiver = inner.notification_sender.as_ref()?.subscribe();
+        self.start_player(&mut inner);
+        Some(receiver)
+    }
+
+    /// wait until all notifications are replayed
+    pub async fn wait_replayed(&self) {
+        let mut replayed = self.replayed.subscribe();
+        let _ = replayed.wait_for(|replayed| *replayed).await;
+    }
+
+    fn check_write(&self, uuid: Uuid, bytes: &[u8]) -> Result<(), CoreCubeError> {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.notification_sender.is_none() {
+            return Err(CoreCubeError::NotConnected);
+        }
+        let index = inner.write_count;
+        inner.write_count += 1;
+        // re-synchronize with the next recorded write which matches
+        let found = inner
+            .writes
+            .iter()
+            .take(RESYNC_WINDOW)
+            .position(|expected| expected.uuid == uuid && expected.data == bytes);
+        let Some(skipped) = found else {
+            warn!(
+                "replay: write {} is not recorded: {}",
+                index,
+                describe(uuid, bytes)
+            );
+            inner.divergences.push(Divergence::Unexpected {
+                index,
+                uuid,
+                data: bytes.to_vec(),
+            });
+            return Ok(());
+        };
+        let missing: Vec<RecordEntry> = inner.writes.drain(..skipped).collect();
+        for expected in missing {
+            warn!(
+                "replay: write {} is missing: {}",
+                index,
+                describe(expected.uuid, &expected.data)
+            );
+            inner
+                .divergences
+                .push(Divergence::Missing { index, expected });
+        }
+        inner.writes.pop_front();
+        Ok(())
+    }
+}
+
+/// send the notifications at the recorded times (relative to the first one)
+async fn play(
+    notifications: Arc<Vec<RecordEntry>>,
+    timing: ReplayTiming,
+    inner: Arc<Mutex<ReplayInner>>,
+    replayed: Arc<watch::Sender<bool>>,
+) {
+    let start = tokio::time::Instant::now();
+    let origin = notifications
+        .first()
+        .map(|entry| entry.time())
+        .unwrap_or_default();
+    for entry in notifications.iter() {
+        tokio::time::sleep_until(start + timing.scale(entry.time().saturating_sub(origin))).await;
+        let inner = inner.lock().unwrap();
+        let subscribed = CoreCubeUuid::from_uuid(entry.uuid)
+            .is_some_and(|characteristic| inner.notification_enabled.contains(&characteristic));
+        if let (true, Some(sender)) = (subscribed, &inner.notification_sender) {
+            debug!("replay notification: {}", describe(entry.uuid, &entry.data));
+            // no receiver is not an error (same as a real cube)
+            let _ = sender.send(NotificationData {
+                uuid: entry.uuid,
+                value: entry.data.clone(),
+            });
+        }
+    }
+    replayed.send_replace(true);
+}
+
+#[async_trait]
+impl CubeInterface for ReplayCube {
+    async fn connect_with(&mut self, options: &ConnectOptions) -> Result<(), CoreCubeError> {
+        if !options
+            .notifications
+            .iter()
+            .all(|notified| NOTIFY_CHARACTERISTICS.contains(notified))
+        {
+            return Err(CoreCubeError::WrongParameter);
+        }
+        let mut inner = self.inner.lock().unwrap();
+        let (sender, _) = broadcast::channel(NOTIFICATION_CAPACITY);
+        inner.notification_sender = Some(sender);
+        inner.notification_enabled = options.notifications.clone();
+        self.replayed.send_replace(false);
+        // the replay starts with the first receiver (or start())
+        if let Some(previous) = inner.player.take() {
+            previous.abort();
+        }
+        Ok(())
+    }
+
+    async fn disconnect(&mut self) -> Result<(), CoreCubeError> {
+        let mut inner = self.inner.lock().unwrap();
+        if let Some(player) = inner.player.take() {
+            player.abort();
+        }
+        // dropping the sender finishes all notification receivers
+        inner.notification_sender = None;
+        inner.notification_enabled.clear();
+        Ok(())
+    }
+
+    async fn read(&self, uuid: Uuid) -> Result<Vec<u8>, CoreCubeError> {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.notification_sender.is_none() {
+            return Err(CoreCubeError::NotConnected);
+        }
+        let reads = inner
+            .reads
+            .get_mut(&uuid)
+            .ok_or(CoreCubeError::CharacteristicNotFound(uuid))?;
+        if reads.len() > 1 {
+            Ok(reads.pop_front().unwrap_or_default())
+        } else {
+            reads
+                .front()
+                .cloned()
+                .ok_or(CoreCubeError::CharacteristicNotFound(uuid))
+        }
+    }
+
+    async fn write(&self, uuid: Uuid, bytes: &[u8]) -> Result<bool, CoreCubeError> {
+        self.check_write(uuid, bytes)?;
+        Ok(true)
+    }
+
+    async fn write_with_response(&self, uuid: Uuid, bytes: &[u8]) -> Result<bool, CoreCubeError> {
+        self.check_write(uuid, bytes)?;
+        Ok(true)
+    }
+
+    async fn subscribe(&self, characteristic: CoreCubeUuid) -> Result<(), CoreCubeError> {
+        if !NOTIFY_CHARACTERISTICS.contains(&characteristic) {
+            return Err(CoreCubeError::WrongParameter);
+        }
+        let mut inner = self.inner.lock().unwrap();
+        if inner.notification_sender.is_none() {
+            return Err(CoreCubeError::NotConnected);
+        }
+        if !inner.notification_enabled.contains(&characteristic) {
+            inner.notification_enabled.push(characteristic);
+        }
+        Ok(())
+    }
+
+    async fn unsubscribe(&self, characteristic: CoreCubeUuid) -> Result<(), CoreCubeError> {
+        self.inner
+            .lock()
+            .unwrap()
+            .notification_enabled
+            .retain(|notified| *notified != characteristic);
+        Ok(())
+    }
+
+    fn notification_enabled(&self) -> Vec<CoreCubeUuid> {
+        self.inner.lock().unwrap().notification_enabled.clone()
+    }
+
+    fn create_notification_receiver_with_manager(
+        &self,
+        nf_manager: Arc<NotificationManager<NotificationData>>,
+    ) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        let receiver = self.subscribe_notifications();
+        Box::pin(async move {
+            let Some(mut receiver) = receiver else {
+                warn!("replay cube is not connected");
+                return;
+            };
+            loop {
+                match receiver.recv().await {
+                    Ok(data) => {
+                        let _ = nf_manager.dispatch(data).await;
+                    }
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!("replay cube: {} notifications are dropped", skipped);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        })
+    }
+
+    fn request_id_allocator(&self) -> RequestIdAllocator {
+        self.request_id_allocator.clone()
+    }
+
+    async fn notification_stream(&self) -> Result<NotificationStream, CoreCubeError> {
+        let receiver = self
+            .subscribe_notifications()
+            .ok_or(CoreCubeError::NotConnected)?;
+        Ok(Box::pin(futures::stream::unfold(
+            receiver,
+            |mut receiver| async move {
+                loop {
+                    match receiver.recv().await {
+                        Ok(data) => return Some((data, receiver)),
+                        Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                            warn!("replay cube: {} notifications are dropped", skipped);
+                        }
+                        Err(broadcast::error::RecvError::Closed) => return None,
+                    }
+                }
+            },
+        )))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::characteristic::motor::MotorControl;
+    use crate::interface::recorder::{Recorder, RecordingCube, SharedBuffer};
+    use crate::interface::virtual_cube::VirtualCube;
+    use crate::payload::ToPayload;
+    use futures::StreamExt;
+
+    fn _setup() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    fn notification(time_ms: u64, uuid: CoreCubeUuid, data: &[u8]) -> RecordEntry {
+        RecordEntry::new(
+            Duration::from_millis(time_ms),
+            RecordKind::Notification,
+            uuid.uuid(),
+            data,
+        )
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn replay_cube1() {
+        _setup();
+        // record a session with the virtual cube
+        let buffer = SharedBuffer::default();
+        let virtual_cube = VirtualCube::default();
+        let mut recording = RecordingCube::new(virtual_cube.clone(), Recorder::new(buffer.clone()));
+        recording.connect().await.unwrap();
+        let motor1 = MotorControl::set_value(10, 10).unwrap().to_payload();
+        let motor2 = MotorControl::set_value(0, 0).unwrap().to_payload();
+        recording
+            .write(CoreCubeUuid::MotorCtrl.uuid(), &motor1)
+            .await
+            .unwrap();
+        virtual_cube.press_button();
+        virtual_cube.set_battery_level(60);
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        recording.read_battery().await.unwrap();
+        recording
+            .write(CoreCubeUuid::MotorCtrl.uuid(), &motor2)
+            .await
+            .unwrap();
+        recording.disconnect().await.unwrap();
+        let session = buffer.0.lock().unwrap().clone();
+        let entries = crate::interface::recorder::parse_session(session.as_slice()).unwrap();
+
+        // replay it
+        let replay = ReplayCube::new(entries, ReplayTiming::Immediate);
+        let mut cube = replay.clone();
+        cube.connect().await.unwrap();
+        let mut notifications = cube.notification_stream().await.unwrap();
+        cube.write(CoreCubeUuid::MotorCtrl.uuid(), &motor1)
+            .await
+            .unwrap();
+        assert_eq!(
+            notifications.next().await.unwrap().uuid,
+            CoreCubeUuid::ButtonInfo.uuid()
+        );
+        assert_eq!(notifications.next().await.unwrap().value, vec![60]);
+        assert_eq!(cube.read_battery().await.unwrap().level, 60);
+        assert!(replay.divergences().is_empty());
+
+        // different command from the recording
+        cube.write(CoreCubeUuid::MotorCtrl.uuid(), &motor1)
+            .await
+            .unwrap();
+        assert_eq!(
+            replay.divergences(),
+            vec![Divergence::Unexpected {
+                index: 1,
+                uuid: CoreCubeUuid::MotorCtrl.uuid(),
+                data: motor1,
+            }]
+        );
+        assert_eq!(replay.unmatched_writes().len(), 1);
+        assert_eq!(replay.unmatched_writes()[0].data, motor2);
+        cube.disconnect().await.unwrap();
+    }
+
+    fn write(time_ms: u64, data: &[u8]) -> RecordEntry {
+        RecordEntry::new(
+            Duration::from_millis(time_ms),
+            RecordKind::Write,
+            CoreCubeUuid::MotorCtrl.uuid(),
+            data,
+        )
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn replay_cube_divergence1() {
+        _setup();
+        let recorded = [vec![0x01, 0x01], vec![0x01, 0x02], vec![0x01, 0x03]];
+        let entries = recorded
+            .iter()
+            .enumerate()
+            .map(|(time, data)| write(time as u64, data))
+            .collect();
+        let replay = ReplayCube::new(entries, ReplayTiming::Immediate);
+        let mut cube = replay.clone();
+        cube.connect().await.unwrap();
+
+        // one inserted write does not make the following writes diverge
+        let motor = CoreCubeUuid::MotorCtrl.uuid();
+        for data in [&recorded[0], &vec![0xff], &recorded[1], &recorded[2]] {
+            cube.write(motor, data).await.unwrap();
+        }
+        assert_eq!(
+            replay.divergences(),
+            vec![Divergence::Unexpected {
+                index: 1,
+                uuid: motor,
+                data: vec![0xff],
+            }]
+        );
+        assert!(replay.unmatched_writes().is_empty());
+        cube.disconnect().await.unwrap();
+
+        // a skipped write is reported as missing
+        let entries = recorded
+            .iter()
+            .enumerate()
+            .map(|(time, data)| write(time as u64, data))
+            .collect();
+        let replay = ReplayCube::new(entries, ReplayTiming::Immediate);
+        let mut cube = replay.clone();
+        cube.connect().await.unwrap();
+        cube.write(motor, &recorded[0]).await.unwrap();
+        cube.write(motor, &recorded[2]).await.unwrap();
+        assert_eq!(
+            replay.divergences(),
+            vec![Divergence::Missing {
+                index: 1,
+                expected: write(1, &recorded[1]),
+            }]
+        );
+        assert!(replay.unmatched_writes().is_empty());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn replay_cube_timing1() {
+        _setup();
+        let entries = vec![
+            notification(1000, CoreCubeUuid::BatteryInfo, &[90]),
+            notification(1100, CoreCubeUuid::ButtonInfo, &[0x01, 0x80]),
+            notification(1300, CoreCubeUuid::BatteryInfo, &[80]),
+        ];
+        let replay = ReplayCube::new(entries, ReplayTiming::Accelerated(4.0));
+        let mut cube = replay.clone();
+        cube.connect_with(&ConnectOptions::new(&[CoreCubeUuid::BatteryInfo]))
+            .await
+            .unwrap();
+        let start = tokio::time::Instant::now();
+        let mut notifications = cube.notification_stream().await.unwrap();
+        assert_eq!(notifications.next().await.unwrap().value, vec![90]);
+        // button is not subscribed
+        assert_eq!(notifications.next().await.unwrap().value, vec![80]);
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(70), "{:?}", elapsed);
+        assert!(elapsed < Duration::from_millis(300), "{:?}", elapsed);
+        replay.wait_replayed().await;
+
+        assert_eq!(
+            ReplayTiming::Original.scale(Duration::from_millis(300)),
+            Duration::from_millis(300)
+        );
+        assert_eq!(
+            ReplayTiming::Immediate.scale(Duration::from_millis(300)),
+            Duration::ZERO
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn replay_cube_start1() {
+        _setup();
+        let entries = vec![
+            notification(0, CoreCubeUuid::BatteryInfo, &[90]),
+            notification(0, CoreCubeUuid::BatteryInfo, &[80]),
+        ];
+        let replay = ReplayCube::new(entries.clone(), ReplayTiming::Immediate);
+        let mut cube = replay.clone();
+        cube.connect().await.unwrap();
+        // nothing is replayed before the application subscribes
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        let mut notifications = cube.notification_stream().await.unwrap();
+        assert_eq!(notifications.next().await.unwrap().value, vec![90]);
+        assert_eq!(notifications.next().await.unwrap().value, vec![80]);
+        replay.wait_replayed().await;
+        cube.disconnect().await.unwrap();
+
+        // replay without receivers
+        let replay = ReplayCube::new(entries, ReplayTiming::Immediate);
+        assert!(matches!(replay.start(), Err(CoreCubeError::NotConnected)));
+        let mut cube = replay.clone();
+        cube.connect().await.unwrap();
+        replay.start().unwrap();
+        replay.wait_replayed().await;
+    }
+}
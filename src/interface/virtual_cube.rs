@@ -0,0 +1,1647 @@
+//! In-memory toio core cube
+//!
+//! `VirtualCube` accepts the same payloads as a real cube and answers with the
+//! notifications the firmware would send, so control logic can be tested without hardware.
+//! Motor commands move the cube on a toio mat (see [`crate::simulator`]) while the
+//! simulation time is advanced by [`VirtualCube::advance`] or [`VirtualCube::run`].
+
+use crate::characteristic::battery::BatteryInformation;
+use crate::characteristic::button::{ButtonInformation, ButtonState};
+use crate::characteristic::configuration::ResponseBleProtocolVersionData;
+use crate::characteristic::id::{IdInformation, PositionIdData, StandardIdData};
+use crate::characteristic::indicator::Color;
+use crate::characteristic::motor::{
+    MotorSpeedInformation, MovingDirection, Priority, RequestIdAllocator, RotationDirection,
+};
+use crate::characteristic::sensor::{
+    MagneticSensorInformation, MotionDetectionInformation, PostureAngleEulerInformation,
+    PostureAngleQuaternionsInformation,
+};
+use crate::characteristic::CoreCubeUuid;
+use crate::characteristic::NotificationData;
+use crate::describe::describe;
+use crate::notification_manager::NotificationManager;
+use crate::payload::ToPayload;
+use crate::position::{CubeLocation, ToioMat};
+use crate::simulator::id_sensor::ID_SENSOR_PERIOD;
+use crate::simulator::{AccelerationControl, IdSensor, Pose, TargetController};
+use crate::CoreCubeError;
+use async_trait::async_trait;
+use log::{debug, warn};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use std::vec::Vec;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use super::{ConnectOptions, CubeInterface, NotificationStream, NOTIFY_CHARACTERISTICS};
+
+/// BLE protocol version reported by the virtual cube
+pub const VIRTUAL_CUBE_PROTOCOL_VERSION: &[u8; 5] = b"2.4.0";
+
+/// Maximum motor speed indication value accepted by the firmware
+const MOTOR_SPEED_MAX: u8 = 115;
+
+/// Number of notifications buffered for slow receivers
+const NOTIFICATION_CAPACITY: usize = 256;
+
+/// The cube leaves a standard ID when it moves more than this distance (mat units)
+const STANDARD_ID_RANGE: f64 = 10.0;
+
+/// Motor state
+
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct VirtualMotorState {
+    /// signed speed indication value (negative value is backward)
+    pub left: i16,
+    pub right: i16,
+    /// time left until the motors stop (None: run until next command)
+    pub remaining: Option<Duration>,
+}
+
+/// Indicator state
+
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct VirtualIndicatorState {
+    /// current color (None: turned off)
+    pub color: Option<Color>,
+    /// (duration, color) pairs of the running sequence (duration 0: forever)
+    pub sequence: Vec<(Duration, Color)>,
+    /// remaining repeat count of the sequence (0: forever)
+    pub repeat: u8,
+    pub position: usize,
+    pub elapsed: Duration,
+}
+
+/// Sound state
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VirtualSoundState {
+    Stopped,
+    SoundEffect { id: u8, volume: u8 },
+    MidiNotes { repeat: u8, notes: Vec<u8> },
+}
+
+/// Configuration written to the cube
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct VirtualCubeConfig {
+    pub horizontal_detection_threshold: u8,
+    pub collision_detection_threshold: u8,
+    pub double_tap_detection_time_interval: u8,
+    pub id_notification_interval: u8,
+    pub id_notification_condition: u8,
+    pub id_missed_notification_sensitivity: u8,
+    pub magnetic_sensor_function: u8,
+    pub magnetic_sensor_interval: u8,
+    pub magnetic_sensor_condition: u8,
+    pub motor_speed_enabled: bool,
+    pub posture_angle_data_type: u8,
+    pub posture_angle_interval: u8,
+    pub posture_angle_condition: u8,
+}
+
+impl Default for VirtualCubeConfig {
+    fn default() -> Self {
+        Self {
+            horizontal_detection_threshold: 45,
+            collision_detection_threshold: 7,
+            double_tap_detection_time_interval: 5,
+            id_notification_interval: 0,
+            id_notification_condition: 0,
+            id_missed_notification_sensitivity: 7,
+            magnetic_sensor_function: 0,
+            magnetic_sensor_interval: 0,
+            magnetic_sensor_condition: 0,
+            motor_speed_enabled: false,
+            posture_angle_data_type: 0,
+            posture_angle_interval: 0,
+            posture_angle_condition: 0,
+        }
+    }
+}
+
+/// Snapshot of the whole virtual cube
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct VirtualCubeState {
+    pub connected: bool,
+    /// mat under the cube (Position ID is missed outside of its rect)
+    pub mat: ToioMat,
+    pub pose: Pose,
+    /// the cube is held in the air (wheels do not move the cube)
+    pub lifted: bool,
+    pub motor: VirtualMotorState,
+    pub indicator: VirtualIndicatorState,
+    pub sound: VirtualSoundState,
+    pub id: IdInformation,
+    pub button: ButtonState,
+    pub battery_level: usize,
+    pub motion: MotionDetectionInformation,
+    pub posture_angle_euler: PostureAngleEulerInformation,
+    pub posture_angle_quaternions: PostureAngleQuaternionsInformation,
+    pub magnetic: MagneticSensorInformation,
+    pub config: VirtualCubeConfig,
+}
+
+impl Default for VirtualCubeState {
+    fn default() -> Self {
+        Self {
+            connected: false,
+            mat: ToioMat::ToioCollectionMatRing,
+            pose: Pose {
+                x: 250.0,
+                y: 250.0,
+                angle: 0.0,
+            },
+            lifted: true,
+            motor: VirtualMotorState::default(),
+            indicator: VirtualIndicatorState::default(),
+            sound: VirtualSoundState::Stopped,
+            id: IdInformation::PositionIdMissed,
+            button: ButtonState::Released,
+            battery_level: 100,
+            motion: MotionDetectionInformation {
+                horizontal: true,
+                ..MotionDetectionInformation::default()
+            },
+            posture_angle_euler: PostureAngleEulerInformation::default(),
+            posture_angle_quaternions: PostureAngleQuaternionsInformation::default(),
+            magnetic: MagneticSensorInformation::default(),
+            config: VirtualCubeConfig::default(),
+        }
+    }
+}
+
+struct VirtualCubeInner {
+    state: VirtualCubeState,
+    notification_sender: Option<broadcast::Sender<NotificationData>>,
+    notification_enabled: Vec<CoreCubeUuid>,
+    acceleration: Option<AccelerationControl>,
+    target: TargetController,
+    id_sensor: IdSensor,
+    /// standard ID under the cube and the pose where the cube was placed on it
+    standard_id: Option<(StandardIdData, Pose)>,
+    /// simulation time not yet processed
+    pending: Duration,
+}
+
+impl VirtualCubeInner {
+    fn notify(&self, uuid: CoreCubeUuid, value: Vec<u8>) {
+        if !self.notification_enabled.contains(&uuid) {
+            return;
+        }
+        if let Some(sender) = &self.notification_sender {
+            debug!(
+                "virtual cube notification: {}",
+                describe(uuid.uuid(), &value)
+            );
+            // no receiver is not an error (same as a real cube)
+            let _ = sender.send(NotificationData {
+                uuid: uuid.uuid(),
+                value,
+            });
+        }
+    }
+
+    fn set_motor(&mut self, left: i16, right: i16, remaining: Option<Duration>) {
+        let changed = (self.state.motor.left != left) || (self.state.motor.right != right);
+        self.state.motor = VirtualMotorState {
+            left,
+            right,
+            remaining,
+        };
+        if changed && self.state.config.motor_speed_enabled {
+            self.notify(CoreCubeUuid::MotorCtrl, self.motor_speed_payload());
+        }
+    }
+
+    fn motor_speed_payload(&self) -> Vec<u8> {
+        let speed = |value: i16| value.unsigned_abs().min(MOTOR_SPEED_MAX.into()) as u8;
+        MotorSpeedInformation {
+            left: speed(self.state.motor.left),
+            right: speed(self.state.motor.right),
+        }
+        .to_payload()
+    }
+
+    fn write(&mut self, uuid: Uuid, bytes: &[u8]) -> Result<(), CoreCubeError> {
+        if !self.state.connected {
+            return Err(CoreCubeError::NotConnected);
+        }
+        if bytes.is_empty() {
+            return Err(CoreCubeError::WrongParameter);
+        }
+        if uuid == CoreCubeUuid::MotorCtrl.uuid() {
+            self.write_motor(bytes)
+        } else if uuid == CoreCubeUuid::LightCtrl.uuid() {
+            self.write_light(bytes)
+        } else if uuid == CoreCubeUuid::SoundCtrl.uuid() {
+            self.write_sound(bytes)
+        } else if uuid == CoreCubeUuid::SensorInfo.uuid() {
+            self.write_sensor(bytes)
+        } else if uuid == CoreCubeUuid::Config.uuid() {
+            self.write_config(bytes)
+        } else {
+            Err(CoreCubeError::WrongParameter)
+        }
+    }
+
+    fn write_motor(&mut self, bytes: &[u8]) -> Result<(), CoreCubeError> {
+        match bytes[0] {
+            // motor control / motor control with specified duration
+            0x01u8 | 0x02u8 => {
+                let length = if bytes[0] == 0x01u8 { 7 } else { 8 };
+                if bytes.len() < length {
+                    return Err(CoreCubeError::WrongParameter);
+                }
+                let mut left: i16 = 0;
+                let mut right: i16 = 0;
+                for drive in bytes[1..7].chunks(3) {
+                    let speed = i16::from(drive[2].min(MOTOR_SPEED_MAX));
+                    let speed = match drive[1] {
+                        0x01u8 => speed,
+                        0x02u8 => -speed,
+                        _ => return Err(CoreCubeError::WrongParameter),
+                    };
+                    match drive[0] {
+                        0x01u8 => left = speed,
+                        0x02u8 => right = speed,
+                        _ => return Err(CoreCubeError::WrongParameter),
+                    }
+                }
+                let remaining = period(bytes.get(7));
+                self.cancel_target();
+                self.acceleration = None;
+                self.set_motor(left, right, remaining);
+                Ok(())
+            }
+            // motor control with target specified / multiple targets specified
+            0x03u8 | 0x04u8 => {
+                if bytes.len() < 2 {
+                    return Err(CoreCubeError::WrongParameter);
+                }
+                let pose = self.position_id_pose();
+                for response in self.target.write(bytes, pose) {
+                    self.notify(CoreCubeUuid::MotorCtrl, response.to_payload());
+                }
+                if self.target.is_active() {
+                    self.acceleration = None;
+                    self.state.motor.remaining = None;
+                }
+                Ok(())
+            }
+            // motor control with acceleration specified
+            0x05u8 => {
+                if bytes.len() < 9 {
+                    return Err(CoreCubeError::WrongParameter);
+                }
+                let rotation_direction = match bytes[5] {
+                    0x00u8 => RotationDirection::Positive,
+                    0x01u8 => RotationDirection::Negative,
+                    _ => return Err(CoreCubeError::WrongParameter),
+                };
+                let moving_direction = match bytes[6] {
+                    0x00u8 => MovingDirection::Forward,
+                    0x01u8 => MovingDirection::Backward,
+                    _ => return Err(CoreCubeError::WrongParameter),
+                };
+                let priority = match bytes[7] {
+                    0x00u8 => Priority::TranslationalSpeed,
+                    0x01u8 => Priority::RotationVelocity,
+                    _ => return Err(CoreCubeError::WrongParameter),
+                };
+                self.cancel_target();
+                let motor = self.state.motor;
+                self.acceleration = Some(AccelerationControl::new(
+                    bytes[1],
+                    bytes[2],
+                    u16::from_le_bytes([bytes[3], bytes[4]]),
+                    rotation_direction,
+                    moving_direction,
+                    priority,
+                    f64::from(motor.left + motor.right) / 2.0,
+                ));
+                self.state.motor.remaining = period(bytes.get(8));
+                Ok(())
+            }
+            _ => Err(CoreCubeError::WrongParameter),
+        }
+    }
+
+    fn write_light(&mut self, bytes: &[u8]) -> Result<(), CoreCubeError> {
+        let indicator = &mut self.state.indicator;
+        match bytes[0] {
+            // turn off all indicators / turn off a specific indicator
+            0x01u8 | 0x02u8 => {
+                *indicator = VirtualIndicatorState::default();
+                Ok(())
+            }
+            // turning the indicator on and off
+            0x03u8 => {
+                let sequence = parse_indicator_params(bytes.get(1..).unwrap_or_default(), 1)?;
+                *indicator = VirtualIndicatorState {
+                    color: Some(sequence[0].1),
+                    sequence,
+                    repeat: 1,
+                    ..VirtualIndicatorState::default()
+                };
+                Ok(())
+            }
+            // repeated turning on and off of indicator
+            0x04u8 => {
+                if bytes.len() < 3 {
+                    return Err(CoreCubeError::WrongParameter);
+                }
+                let sequence = parse_indicator_params(&bytes[3..], bytes[2].into())?;
+                *indicator = VirtualIndicatorState {
+                    color: sequence.first().map(|(_, color)| *color),
+                    sequence,
+                    repeat: bytes[1],
+                    ..VirtualIndicatorState::default()
+                };
+                Ok(())
+            }
+            _ => Err(CoreCubeError::WrongParameter),
+        }
+    }
+
+    fn write_sound(&mut self, bytes: &[u8]) -> Result<(), CoreCubeError> {
+        match bytes[0] {
+            0x01u8 => {
+                self.state.sound = VirtualSoundState::Stopped;
+                Ok(())
+            }
+            0x02u8 => {
+                if bytes.len() < 3 {
+                    return Err(CoreCubeError::WrongParameter);
+                }
+                self.state.sound = VirtualSoundState::SoundEffect {
+                    id: bytes[1],
+                    volume: bytes[2],
+                };
+                Ok(())
+            }
+            0x03u8 => {
+                if bytes.len() < 3 || bytes.len() < 3 + (bytes[2] as usize) * 3 {
+                    return Err(CoreCubeError::WrongParameter);
+                }
+                let notes = bytes[3..3 + (bytes[2] as usize) * 3]
+                    .chunks(3)
+                    .map(|note| note[1])
+                    .collect();
+                self.state.sound = VirtualSoundState::MidiNotes {
+                    repeat: bytes[1],
+                    notes,
+                };
+                Ok(())
+            }
+            _ => Err(CoreCubeError::WrongParameter),
+        }
+    }
+
+    fn write_sensor(&mut self, bytes: &[u8]) -> Result<(), CoreCubeError> {
+        match bytes[0] {
+            0x81u8 => {
+                self.notify(CoreCubeUuid::SensorInfo, self.state.motion.to_payload());
+                Ok(())
+            }
+            0x82u8 => {
+                self.notify(CoreCubeUuid::SensorInfo, self.state.magnetic.to_payload());
+                Ok(())
+            }
+            0x83u8 => match bytes.get(1) {
+                Some(0x01u8) => {
+                    self.notify(
+                        CoreCubeUuid::SensorInfo,
+                        self.state.posture_angle_euler.to_payload(),
+                    );
+                    Ok(())
+                }
+                Some(0x02u8) => {
+                    self.notify(
+                        CoreCubeUuid::SensorInfo,
+                        self.state.posture_angle_quaternions.to_payload(),
+                    );
+                    Ok(())
+                }
+                _ => Err(CoreCubeError::WrongParameter),
+            },
+            _ => Err(CoreCubeError::WrongParameter),
+        }
+    }
+
+    fn write_config(&mut self, bytes: &[u8]) -> Result<(), CoreCubeError> {
+        let config = &mut self.state.config;
+        let argument = |index: usize| {
+            bytes
+                .get(index)
+                .copied()
+                .ok_or(CoreCubeError::WrongParameter)
+        };
+        let respond = match bytes[0] {
+            0x01u8 => {
+                let response = ResponseBleProtocolVersionData {
+                    version: *VIRTUAL_CUBE_PROTOCOL_VERSION,
+                };
+                self.notify(CoreCubeUuid::Config, response.to_payload());
+                return Ok(());
+            }
+            0x05u8 => {
+                config.horizontal_detection_threshold = argument(2)?;
+                false
+            }
+            0x06u8 => {
+                config.collision_detection_threshold = argument(2)?;
+                false
+            }
+            0x17u8 => {
+                config.double_tap_detection_time_interval = argument(2)?;
+                false
+            }
+            0x18u8 => {
+                config.id_notification_interval = argument(2)?;
+                config.id_notification_condition = argument(3)?;
+                self.id_sensor.interval =
+                    Duration::from_millis(config.id_notification_interval as u64 * 10);
+                self.id_sensor.condition = config.id_notification_condition;
+                true
+            }
+            0x19u8 => {
+                config.id_missed_notification_sensitivity = argument(2)?;
+                self.id_sensor.missed_sensitivity =
+                    Duration::from_millis(config.id_missed_notification_sensitivity as u64 * 10);
+                true
+            }
+            0x1bu8 => {
+                config.magnetic_sensor_function = argument(2)?;
+                config.magnetic_sensor_interval = argument(3)?;
+                config.magnetic_sensor_condition = argument(4)?;
+                true
+            }
+            0x1cu8 => {
+                config.motor_speed_enabled = argument(2)? != 0;
+                true
+            }
+            0x1du8 => {
+                config.posture_angle_data_type = argument(2)?;
+                config.posture_angle_interval = argument(3)?;
+                config.posture_angle_condition = argument(4)?;
+                true
+            }
+            _ => return Err(CoreCubeError::WrongParameter),
+        };
+        if respond {
+            self.notify(
+                CoreCubeUuid::Config,
+                vec![bytes[0] | 0x80u8, 0x00u8, 0x00u8],
+            );
+        }
+        Ok(())
+    }
+
+    fn read(&self, uuid: Uuid) -> Result<Vec<u8>, CoreCubeError> {
+        if !self.state.connected {
+            return Err(CoreCubeError::NotConnected);
+        }
+        if uuid == CoreCubeUuid::IdInfo.uuid() {
+            Ok(self.state.id.to_payload())
+        } else if uuid == CoreCubeUuid::SensorInfo.uuid() {
+            Ok(self.state.motion.to_payload())
+        } else if uuid == CoreCubeUuid::ButtonInfo.uuid() {
+            Ok(ButtonInformation {
+                time: Instant::now(),
+                state: self.state.button,
+            }
+            .to_payload())
+        } else if uuid == CoreCubeUuid::BatteryInfo.uuid() {
+            Ok(BatteryInformation {
+                level: self.state.battery_level,
+            }
+            .to_payload())
+        } else if uuid == CoreCubeUuid::MotorCtrl.uuid() {
+            Ok(self.motor_speed_payload())
+        } else {
+            Err(CoreCubeError::WrongParameter)
+        }
+    }
+
+    fn advance(&mut self, dt: Duration) {
+        self.pending += dt;
+        while self.pending >= ID_SENSOR_PERIOD {
+            self.pending -= ID_SENSOR_PERIOD;
+            self.step(ID_SENSOR_PERIOD);
+        }
+        advance_indicator(&mut self.state.indicator, dt);
+    }
+
+    fn cancel_target(&mut self) {
+        for response in self.target.cancel() {
+            self.notify(CoreCubeUuid::MotorCtrl, response.to_payload());
+        }
+    }
+
+    /// pose which the firmware knows (None while Position ID is missed)
+    fn position_id_pose(&self) -> Option<Pose> {
+        match self.state.id {
+            IdInformation::PositionId(_) => Some(self.state.pose),
+            _ => None,
+        }
+    }
+
+    fn step(&mut self, dt: Duration) {
+        if let Some(remaining) = self.state.motor.remaining {
+            if remaining <= dt {
+                self.acceleration = None;
+                self.set_motor(0, 0, None);
+            } else {
+                self.state.motor.remaining = Some(remaining - dt);
+            }
+        }
+        if self.target.is_active() {
+            let output = self.target.step(self.position_id_pose(), dt);
+            if let Some((left, right)) = output.wheel_speeds {
+                self.set_motor(left.round() as i16, right.round() as i16, None);
+            }
+            for response in output.responses {
+                self.notify(CoreCubeUuid::MotorCtrl, response.to_payload());
+            }
+        }
+        if let Some(acceleration) = &mut self.acceleration {
+            let (left, right) = acceleration.wheel_speeds(dt);
+            let remaining = self.state.motor.remaining;
+            self.set_motor(left.round() as i16, right.round() as i16, remaining);
+        }
+        if !self.state.lifted {
+            let motor = self.state.motor;
+            self.state
+                .pose
+                .step(f64::from(motor.left), f64::from(motor.right), dt);
+        }
+
+        let reading = self.id_reading();
+        self.state.id = reading;
+        if let Some(id) = self.id_sensor.read(reading, dt) {
+            self.notify(CoreCubeUuid::IdInfo, id.to_payload());
+        }
+    }
+
+    /// ID under the current pose
+    fn id_reading(&mut self) -> IdInformation {
+        if self.state.lifted {
+            return self.missed_id();
+        }
+        let pose = self.state.pose;
+        if let Some((standard_id, origin)) = self.standard_id {
+            if (pose.x - origin.x).hypot(pose.y - origin.y) <= STANDARD_ID_RANGE {
+                return IdInformation::StandardId(StandardIdData {
+                    angle: pose.location().angle,
+                    ..standard_id
+                });
+            }
+            self.standard_id = None;
+        }
+        if self.state.mat.rect().includes(pose.point()) {
+            IdInformation::PositionId(PositionIdData {
+                center: pose.location(),
+                sensor: pose.location(),
+            })
+        } else {
+            self.missed_id()
+        }
+    }
+
+    fn missed_id(&self) -> IdInformation {
+        match self.state.id {
+            IdInformation::StandardId(_) | IdInformation::StandardIdMissed => {
+                IdInformation::StandardIdMissed
+            }
+            _ => IdInformation::PositionIdMissed,
+        }
+    }
+
+    /// read the ID immediately (e.g. the cube is placed or lifted)
+    fn update_id(&mut self) {
+        let previous = self.state.id;
+        let reading = self.id_reading();
+        self.state.id = reading;
+        self.id_sensor.reset(reading);
+        let missed = matches!(
+            reading,
+            IdInformation::PositionIdMissed | IdInformation::StandardIdMissed
+        );
+        // missed notification is sent only once
+        if !missed || previous != reading {
+            self.notify(CoreCubeUuid::IdInfo, reading.to_payload());
+        }
+    }
+}
+
+fn period(value: Option<&u8>) -> Option<Duration> {
+    match value {
+        Some(0) | None => None,
+        Some(period) => Some(Duration::from_millis(*period as u64 * 10)),
+    }
+}
+
+fn parse_indicator_params(
+    bytes: &[u8],
+    count: usize,
+) -> Result<Vec<(Duration, Color)>, CoreCubeError> {
+    if count == 0 || bytes.len() < count * 6 {
+        return Err(CoreCubeError::WrongParameter);
+    }
+    Ok(bytes[..count * 6]
+        .chunks(6)
+        .map(|param| {
+            (
+                Duration::from_millis(param[0] as u64 * 10),
+                Color::from(&param[3..6]),
+            )
+        })
+        .collect())
+}
+
+fn advance_indicator(indicator: &mut VirtualIndicatorState, dt: Duration) {
+    if indicator.sequence.is_empty() {
+        return;
+    }
+    indicator.elapsed += dt;
+    loop {
+        let (duration, _) = indicator.sequence[indicator.position];
+        if duration.is_zero() || indicator.elapsed < duration {
+            break;
+        }
+        indicator.elapsed -= duration;
+        indicator.position += 1;
+        if indicator.position == indicator.sequence.len() {
+            indicator.position = 0;
+            match indicator.repeat {
+                // repeat forever
+                0 => (),
+                1 => {
+                    *indicator = VirtualIndicatorState::default();
+                    return;
+                }
+                _ => indicator.repeat -= 1,
+            }
+        }
+    }
+    indicator.color = Some(indicator.sequence[indicator.position].1);
+}
+
+/// Virtual toio core cube
+///
+/// Clones share the same cube, so a test can keep one handle to drive the
+/// simulation while the application under test owns another.
+
+#[derive(Clone)]
+pub struct VirtualCube {
+    inner: Arc<Mutex<VirtualCubeInner>>,
+    request_id_allocator: RequestIdAllocator,
+}
+
+impl Default for VirtualCube {
+    fn default() -> Self {
+        Self::new(VirtualCubeState::default())
+    }
+}
+
+impl VirtualCube {
+    pub fn new(state: VirtualCubeState) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(VirtualCubeInner {
+                state,
+                notification_sender: None,
+                notification_enabled: Vec::new(),
+                acceleration: None,
+                target: TargetController::default(),
+                id_sensor: IdSensor::default(),
+                standard_id: None,
+                pending: Duration::ZERO,
+            })),
+            request_id_allocator: RequestIdAllocator::new(),
+        }
+    }
+
+    /// snapshot of the current state
+    pub fn state(&self) -> VirtualCubeState {
+        self.inner.lock().unwrap().state.clone()
+    }
+
+    /// progress the simulation (cube movement, ID notifications, timers)
+    pub fn advance(&self, dt: Duration) {
+        self.inner.lock().unwrap().advance(dt);
+    }
+
+    /// advance the simulation in real time every `period` until all handles of the cube are dropped
+    pub fn run(&self, period: Duration) -> impl Future<Output = ()> + Send + 'static {
+        let inner = Arc::downgrade(&self.inner);
+        async move {
+            let mut interval = tokio::time::interval(period);
+            let mut last = tokio::time::Instant::now();
+            loop {
+                interval.tick().await;
+                let Some(inner) = inner.upgrade() else {
+                    break;
+                };
+                let now = tokio::time::Instant::now();
+                inner.lock().unwrap().advance(now - last);
+                last = now;
+            }
+        }
+    }
+
+    /// put the cube on the mat at the specified location
+    pub fn place(&self, location: CubeLocation) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.state.pose = Pose::from(location);
+        inner.state.lifted = false;
+        inner.standard_id = None;
+        inner.update_id();
+    }
+
+    /// put the cube on a card or a sticker with standard ID
+    pub fn place_on_standard_id(&self, standard_id: StandardIdData) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.state.pose.angle = f64::from(standard_id.angle);
+        inner.state.lifted = false;
+        inner.standard_id = Some((standard_id, inner.state.pose));
+        inner.update_id();
+    }
+
+    /// pick the cube up
+    pub fn lift(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.state.lifted = true;
+        inner.update_id();
+    }
+
+    pub fn press_button(&self) {
+        self.set_button(ButtonState::Pressed);
+    }
+
+    pub fn release_button(&self) {
+        self.set_button(ButtonState::Released);
+    }
+
+    fn set_button(&self, button: ButtonState) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.state.button = button;
+        let payload = ButtonInformation {
+            time: Instant::now(),
+            state: button,
+        }
+        .to_payload();
+        inner.notify(CoreCubeUuid::ButtonInfo, payload);
+    }
+
+    pub fn set_battery_level(&self, level: usize) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.state.battery_level = level.min(100);
+        let payload = BatteryInformation {
+            level: inner.state.battery_level,
+        }
+        .to_payload();
+        inner.notify(CoreCubeUuid::BatteryInfo, payload);
+    }
+
+    pub fn set_motion(&self, motion: MotionDetectionInformation) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.state.motion = motion;
+        inner.notify(CoreCubeUuid::SensorInfo, motion.to_payload());
+    }
+
+    pub fn set_posture_angle(&self, euler: PostureAngleEulerInformation) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.state.posture_angle_euler = euler;
+        // posture angle is notified only after it is enabled by configuration
+        if inner.state.config.posture_angle_data_type == 0x01u8 {
+            inner.notify(CoreCubeUuid::SensorInfo, euler.to_payload());
+        }
+    }
+
+    pub fn set_magnetic(&self, magnetic: MagneticSensorInformation) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.state.magnetic = magnetic;
+        // magnetic sensor is notified only after it is enabled by configuration
+        if inner.state.config.magnetic_sensor_function != 0x00u8 {
+            inner.notify(CoreCubeUuid::SensorInfo, magnetic.to_payload());
+        }
+    }
+}
+
+#[async_trait]
+impl CubeInterface for VirtualCube {
+    async fn connect_with(&mut self, options: &ConnectOptions) -> Result<(), CoreCubeError> {
+        if !options
+            .notifications
+            .iter()
+            .all(|notified| NOTIFY_CHARACTERISTICS.contains(notified))
+        {
+            return Err(CoreCubeError::WrongParameter);
+        }
+        let mut inner = self.inner.lock().unwrap();
+        inner.state.connected = true;
+        let (sender, _) = broadcast::channel(NOTIFICATION_CAPACITY);
+        inner.notification_sender = Some(sender);
+        inner.notification_enabled = options.notifications.clone();
+        Ok(())
+    }
+
+    async fn disconnect(&mut self) -> Result<(), CoreCubeError> {
+        let mut inner = self.inner.lock().unwrap();
+        inner.state.connected = false;
+        // dropping the sender finishes all notification receivers
+        inner.notification_sender = None;
+        inner.notification_enabled.clear();
+        Ok(())
+    }
+
+    async fn read(&self, uuid: Uuid) -> Result<Vec<u8>, CoreCubeError> {
+        Ok(self.inner.lock().unwrap().read(uuid)?)
+    }
+
+    async fn write(&self, uuid: Uuid, bytes: &[u8]) -> Result<bool, CoreCubeError> {
+        self.inner.lock().unwrap().write(uuid, bytes)?;
+        Ok(true)
+    }
+
+    async fn write_with_response(&self, uuid: Uuid, bytes: &[u8]) -> Result<bool, CoreCubeError> {
+        self.inner.lock().unwrap().write(uuid, bytes)?;
+        Ok(true)
+    }
+
+    async fn subscribe(&self, characteristic: CoreCubeUuid) -> Result<(), CoreCubeError> {
+        if !NOTIFY_CHARACTERISTICS.contains(&characteristic) {
+            return Err(CoreCubeError::WrongParameter);
+        }
+        let mut inner = self.inner.lock().unwrap();
+        if inner.notification_sender.is_none() {
+            return Err(CoreCubeError::NotConnected);
+        }
+        if !inner.notification_enabled.contains(&characteristic) {
+            inner.notification_enabled.push(characteristic);
+        }
+        Ok(())
+    }
+
+    async fn unsubscribe(&self, characteristic: CoreCubeUuid) -> Result<(), CoreCubeError> {
+        self.inner
+            .lock()
+            .unwrap()
+            .notification_enabled
+            .retain(|notified| *notified != characteristic);
+        Ok(())
+    }
+
+    fn notification_enabled(&self) -> Vec<CoreCubeUuid> {
+        self.inner.lock().unwrap().notification_enabled.clone()
+    }
+
+    fn create_notification_receiver_with_manager(
+        &self,
+        nf_manager: Arc<NotificationManager<NotificationData>>,
+    ) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        let receiver = self
+            .inner
+            .lock()
+            .unwrap()
+            .notification_sender
+            .as_ref()
+            .map(|sender| sender.subscribe());
+        Box::pin(async move {
+            let Some(mut receiver) = receiver else {
+                warn!("virtual cube is not connected");
+                return;
+            };
+            loop {
+                match receiver.recv().await {
+                    Ok(data) => {
+                        let _ = nf_manager.dispatch(data).await;
+                    }
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!("virtual cube: {} notifications are dropped", skipped);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        })
+    }
+
+    fn request_id_allocator(&self) -> RequestIdAllocator {
+        self.request_id_allocator.clone()
+    }
+
+    async fn notification_stream(&self) -> Result<NotificationStream, CoreCubeError> {
+        let receiver = self
+            .inner
+            .lock()
+            .unwrap()
+            .notification_sender
+            .as_ref()
+            .map(|sender| sender.subscribe())
+            .ok_or(CoreCubeError::NotConnected)?;
+        Ok(Box::pin(futures::stream::unfold(
+            receiver,
+            |mut receiver| async move {
+                loop {
+                    match receiver.recv().await {
+                        Ok(data) => return Some((data, receiver)),
+                        Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                            warn!("virtual cube: {} notifications are dropped", skipped);
+                        }
+                        Err(broadcast::error::RecvError::Closed) => return None,
+                    }
+                }
+            },
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::characteristic::battery::BatteryInformation;
+    use crate::characteristic::configuration::{
+        ConfigurationInformation, EnableMotorSpeed, IdSensorNotificationCondition,
+        RequestBleProtocolVersion, ResponseEnableMotorSpeedData, SetHorizontalDetectionThreshold,
+        SetIdSensorNotification,
+    };
+    use crate::characteristic::indicator::{IndicatorParam, TurningOnAndOff};
+    use crate::characteristic::motor::{
+        Acceleration, AngleVelocity, MotorControl, MotorControlAcceleration, MotorControlTarget,
+        MotorControlWithSpecifiedDuration, MotorInformation, Period, RequestId, ResponseCode,
+        ResponseMotorControlTarget, Speed, TargetPosition,
+    };
+    use crate::characteristic::sensor::SensorInformation;
+    use crate::characteristic::sound::{MidiNote, Note, PlayMidiNote};
+    use crate::characteristic::CubeEvent;
+    use crate::interface::{CoreCubeNotificationControl, PausePolicy};
+    use crate::notification_manager::NotificationFilter;
+    use crate::payload::{FromPayload, ToPayload};
+    use crate::position::Point;
+    use futures::StreamExt;
+    use tokio::sync::mpsc;
+
+    fn _setup() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    async fn connected_cube() -> (VirtualCube, Arc<Mutex<Vec<NotificationData>>>) {
+        let mut cube = VirtualCube::default();
+        cube.connect().await.unwrap();
+        let received: Arc<Mutex<Vec<NotificationData>>> = Arc::new(Mutex::new(Vec::new()));
+        let received_by_handler = received.clone();
+        let receiver = cube.create_notification_receiver(Box::new(vec![Box::new(
+            move |data: NotificationData| received_by_handler.lock().unwrap().push(data),
+        )]));
+        tokio::spawn(receiver);
+        (cube, received)
+    }
+
+    // let the receiver dispatch the notifications
+    //
+    // The clock is paused, so this returns as soon as all tasks are idle.
+    async fn settle() {
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn virtual_cube_motor1() {
+        _setup();
+        let (cube, _) = connected_cube().await;
+
+        let motor = MotorControl::set_value(50, -20).unwrap();
+        cube.write(CoreCubeUuid::MotorCtrl.uuid(), &motor.to_payload())
+            .await
+            .unwrap();
+        assert_eq!(cube.state().motor.left, 50);
+        assert_eq!(cube.state().motor.right, -20);
+        assert_eq!(cube.state().motor.remaining, None);
+
+        let motor = MotorControlWithSpecifiedDuration::set_value(30, 30, 100).unwrap();
+        cube.write(CoreCubeUuid::MotorCtrl.uuid(), &motor.to_payload())
+            .await
+            .unwrap();
+        cube.advance(Duration::from_millis(50));
+        assert_eq!(cube.state().motor.left, 30);
+        cube.advance(Duration::from_millis(50));
+        assert_eq!(cube.state().motor, VirtualMotorState::default());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn virtual_cube_motor_speed1() {
+        _setup();
+        let (cube, received) = connected_cube().await;
+
+        cube.write(
+            CoreCubeUuid::Config.uuid(),
+            &EnableMotorSpeed::new(true).to_payload(),
+        )
+        .await
+        .unwrap();
+        let motor = MotorControl::set_value(40, 200).unwrap();
+        cube.write(CoreCubeUuid::MotorCtrl.uuid(), &motor.to_payload())
+            .await
+            .unwrap();
+        settle().await;
+
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 2);
+        assert_eq!(received[0].value, vec![0x9c, 0x00, 0x00]);
+        match MotorInformation::from_payload(&received[1].value) {
+            Some(MotorInformation::MotorSpeed(speed)) => {
+                assert_eq!(speed.left, 40);
+                assert_eq!(speed.right, MOTOR_SPEED_MAX);
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn virtual_cube_id1() {
+        _setup();
+        let (cube, received) = connected_cube().await;
+
+        let location = CubeLocation {
+            point: Point { x: 250, y: 300 },
+            angle: 90,
+        };
+        cube.place(location);
+        cube.lift();
+        cube.lift();
+        settle().await;
+
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 2);
+        assert_eq!(received[0].uuid, CoreCubeUuid::IdInfo.uuid());
+        match IdInformation::from_payload(&received[0].value) {
+            Some(IdInformation::PositionId(position_id)) => {
+                assert_eq!(position_id.center, location);
+            }
+            _ => unreachable!(),
+        }
+        assert_eq!(
+            IdInformation::from_payload(&received[1].value),
+            Some(IdInformation::PositionIdMissed)
+        );
+    }
+
+    fn last_id(received: &Arc<Mutex<Vec<NotificationData>>>) -> Option<IdInformation> {
+        received
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            .find(|data| data.uuid == CoreCubeUuid::IdInfo.uuid())
+            .and_then(|data| IdInformation::from_payload(&data.value))
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn virtual_cube_mat1() {
+        _setup();
+        let (cube, received) = connected_cube().await;
+
+        cube.write(
+            CoreCubeUuid::Config.uuid(),
+            &SetIdSensorNotification::new(10, IdSensorNotificationCondition::AtChange).to_payload(),
+        )
+        .await
+        .unwrap();
+        cube.place(CubeLocation {
+            point: ToioMat::ToioCollectionMatRing.rect().center(),
+            angle: 0,
+        });
+        let motor = MotorControl::set_value(50, 50).unwrap();
+        cube.write(CoreCubeUuid::MotorCtrl.uuid(), &motor.to_payload())
+            .await
+            .unwrap();
+        cube.advance(Duration::from_secs(1));
+        settle().await;
+
+        let pose = cube.state().pose;
+        assert_eq!(pose.point(), Point { x: 353, y: 250 });
+        match last_id(&received) {
+            Some(IdInformation::PositionId(position_id)) => {
+                assert_eq!(position_id.center.point, pose.point());
+                assert_eq!(position_id.center.angle, 0);
+            }
+            _ => unreachable!(),
+        }
+
+        // runs off the mat
+        cube.advance(Duration::from_secs(2));
+        settle().await;
+        assert_eq!(cube.state().id, IdInformation::PositionIdMissed);
+        assert_eq!(last_id(&received), Some(IdInformation::PositionIdMissed));
+        let count = received.lock().unwrap().len();
+        cube.advance(Duration::from_secs(1));
+        settle().await;
+        assert_eq!(received.lock().unwrap().len(), count);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn virtual_cube_mat2() {
+        _setup();
+        let (cube, _) = connected_cube().await;
+
+        cube.place(CubeLocation {
+            point: Point { x: 200, y: 200 },
+            angle: 270,
+        });
+        // turn half around clockwise
+        let motor = MotorControlAcceleration {
+            acceleration: Acceleration {
+                translational_speed: 0,
+                acceleration: 0,
+            },
+            angle_velocity: AngleVelocity {
+                rotation_velocity: 180,
+                ..AngleVelocity::default()
+            },
+            period: Period::from_millis(1000),
+            ..MotorControlAcceleration::default()
+        };
+        cube.write(CoreCubeUuid::MotorCtrl.uuid(), &motor.to_payload())
+            .await
+            .unwrap();
+        cube.advance(Duration::from_millis(500));
+        assert!(cube.state().motor.left > 0);
+        assert!(cube.state().motor.right < 0);
+        cube.advance(Duration::from_millis(500));
+        assert_eq!(cube.state().motor, VirtualMotorState::default());
+
+        let location = cube.state().pose.location();
+        assert_eq!(location.point, Point { x: 200, y: 200 });
+        // rotation velocity is approximated by integer motor speeds
+        assert!((85..=95).contains(&location.angle));
+
+        // wheels turn in the air but the cube does not move
+        cube.lift();
+        let motor = MotorControl::set_value(100, 100).unwrap();
+        cube.write(CoreCubeUuid::MotorCtrl.uuid(), &motor.to_payload())
+            .await
+            .unwrap();
+        cube.advance(Duration::from_secs(1));
+        assert_eq!(cube.state().pose.point(), Point { x: 200, y: 200 });
+    }
+
+    fn motor_responses(received: &Arc<Mutex<Vec<NotificationData>>>) -> Vec<MotorInformation> {
+        received
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|data| data.uuid == CoreCubeUuid::MotorCtrl.uuid())
+            .filter_map(|data| MotorInformation::from_payload(&data.value))
+            .collect()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn virtual_cube_target1() {
+        _setup();
+        let (cube, received) = connected_cube().await;
+
+        cube.write(
+            CoreCubeUuid::Config.uuid(),
+            &SetIdSensorNotification::new(10, IdSensorNotificationCondition::AtChange).to_payload(),
+        )
+        .await
+        .unwrap();
+        cube.place(CubeLocation {
+            point: Point { x: 100, y: 100 },
+            angle: 0,
+        });
+        let target = MotorControlTarget {
+            speed: Speed {
+                max: 80,
+                ..Speed::default()
+            },
+            target: TargetPosition {
+                cube_location: CubeLocation {
+                    point: Point { x: 200, y: 150 },
+                    angle: 90,
+                },
+                ..TargetPosition::default()
+            },
+            ..MotorControlTarget::new(RequestId::received(7))
+        };
+        cube.write(CoreCubeUuid::MotorCtrl.uuid(), &target.to_payload())
+            .await
+            .unwrap();
+        for _ in 0..5 {
+            cube.advance(Duration::from_secs(1));
+            settle().await;
+        }
+
+        assert_eq!(
+            motor_responses(&received),
+            vec![MotorInformation::MotorControlTarget(
+                ResponseMotorControlTarget {
+                    request_id: RequestId::received(7),
+                    response_code: ResponseCode::Success,
+                }
+            )]
+        );
+        let location = cube.state().pose.location();
+        assert!(location.point.distance(Point { x: 200, y: 150 }) <= 5);
+        assert!((85..=95).contains(&location.angle));
+        assert_eq!(cube.state().motor, VirtualMotorState::default());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn virtual_cube_target2() {
+        _setup();
+        let (cube, received) = connected_cube().await;
+
+        let target = MotorControlTarget {
+            speed: Speed {
+                max: 50,
+                ..Speed::default()
+            },
+            target: TargetPosition {
+                cube_location: CubeLocation {
+                    point: Point { x: 400, y: 400 },
+                    angle: 0,
+                },
+                ..TargetPosition::default()
+            },
+            ..MotorControlTarget::new(RequestId::received(1))
+        };
+
+        // not on the mat
+        cube.write(CoreCubeUuid::MotorCtrl.uuid(), &target.to_payload())
+            .await
+            .unwrap();
+
+        // overwritten by motor control
+        cube.place(CubeLocation {
+            point: Point { x: 100, y: 100 },
+            angle: 0,
+        });
+        cube.write(CoreCubeUuid::MotorCtrl.uuid(), &target.to_payload())
+            .await
+            .unwrap();
+        cube.advance(Duration::from_millis(100));
+        assert!(cube.state().motor.left > 0);
+        let motor = MotorControl::set_value(0, 0).unwrap();
+        cube.write(CoreCubeUuid::MotorCtrl.uuid(), &motor.to_payload())
+            .await
+            .unwrap();
+
+        // lifted while moving
+        cube.write(CoreCubeUuid::MotorCtrl.uuid(), &target.to_payload())
+            .await
+            .unwrap();
+        cube.advance(Duration::from_millis(100));
+        cube.lift();
+        cube.advance(Duration::from_millis(10));
+        settle().await;
+
+        let response_codes: Vec<ResponseCode> = motor_responses(&received)
+            .iter()
+            .map(|response| match response {
+                MotorInformation::MotorControlTarget(response) => response.response_code,
+                _ => unreachable!(),
+            })
+            .collect();
+        assert_eq!(
+            response_codes,
+            vec![
+                ResponseCode::ErrorIdMissed,
+                ResponseCode::SuccessWithOverwrite,
+                ResponseCode::ErrorIdMissed
+            ]
+        );
+        assert_eq!(cube.state().motor, VirtualMotorState::default());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn virtual_cube_button_battery1() {
+        _setup();
+        let (cube, received) = connected_cube().await;
+
+        cube.press_button();
+        cube.set_battery_level(80);
+        settle().await;
+
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 2);
+        assert_eq!(received[0].value, vec![0x01, 0x80]);
+        assert_eq!(
+            BatteryInformation::from_payload(&received[1].value),
+            Some(BatteryInformation { level: 80 })
+        );
+        let battery = cube
+            .inner
+            .lock()
+            .unwrap()
+            .read(CoreCubeUuid::BatteryInfo.uuid())
+            .unwrap();
+        assert_eq!(battery, vec![80]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn virtual_cube_light_sound1() {
+        _setup();
+        let (cube, _) = connected_cube().await;
+
+        let light = TurningOnAndOff {
+            param: IndicatorParam {
+                duration: Period::from_millis(200),
+                color: Color { r: 255, g: 0, b: 0 },
+            },
+            ..TurningOnAndOff::default()
+        };
+        cube.write(CoreCubeUuid::LightCtrl.uuid(), &light.to_payload())
+            .await
+            .unwrap();
+        assert_eq!(
+            cube.state().indicator.color,
+            Some(Color { r: 255, g: 0, b: 0 })
+        );
+        cube.advance(Duration::from_millis(200));
+        assert_eq!(cube.state().indicator.color, None);
+
+        let sound = PlayMidiNote {
+            midi_notes: vec![MidiNote {
+                volume: 0xff,
+                duration: Period::from_millis(300),
+                note: Note::C5,
+            }],
+            ..PlayMidiNote::default()
+        };
+        cube.write(CoreCubeUuid::SoundCtrl.uuid(), &sound.to_payload())
+            .await
+            .unwrap();
+        assert_eq!(
+            cube.state().sound,
+            VirtualSoundState::MidiNotes {
+                repeat: 1,
+                notes: vec![Note::C5.into()]
+            }
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn virtual_cube_config1() {
+        _setup();
+        let (cube, received) = connected_cube().await;
+
+        cube.write(
+            CoreCubeUuid::Config.uuid(),
+            &RequestBleProtocolVersion::new().to_payload(),
+        )
+        .await
+        .unwrap();
+        settle().await;
+
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 1);
+        assert_eq!(received[0].value[0], 0x81);
+        assert_eq!(&received[0].value[2..], VIRTUAL_CUBE_PROTOCOL_VERSION);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn virtual_cube_event_stream1() {
+        _setup();
+        let mut cube = VirtualCube::default();
+        assert!(cube.event_stream().await.is_err());
+        cube.connect().await.unwrap();
+
+        let mut events = cube.event_stream().await.unwrap();
+        cube.press_button();
+        cube.set_battery_level(30);
+        cube.place(CubeLocation {
+            point: Point { x: 100, y: 120 },
+            angle: 45,
+        });
+        match events.next().await {
+            Some(CubeEvent::Button(button)) => assert_eq!(button.state, ButtonState::Pressed),
+            _ => unreachable!(),
+        }
+        assert_eq!(
+            events.next().await,
+            Some(CubeEvent::Battery(BatteryInformation { level: 30 }))
+        );
+        match events.next().await {
+            Some(CubeEvent::Id(IdInformation::PositionId(position_id))) => {
+                assert_eq!(position_id.center.point, Point { x: 100, y: 120 });
+            }
+            _ => unreachable!(),
+        }
+        cube.disconnect().await.unwrap();
+        assert_eq!(events.next().await, None);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn virtual_cube_subscribe1() {
+        _setup();
+        let mut cube = VirtualCube::default();
+        assert!(matches!(
+            cube.subscribe(CoreCubeUuid::ButtonInfo).await,
+            Err(CoreCubeError::NotConnected)
+        ));
+        assert!(matches!(
+            cube.connect_with(&ConnectOptions::new(&[CoreCubeUuid::LightCtrl]))
+                .await,
+            Err(CoreCubeError::WrongParameter)
+        ));
+        cube.connect_with(&ConnectOptions::new(&[CoreCubeUuid::ButtonInfo]))
+            .await
+            .unwrap();
+        assert_eq!(cube.notification_enabled(), vec![CoreCubeUuid::ButtonInfo]);
+        let mut notifications = cube.notification_stream().await.unwrap();
+
+        cube.set_battery_level(80);
+        cube.press_button();
+        assert_eq!(
+            notifications.next().await.unwrap().uuid,
+            CoreCubeUuid::ButtonInfo.uuid()
+        );
+
+        cube.subscribe(CoreCubeUuid::BatteryInfo).await.unwrap();
+        cube.unsubscribe(CoreCubeUuid::ButtonInfo).await.unwrap();
+        assert_eq!(cube.notification_enabled(), vec![CoreCubeUuid::BatteryInfo]);
+        cube.release_button();
+        cube.set_battery_level(70);
+        assert_eq!(notifications.next().await.unwrap().value, vec![70]);
+
+        cube.disconnect().await.unwrap();
+        assert!(cube.notification_enabled().is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn virtual_cube_shared_manager1() {
+        _setup();
+        let mut cube = VirtualCube::default();
+        cube.connect().await.unwrap();
+        let nf_manager = Arc::new(NotificationManager::<NotificationData>::new());
+        let receiver_task =
+            tokio::spawn(cube.create_notification_receiver_with_manager(nf_manager.clone()));
+
+        cube.set_battery_level(90);
+        settle().await;
+
+        // register a handler after the receiver is started
+        let received: Arc<Mutex<Vec<NotificationData>>> = Arc::new(Mutex::new(Vec::new()));
+        let received_by_handler = received.clone();
+        let handler = nf_manager
+            .register(Box::new(move |data: NotificationData| {
+                received_by_handler.lock().unwrap().push(data)
+            }))
+            .unwrap();
+        cube.set_battery_level(80);
+        settle().await;
+        nf_manager.unregister(handler).unwrap();
+        cube.set_battery_level(70);
+        settle().await;
+
+        let received = received.lock().unwrap().clone();
+        assert_eq!(received.len(), 1);
+        assert_eq!(received[0].value, vec![80]);
+
+        cube.disconnect().await.unwrap();
+        receiver_task.await.unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn virtual_cube_control1() {
+        _setup();
+        let mut cube = VirtualCube::default();
+        cube.connect().await.unwrap();
+        let received: Arc<Mutex<Vec<u8>>> = Arc::new(Mutex::new(Vec::new()));
+        let received_by_handler = received.clone();
+        let (control, control_receiver) = mpsc::channel(4);
+        let receiver_task = tokio::spawn(cube.create_controlled_notification_receiver(
+            vec![(
+                NotificationFilter::Characteristic(CoreCubeUuid::BatteryInfo),
+                Box::new(move |data: NotificationData| {
+                    received_by_handler.lock().unwrap().push(data.value[0])
+                }),
+            )],
+            control_receiver,
+            PausePolicy::Buffer(1),
+        ));
+        settle().await;
+
+        cube.set_battery_level(90);
+        settle().await;
+        control
+            .send(CoreCubeNotificationControl::Pause)
+            .await
+            .unwrap();
+        settle().await;
+        cube.set_battery_level(80);
+        cube.set_battery_level(70);
+        settle().await;
+        assert_eq!(*received.lock().unwrap(), vec![90]);
+
+        // only the latest one is kept while paused
+        control
+            .send(CoreCubeNotificationControl::Run)
+            .await
+            .unwrap();
+        settle().await;
+        assert_eq!(*received.lock().unwrap(), vec![90, 70]);
+
+        control
+            .send(CoreCubeNotificationControl::Quit)
+            .await
+            .unwrap();
+        receiver_task.await.unwrap();
+
+        // disconnection stops the receiver without Quit
+        let (_control, control_receiver) = mpsc::channel(1);
+        let receiver_task = tokio::spawn(cube.create_notification_receiver_with_control(
+            Arc::new(NotificationManager::new()),
+            control_receiver,
+            PausePolicy::Drop,
+        ));
+        cube.disconnect().await.unwrap();
+        tokio::time::timeout(Duration::from_secs(1), receiver_task)
+            .await
+            .unwrap()
+            .unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn virtual_cube_disconnected1() {
+        _setup();
+        let mut cube = VirtualCube::default();
+        let motor = MotorControl::set_value(10, 10).unwrap();
+        assert!(cube
+            .write(CoreCubeUuid::MotorCtrl.uuid(), &motor.to_payload())
+            .await
+            .is_err());
+
+        cube.connect().await.unwrap();
+        let receiver = cube.create_notification_receiver(Box::new(vec![]));
+        let receiver_task = tokio::spawn(receiver);
+        cube.disconnect().await.unwrap();
+        receiver_task.await.unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn virtual_cube_read1() {
+        _setup();
+        let (cube, _) = connected_cube().await;
+
+        cube.set_battery_level(60);
+        cube.press_button();
+        cube.place(CubeLocation {
+            point: Point { x: 150, y: 250 },
+            angle: 0,
+        });
+        assert_eq!(
+            cube.read_battery().await.unwrap(),
+            BatteryInformation { level: 60 }
+        );
+        assert_eq!(
+            cube.read_button().await.unwrap().state,
+            ButtonState::Pressed
+        );
+        match cube.read_id().await.unwrap() {
+            IdInformation::PositionId(position) => {
+                assert_eq!(position.center.point, Point { x: 150, y: 250 })
+            }
+            id => unreachable!("{:?}", id),
+        }
+        assert_eq!(
+            cube.read_sensor().await.unwrap(),
+            SensorInformation::MotionDetection(cube.state().motion)
+        );
+
+        let version = cube.read_ble_protocol_version().await.unwrap();
+        assert_eq!(&version.version, VIRTUAL_CUBE_PROTOCOL_VERSION);
+        assert_eq!(
+            cube.request_configuration(&EnableMotorSpeed::new(true).to_payload())
+                .await
+                .unwrap()
+                .await
+                .unwrap(),
+            ConfigurationInformation::MotorSpeed(ResponseEnableMotorSpeedData { result: true })
+        );
+        assert!(matches!(
+            cube.request_configuration(&[]).await,
+            Err(CoreCubeError::WrongParameter)
+        ));
+        // no response is sent for the threshold settings
+        assert!(matches!(
+            cube.request_configuration(&SetHorizontalDetectionThreshold::new(45).to_payload())
+                .await,
+            Err(CoreCubeError::WrongParameter)
+        ));
+        cube.write_configuration(&SetHorizontalDetectionThreshold::new(45).to_payload())
+            .await
+            .unwrap();
+        assert_eq!(cube.state().config.horizontal_detection_threshold, 45);
+        assert!(matches!(
+            cube.write_configuration(&EnableMotorSpeed::new(true).to_payload())
+                .await,
+            Err(CoreCubeError::WrongParameter)
+        ));
+    }
+}
@@ -2,11 +2,15 @@ mod cube_error;
 
 pub mod api;
 pub mod characteristic;
+pub mod cube;
+pub mod describe;
 pub mod integer_converter;
 pub mod interface;
 pub mod notification_manager;
 pub mod payload;
 pub mod position;
 pub mod scanner;
+pub mod simulator;
 
+pub use cube::Cube;
 pub use cube_error::CoreCubeError;
@@ -1,14 +1,101 @@
-use log::{debug, error};
+use crate::characteristic::{CoreCubeUuid, CubeEvent, CubeEventKind, NotificationData};
+use crate::CoreCubeError;
+use futures::future::BoxFuture;
+use log::{debug, warn};
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 use thiserror::Error;
+use tokio::sync::{broadcast, mpsc};
 use uuid::Uuid;
 
 pub type HandlerFunction<T> = Box<dyn Fn(T) + Send + Sync + 'static>;
 
+pub type AsyncHandlerFunction<T> = Box<dyn Fn(T) -> BoxFuture<'static, ()> + Send + Sync + 'static>;
+
+/// Notifications which are passed to the handler
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum NotificationFilter {
+    All,
+    /// notifications from the characteristic
+    Characteristic(CoreCubeUuid),
+    /// notifications which are decoded to the kind of event
+    Event(CubeEventKind),
+}
+
+/// Notification which can be dispatched with NotificationFilter
+pub trait Routable {
+    fn characteristic(&self) -> Option<CoreCubeUuid>;
+
+    fn event_kind(&self) -> CubeEventKind;
+}
+
+impl Routable for NotificationData {
+    fn characteristic(&self) -> Option<CoreCubeUuid> {
+        CoreCubeUuid::from_uuid(self.uuid)
+    }
+
+    fn event_kind(&self) -> CubeEventKind {
+        CubeEvent::decode(self.uuid, &self.value).kind()
+    }
+}
+
+/// What to do when the channel of the handler is full
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DeliveryPolicy {
+    /// drop the oldest notification in the channel (the dispatcher never waits)
+    ///
+    /// The capacity is rounded up to a power of two (e.g. 3 keeps 4 notifications)
+    /// because the channel is a tokio broadcast channel.
+    DropOldest,
+    /// wait until the handler takes a notification from the channel
+    ///
+    /// Only `dispatch()` waits. `invoke_all_handlers()` is not async, so it drops
+    /// the notification when the channel is full.
+    Block,
+}
+
+/// Registered handler
+///
+/// Handlers are cloned out of the registry and invoked after the registry is unlocked,
+/// so a handler can register or unregister handlers.
+#[derive(Clone)]
+pub enum Handler<T> {
+    /// called directly by the dispatcher
+    Function(Arc<dyn Fn(T) + Send + Sync + 'static>),
+    /// bounded channel (see `DeliveryPolicy::DropOldest`)
+    DropOldest(broadcast::Sender<T>),
+    /// bounded channel (see `DeliveryPolicy::Block`)
+    Block(mpsc::Sender<T>),
+}
+
+/// Receiving end of the handler registered by `register_channel()`
+pub enum NotificationReceiver<T> {
+    DropOldest(broadcast::Receiver<T>),
+    Block(mpsc::Receiver<T>),
+}
+
+impl<T: Clone> NotificationReceiver<T> {
+    /// next notification (`None` after the handler is unregistered)
+    pub async fn recv(&mut self) -> Option<T> {
+        match self {
+            NotificationReceiver::DropOldest(receiver) => loop {
+                match receiver.recv().await {
+                    Ok(data) => return Some(data),
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!("{} notifications are dropped", skipped);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            },
+            NotificationReceiver::Block(receiver) => receiver.recv().await,
+        }
+    }
+}
+
 pub struct NotificationManager<T> {
     order: Arc<Mutex<Vec<uuid::Uuid>>>,
-    pub handlers: Arc<Mutex<HashMap<uuid::Uuid, HandlerFunction<T>>>>,
+    pub handlers: Arc<Mutex<HashMap<uuid::Uuid, Handler<T>>>>,
+    filters: Arc<Mutex<HashMap<uuid::Uuid, NotificationFilter>>>,
 }
 
 #[derive(Error, Debug, PartialEq, Eq)]
@@ -17,6 +104,8 @@ pub enum NotificationManagerError {
     HandlerNotFound(uuid::Uuid),
     #[error("handler name '{0}' is already used (same handler?)")]
     HandlerNameIsUsed(uuid::Uuid),
+    #[error("capacity of the channel must be larger than 0")]
+    ZeroCapacity,
     #[error("internal error of handler.rs")]
     FoundBug,
 }
@@ -26,6 +115,7 @@ impl<T> Default for NotificationManager<T> {
         Self {
             order: Arc::new(Mutex::new(Vec::new())),
             handlers: Arc::new(Mutex::new(HashMap::new())),
+            filters: Arc::new(Mutex::new(HashMap::new())),
         }
     }
 }
@@ -39,10 +129,24 @@ where
     }
 
     /// register notification handler
-    pub fn register(
+    pub fn register(&self, func: HandlerFunction<T>) -> Result<uuid::Uuid, CoreCubeError> {
+        self.register_with_filter(func, NotificationFilter::All)
+    }
+
+    /// register notification handler which receives only the notifications matching the filter
+    pub fn register_with_filter(
         &self,
         func: HandlerFunction<T>,
-    ) -> Result<uuid::Uuid, Box<dyn std::error::Error + Send + Sync + 'static>> {
+        filter: NotificationFilter,
+    ) -> Result<uuid::Uuid, CoreCubeError> {
+        self.register_handler(Handler::Function(Arc::from(func)), filter)
+    }
+
+    fn register_handler(
+        &self,
+        handler: Handler<T>,
+        filter: NotificationFilter,
+    ) -> Result<uuid::Uuid, CoreCubeError> {
         let id = Uuid::new_v4();
         debug!("uuid: {}", id);
         let order_binding = self.order.clone();
@@ -51,18 +155,72 @@ where
             let handlers_binding = self.handlers.clone();
             let mut handlers = handlers_binding.lock().unwrap();
             order.push(id);
-            handlers.insert(id, func);
+            handlers.insert(id, handler);
+            self.filters.lock().unwrap().insert(id, filter);
             Ok(id)
         } else {
             Err(NotificationManagerError::HandlerNameIsUsed(id).into())
         }
     }
 
-    /// unregister notification handler
-    pub fn unregister(
+    /// register a bounded channel which receives the notifications matching the filter
+    ///
+    /// The channel is closed when the handler is unregistered.
+    pub fn register_channel(
+        &self,
+        filter: NotificationFilter,
+        capacity: usize,
+        policy: DeliveryPolicy,
+    ) -> Result<(uuid::Uuid, NotificationReceiver<T>), CoreCubeError> {
+        if capacity == 0 {
+            return Err(NotificationManagerError::ZeroCapacity.into());
+        }
+        let (handler, receiver) = match policy {
+            DeliveryPolicy::DropOldest => {
+                let (sender, receiver) = broadcast::channel(capacity);
+                (
+                    Handler::DropOldest(sender),
+                    NotificationReceiver::DropOldest(receiver),
+                )
+            }
+            DeliveryPolicy::Block => {
+                let (sender, receiver) = mpsc::channel(capacity);
+                (
+                    Handler::Block(sender),
+                    NotificationReceiver::Block(receiver),
+                )
+            }
+        };
+        Ok((self.register_handler(handler, filter)?, receiver))
+    }
+
+    /// register async notification handler
+    ///
+    /// The handler runs in its own task and receives the notifications through
+    /// a bounded channel, so a slow handler does not stall the dispatcher
+    /// (except with `DeliveryPolicy::Block`).
+    pub fn register_async(
         &self,
-        id: uuid::Uuid,
-    ) -> Result<bool, Box<dyn std::error::Error + Send + Sync + 'static>> {
+        func: AsyncHandlerFunction<T>,
+        filter: NotificationFilter,
+        capacity: usize,
+        policy: DeliveryPolicy,
+    ) -> Result<uuid::Uuid, CoreCubeError>
+    where
+        T: 'static,
+    {
+        let (id, mut receiver) = self.register_channel(filter, capacity, policy)?;
+        tokio::spawn(async move {
+            while let Some(data) = receiver.recv().await {
+                func(data).await;
+            }
+            debug!("async handler {} is finished", id);
+        });
+        Ok(id)
+    }
+
+    /// unregister notification handler
+    pub fn unregister(&self, id: uuid::Uuid) -> Result<bool, CoreCubeError> {
         let order_binding = self.order.clone();
         let mut order = order_binding.lock().unwrap();
         for (index, registered_id) in order.iter().enumerate() {
@@ -70,6 +228,7 @@ where
                 let handlers_binding = self.handlers.clone();
                 let mut handlers = handlers_binding.lock().unwrap();
                 handlers.remove(registered_id);
+                self.filters.lock().unwrap().remove(registered_id);
                 order.remove(index);
                 return Ok(true);
             }
@@ -77,25 +236,81 @@ where
         Err(NotificationManagerError::HandlerNotFound(id).into())
     }
 
-    /// invoke all handlers
-    pub fn invoke_all_handlers(
-        &self,
-        data: T,
-    ) -> Result<bool, Box<dyn std::error::Error + Send + Sync + 'static>> {
+    /// handlers in the order of registration (and their filters)
+    fn snapshot(&self) -> Result<Vec<(uuid::Uuid, NotificationFilter, Handler<T>)>, CoreCubeError> {
         let order_binding = self.order.clone();
         let order = order_binding.lock().unwrap();
         let handlers_binding = self.handlers.clone();
         let handlers = handlers_binding.lock().unwrap();
-        for id in order.iter() {
+        let filters = self.filters.lock().unwrap();
+        order
+            .iter()
+            .map(|id| match (filters.get(id), handlers.get(id)) {
+                (Some(filter), Some(handler)) => Ok((*id, *filter, handler.clone())),
+                _ => Err(NotificationManagerError::FoundBug.into()),
+            })
+            .collect()
+    }
+
+    /// invoke all handlers
+    ///
+    /// This does not wait: a notification to the full channel of `DeliveryPolicy::Block` is dropped.
+    pub fn invoke_all_handlers(&self, data: T) -> Result<bool, CoreCubeError> {
+        for (id, _, handler) in self.snapshot()? {
             debug!("invoke handler {}", id);
-            if let Some(handler) = handlers.get(id) {
-                handler(data.clone());
-            } else {
-                return Err(NotificationManagerError::FoundBug.into());
+            match handler {
+                Handler::Function(func) => func(data.clone()),
+                Handler::DropOldest(sender) => {
+                    // no receiver is not an error
+                    let _ = sender.send(data.clone());
+                }
+                Handler::Block(sender) => {
+                    if let Err(mpsc::error::TrySendError::Full(_)) = sender.try_send(data.clone()) {
+                        warn!("channel of handler {} is full", id);
+                    }
+                }
             }
         }
         Ok(true)
     }
+
+    /// invoke the handlers whose filter matches the notification
+    ///
+    /// The notification is decoded at most once, only if a handler filters by the event.
+    /// Returns the number of handlers which the notification is delivered to.
+    pub async fn dispatch(&self, data: T) -> Result<usize, CoreCubeError>
+    where
+        T: Routable,
+    {
+        let characteristic = data.characteristic();
+        let mut event_kind: Option<CubeEventKind> = None;
+        let mut delivered = 0;
+        for (id, filter, handler) in self.snapshot()? {
+            let matched = match filter {
+                NotificationFilter::All => true,
+                NotificationFilter::Characteristic(uuid) => characteristic == Some(uuid),
+                NotificationFilter::Event(kind) => {
+                    *event_kind.get_or_insert_with(|| data.event_kind()) == kind
+                }
+            };
+            if !matched {
+                continue;
+            }
+            debug!("invoke handler {}", id);
+            let result = match handler {
+                Handler::Function(func) => {
+                    func(data.clone());
+                    true
+                }
+                Handler::DropOldest(sender) => sender.send(data.clone()).is_ok(),
+                Handler::Block(sender) => sender.send(data.clone()).await.is_ok(),
+            };
+            if result {
+                delivered += 1;
+            }
+        }
+        Ok(delivered)
+    }
 }
 
 #[cfg(test)]
@@ -218,4 +433,136 @@ mod tests {
             assert_eq!(handlers.len(), 0);
         }
     }
+
+    #[tokio::test]
+    async fn notification_manager_dispatch1() {
+        _setup();
+        let notification_manager: NotificationManager<NotificationData> =
+            NotificationManager::new();
+        let received: Arc<Mutex<Vec<&str>>> = Arc::new(Mutex::new(Vec::new()));
+
+        for (name, filter) in [
+            ("all", NotificationFilter::All),
+            (
+                "button",
+                NotificationFilter::Characteristic(CoreCubeUuid::ButtonInfo),
+            ),
+            ("battery", NotificationFilter::Event(CubeEventKind::Battery)),
+            ("unknown", NotificationFilter::Event(CubeEventKind::Unknown)),
+        ] {
+            let received = received.clone();
+            notification_manager
+                .register_with_filter(
+                    Box::new(move |_| received.lock().unwrap().push(name)),
+                    filter,
+                )
+                .unwrap();
+        }
+
+        let button = NotificationData {
+            uuid: CoreCubeUuid::ButtonInfo.uuid(),
+            value: vec![0x01, 0x80],
+        };
+        assert_eq!(notification_manager.dispatch(button).await.unwrap(), 2);
+        let battery = NotificationData {
+            uuid: CoreCubeUuid::BatteryInfo.uuid(),
+            value: vec![0x50],
+        };
+        assert_eq!(notification_manager.dispatch(battery).await.unwrap(), 2);
+        let broken = NotificationData {
+            uuid: CoreCubeUuid::BatteryInfo.uuid(),
+            value: vec![],
+        };
+        assert_eq!(notification_manager.dispatch(broken).await.unwrap(), 2);
+        assert_eq!(
+            *received.lock().unwrap(),
+            vec!["all", "button", "all", "battery", "all", "unknown"]
+        );
+    }
+
+    fn battery(level: u8) -> NotificationData {
+        NotificationData {
+            uuid: CoreCubeUuid::BatteryInfo.uuid(),
+            value: vec![level],
+        }
+    }
+
+    #[tokio::test]
+    async fn notification_manager_channel1() {
+        _setup();
+        let notification_manager: NotificationManager<NotificationData> =
+            NotificationManager::new();
+        let (_, mut drop_oldest) = notification_manager
+            .register_channel(NotificationFilter::All, 2, DeliveryPolicy::DropOldest)
+            .unwrap();
+        let (block_id, mut block) = notification_manager
+            .register_channel(NotificationFilter::All, 2, DeliveryPolicy::Block)
+            .unwrap();
+
+        for level in 1..=2 {
+            notification_manager.dispatch(battery(level)).await.unwrap();
+        }
+        // the third notification waits until the handler takes one from the channel
+        let dispatcher = async {
+            notification_manager.dispatch(battery(3)).await.unwrap();
+            notification_manager.dispatch(battery(4)).await.unwrap();
+        };
+        let receiver = async {
+            let mut levels = Vec::new();
+            for _ in 0..4 {
+                levels.push(block.recv().await.unwrap().value[0]);
+            }
+            levels
+        };
+        let (_, levels) = tokio::join!(dispatcher, receiver);
+        assert_eq!(levels, vec![1, 2, 3, 4]);
+
+        // the oldest ones are dropped
+        assert_eq!(drop_oldest.recv().await.unwrap().value, vec![3]);
+        assert_eq!(drop_oldest.recv().await.unwrap().value, vec![4]);
+
+        notification_manager.unregister(block_id).unwrap();
+        assert!(block.recv().await.is_none());
+        assert!(notification_manager
+            .register_channel(NotificationFilter::All, 0, DeliveryPolicy::Block)
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn notification_manager_async1() {
+        _setup();
+        let notification_manager: Arc<NotificationManager<NotificationData>> =
+            Arc::new(NotificationManager::new());
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        notification_manager
+            .register_async(
+                Box::new(move |data: NotificationData| {
+                    let sender = sender.clone();
+                    Box::pin(async move {
+                        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+                        let _ = sender.send(data.value[0]);
+                    })
+                }),
+                NotificationFilter::Characteristic(CoreCubeUuid::BatteryInfo),
+                8,
+                DeliveryPolicy::DropOldest,
+            )
+            .unwrap();
+
+        // a handler can register another handler while it is invoked
+        let manager = notification_manager.clone();
+        notification_manager
+            .register(Box::new(move |_| {
+                manager.register(Box::new(|_| {})).unwrap();
+            }))
+            .unwrap();
+
+        for level in 1..=3 {
+            notification_manager.dispatch(battery(level)).await.unwrap();
+        }
+        for level in 1..=3 {
+            assert_eq!(receiver.recv().await, Some(level));
+        }
+        assert_eq!(notification_manager.handlers.lock().unwrap().len(), 5);
+    }
 }
@@ -1,5 +1,7 @@
 //! convert a struct of cube API to ble payload.
 
+use thiserror::Error;
+
 pub trait ToPayload<T> {
     fn to_payload(self) -> T;
 }
@@ -9,3 +11,374 @@ pub trait FromPayload<T> {
     where
         Self: Sized;
 }
+
+/// convert ble payload to a struct of cube API with the reason of the failure
+pub trait TryFromPayload<T>: Sized {
+    fn try_from_payload(payload: T) -> Result<Self, DecodeError>;
+}
+
+impl<T, P> FromPayload<P> for T
+where
+    T: TryFromPayload<P>,
+{
+    fn from_payload(payload: P) -> Option<Self> {
+        T::try_from_payload(payload).ok()
+    }
+}
+
+/// Reason why a payload could not be decoded
+///
+/// `opcode` is None when the payload has no opcode (e.g. battery information)
+/// or a part of a message is decoded alone.
+#[derive(Error, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    #[error("unknown opcode {0:#04x}")]
+    UnknownOpcode(u8),
+    #[error("unexpected opcode {actual:#04x} (expected {expected:#04x})")]
+    UnexpectedOpcode { expected: u8, actual: u8 },
+    #[error("{} is too short: expected {expected} bytes, got {actual}", payload_name(.opcode))]
+    TooShort {
+        opcode: Option<u8>,
+        expected: usize,
+        actual: usize,
+    },
+    #[error("{} has invalid {field}: {value:#04x}", payload_name(.opcode))]
+    InvalidField {
+        opcode: Option<u8>,
+        field: &'static str,
+        value: u8,
+    },
+}
+
+fn payload_name(opcode: &Option<u8>) -> String {
+    match opcode {
+        Some(opcode) => format!("payload of opcode {:#04x}", opcode),
+        None => "payload".to_string(),
+    }
+}
+
+impl DecodeError {
+    /// opcode of the message which could not be decoded
+    pub fn opcode(&self) -> Option<u8> {
+        match *self {
+            DecodeError::UnknownOpcode(opcode) => Some(opcode),
+            DecodeError::UnexpectedOpcode { actual, .. } => Some(actual),
+            DecodeError::TooShort { opcode, .. } => opcode,
+            DecodeError::InvalidField { opcode, .. } => opcode,
+        }
+    }
+
+    /// attach the opcode of the message to an error of a part of the message
+    fn within(self, message_opcode: u8) -> Self {
+        match self {
+            DecodeError::TooShort {
+                opcode: None,
+                expected,
+                actual,
+            } => DecodeError::TooShort {
+                opcode: Some(message_opcode),
+                expected,
+                actual,
+            },
+            DecodeError::InvalidField {
+                opcode: None,
+                field,
+                value,
+            } => DecodeError::InvalidField {
+                opcode: Some(message_opcode),
+                field,
+                value,
+            },
+            error => error,
+        }
+    }
+}
+
+/// check the length of a payload which has no opcode
+pub(crate) fn check_length(payload: &[u8], length: usize) -> Result<(), DecodeError> {
+    if payload.len() < length {
+        return Err(DecodeError::TooShort {
+            opcode: None,
+            expected: length,
+            actual: payload.len(),
+        });
+    }
+    Ok(())
+}
+
+/// check the opcode and the length of a message, then decode its fields
+pub(crate) fn decode_message<T>(
+    payload: &[u8],
+    opcode: u8,
+    length: usize,
+    decode: impl FnOnce() -> Result<T, DecodeError>,
+) -> Result<T, DecodeError> {
+    if let Some(&actual) = payload.first() {
+        if actual != opcode {
+            return Err(DecodeError::UnexpectedOpcode {
+                expected: opcode,
+                actual,
+            });
+        }
+    }
+    check_length(payload, length)
+        .and_then(|_| decode())
+        .map_err(|error| error.within(opcode))
+}
+
+/// decode a one byte field
+pub(crate) fn decode_field<T: TryFrom<u8>>(
+    field: &'static str,
+    value: u8,
+) -> Result<T, DecodeError> {
+    T::try_from(value).map_err(|_| DecodeError::InvalidField {
+        opcode: None,
+        field,
+        value,
+    })
+}
+
+/// encode and decode a message and check that the same message is returned
+#[cfg(test)]
+pub(crate) fn assert_round_trip<T>(message: T)
+where
+    T: ToPayload<Vec<u8>> + for<'a> FromPayload<&'a [u8]> + Clone + PartialEq + std::fmt::Debug,
+{
+    let payload = message.clone().to_payload();
+    assert_eq!(T::from_payload(&payload), Some(message), "{:02x?}", payload);
+}
+
+/// random number generator of the tests
+///
+/// The seed is fixed, so a failure can be reproduced.
+#[cfg(test)]
+pub(crate) fn test_rng() -> rand::rngs::StdRng {
+    use rand::SeedableRng;
+    rand::rngs::StdRng::seed_from_u64(0x746f_696f)
+}
+
+/// round-trip the random messages made by the generator
+///
+/// The generators are kept in the test module next to each message type.
+#[cfg(test)]
+pub(crate) fn assert_random_round_trip<T>(mut generate: impl FnMut(&mut rand::rngs::StdRng) -> T)
+where
+    T: ToPayload<Vec<u8>> + for<'a> FromPayload<&'a [u8]> + Clone + PartialEq + std::fmt::Debug,
+{
+    let mut rng = test_rng();
+    for _ in 0..1000 {
+        assert_round_trip(generate(&mut rng));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::characteristic::battery::BatteryInformation;
+    use crate::characteristic::button::ButtonInformation;
+    use crate::characteristic::configuration::*;
+    use crate::characteristic::id::*;
+    use crate::characteristic::indicator::*;
+    use crate::characteristic::motor::*;
+    use crate::characteristic::sensor::*;
+    use crate::characteristic::sound::*;
+    use crate::position::{CubeLocation, Point};
+    use rand::Rng;
+
+    fn _setup() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    /// decoding must not panic, and a decoded message must be encoded to a payload
+    /// which is decoded to the same payload again
+    fn check_decode<T>(payload: &[u8])
+    where
+        T: ToPayload<Vec<u8>> + for<'a> TryFromPayload<&'a [u8]>,
+    {
+        if let Ok(message) = T::try_from_payload(payload) {
+            let encoded = message.to_payload();
+            let decoded = T::try_from_payload(&encoded)
+                .unwrap_or_else(|error| panic!("{:02x?} -> {:02x?}: {}", payload, encoded, error));
+            assert_eq!(decoded.to_payload(), encoded, "{:02x?}", payload);
+        }
+    }
+
+    macro_rules! check_decode_all {
+        ($payload:expr, $($t:ty),* $(,)?) => {
+            $(check_decode::<$t>($payload);)*
+        };
+    }
+
+    fn random_payload(rng: &mut impl Rng) -> Vec<u8> {
+        // small values hit valid enum values and counts more often than uniform bytes
+        let length = rng.gen_range(0..40);
+        (0..length)
+            .map(|_| match rng.gen_range(0..4) {
+                0 => rng.gen_range(0..4),
+                1 => 0x80 | rng.gen_range(0..0x20),
+                _ => rng.gen(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn decode_error1() {
+        _setup();
+
+        // standard id is 7 bytes
+        let payload = [0x02, 0x00, 0x00, 0x00, 0x00, 0x00];
+        assert_eq!(
+            IdInformation::try_from_payload(&payload),
+            Err(DecodeError::TooShort {
+                opcode: Some(0x02),
+                expected: 7,
+                actual: 6,
+            })
+        );
+        // quaternions are 10 bytes
+        let payload = [0x03, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+        assert_eq!(
+            SensorInformation::try_from_payload(&payload),
+            Err(DecodeError::TooShort {
+                opcode: Some(0x03),
+                expected: 10,
+                actual: 9,
+            })
+        );
+        assert_eq!(
+            ResponseIdSensorNotificationData::try_from_payload(&[0x98]),
+            Err(DecodeError::TooShort {
+                opcode: Some(0x98),
+                expected: 3,
+                actual: 1,
+            })
+        );
+        // a part of the second target
+        let mut payload = vec![0x04];
+        payload.extend([0x00; 14]);
+        assert_eq!(
+            MotorControlMultipleTargets::try_from_payload(&payload),
+            Err(DecodeError::TooShort {
+                opcode: Some(0x04),
+                expected: 20,
+                actual: 15,
+            })
+        );
+        assert_eq!(
+            BatteryInformation::try_from_payload(&[]),
+            Err(DecodeError::TooShort {
+                opcode: None,
+                expected: 1,
+                actual: 0,
+            })
+        );
+    }
+
+    #[test]
+    fn decode_error2() {
+        _setup();
+
+        let error = MotorControl::try_from_payload(&[0x01, 0x03, 0x01, 0x10, 0x02, 0x01, 0x10])
+            .unwrap_err();
+        assert_eq!(
+            error,
+            DecodeError::InvalidField {
+                opcode: Some(0x01),
+                field: "motor id",
+                value: 0x03,
+            }
+        );
+        assert_eq!(error.opcode(), Some(0x01));
+        assert_eq!(
+            error.to_string(),
+            "payload of opcode 0x01 has invalid motor id: 0x03"
+        );
+
+        assert_eq!(
+            MotorControl::try_from_payload(&[0x02, 0x01, 0x01, 0x10, 0x02, 0x01, 0x10]),
+            Err(DecodeError::UnexpectedOpcode {
+                expected: 0x01,
+                actual: 0x02,
+            })
+        );
+        assert_eq!(
+            IdInformation::try_from_payload(&[0x05]),
+            Err(DecodeError::UnknownOpcode(0x05))
+        );
+        assert_eq!(
+            SensorInformation::try_from_payload(&[0x03, 0x04]),
+            Err(DecodeError::InvalidField {
+                opcode: Some(0x03),
+                field: "posture data type",
+                value: 0x04,
+            })
+        );
+    }
+
+    #[test]
+    fn decode_random_payload1() {
+        _setup();
+
+        let mut rng = test_rng();
+        for _ in 0..20000 {
+            let payload = random_payload(&mut rng);
+            check_decode_all!(
+                &payload,
+                Point,
+                CubeLocation,
+                IdInformation,
+                PositionIdData,
+                StandardIdData,
+                SensorInformation,
+                MotionDetectionInformation,
+                MagneticSensorInformation,
+                PostureAngleEulerInformation,
+                PostureAngleQuaternionsInformation,
+                RequestMotionDetection,
+                RequestMagneticSensor,
+                RequestPostureAngleDetection,
+                ButtonInformation,
+                BatteryInformation,
+                MotorControl,
+                MotorControlWithSpecifiedDuration,
+                MotorControlTarget,
+                MotorControlMultipleTargets,
+                MotorControlAcceleration,
+                MotorInformation,
+                MotorSpeedInformation,
+                ResponseMotorControlTarget,
+                ResponseMotorControlMultipleTargets,
+                MotorDriveParameter,
+                Speed,
+                TargetPosition,
+                Acceleration,
+                AngleVelocity,
+                TurningOnAndOff,
+                RepeatedTuringOnAndOff,
+                TurnOffAll,
+                TurnOff,
+                IndicatorParam,
+                PlaySoundEffect,
+                PlayMidiNote,
+                StopSound,
+                MidiNote,
+                ConfigurationInformation,
+                RequestBleProtocolVersion,
+                SetHorizontalDetectionThreshold,
+                SetCollisionDetectionThreshold,
+                SetDoubleTapDetectionTimeInterval,
+                SetIdSensorNotification,
+                SetIdSensorMissedNotification,
+                SetMagneticSensor,
+                SetPostureAngleDetection,
+                EnableMotorSpeed,
+                ResponseBleProtocolVersionData,
+                ResponseIdSensorNotificationData,
+                ResponseIdSensorMissedNotificationData,
+                ResponseMagneticSensorData,
+                ResponsePostureAngleDetectionData,
+                ResponseEnableMotorSpeedData,
+            );
+        }
+    }
+}
@@ -3,7 +3,8 @@
 use std::convert::{From, TryFrom};
 use std::ops::{Add, Sub};
 
-use crate::payload::ToPayload;
+use crate::integer_converter::i_to_u16;
+use crate::payload::{check_length, DecodeError, ToPayload, TryFromPayload};
 
 /// Point
 
@@ -71,19 +72,30 @@ impl Point {
     }
 
     pub fn inside(self, rect: MatRect) -> bool {
-        rect.top_left <= self && self <= rect.bottom_right
+        (rect.top_left.x <= self.x && self.x <= rect.bottom_right.x)
+            && (rect.top_left.y <= self.y && self.y <= rect.bottom_right.y)
     }
 }
 
 impl ToPayload<Vec<u8>> for Point {
     fn to_payload(self) -> Vec<u8> {
         let mut payload: Vec<u8> = Vec::new();
-        payload.extend(self.x.to_le_bytes().to_vec());
-        payload.extend(self.y.to_le_bytes().to_vec());
+        payload.extend(i_to_u16(self.x).to_le_bytes().to_vec());
+        payload.extend(i_to_u16(self.y).to_le_bytes().to_vec());
         payload
     }
 }
 
+impl TryFromPayload<&[u8]> for Point {
+    fn try_from_payload(payload: &[u8]) -> Result<Self, DecodeError> {
+        check_length(payload, 4)?;
+        Ok(Self {
+            x: u16::from_le_bytes([payload[0], payload[1]]) as isize,
+            y: u16::from_le_bytes([payload[2], payload[3]]) as isize,
+        })
+    }
+}
+
 /// Location information of a cube
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -145,6 +157,16 @@ impl ToPayload<Vec<u8>> for CubeLocation {
     }
 }
 
+impl TryFromPayload<&[u8]> for CubeLocation {
+    fn try_from_payload(payload: &[u8]) -> Result<Self, DecodeError> {
+        check_length(payload, 6)?;
+        Ok(Self {
+            point: Point::try_from_payload(payload)?,
+            angle: u16::from_le_bytes([payload[4], payload[5]]),
+        })
+    }
+}
+
 /// Mat rectangle
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -154,6 +176,28 @@ pub struct MatRect {
 }
 
 impl MatRect {
+    pub fn new(top_left: Point, bottom_right: Point) -> Self {
+        Self {
+            top_left,
+            bottom_right,
+        }
+    }
+
+    pub fn top_left(&self) -> Point {
+        self.top_left
+    }
+
+    pub fn bottom_right(&self) -> Point {
+        self.bottom_right
+    }
+
+    pub fn center(&self) -> Point {
+        Point {
+            x: self.top_left.x + (self.bottom_right.x - self.top_left.x) / 2,
+            y: self.top_left.y + (self.bottom_right.y - self.top_left.y) / 2,
+        }
+    }
+
     pub fn includes(self, point: Point) -> bool {
         point.inside(self)
     }
@@ -223,7 +267,6 @@ impl ToioMat {
 }
 
 /// Cube location on a toio mat
-
 pub struct RelativeCubeLocation {
     pub location: CubeLocation,
     mat: ToioMat,
@@ -305,9 +348,25 @@ impl RelativeCubeLocation {
     }
 }
 
+#[cfg(test)]
+impl CubeLocation {
+    /// random location for the round-trip tests (see [`crate::payload::test_rng`])
+    pub(crate) fn random(rng: &mut rand::rngs::StdRng) -> Self {
+        use rand::Rng;
+        Self {
+            point: Point {
+                x: rng.gen::<u16>() as isize,
+                y: rng.gen::<u16>() as isize,
+            },
+            angle: rng.gen(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::payload::FromPayload;
 
     #[test]
     fn position_point1() {
@@ -375,6 +434,16 @@ mod test {
         assert!(!p1.inside(mat));
     }
 
+    #[test]
+    fn position_inside6() {
+        let p1: Point = Point { x: 12, y: 20 };
+        let mat: MatRect = MatRect {
+            top_left: Point { x: 10, y: 10 },
+            bottom_right: Point { x: 15, y: 15 },
+        };
+        assert!(!p1.inside(mat));
+    }
+
     #[test]
     fn position_mat1() {
         let toio_mat = ToioMat::UserDefinedMat {
@@ -398,6 +467,10 @@ mod test {
         assert!(ToioMat::GesundroidMat.rect().includes(p1));
         let p1: Point = Point { x: 1460, y: 455 };
         assert!(ToioMat::GesundroidMat.rect().includes(p1));
+        assert_eq!(
+            ToioMat::ToioCollectionMatRing.rect().center(),
+            Point { x: 250, y: 250 }
+        );
     }
 
     #[test]
@@ -412,4 +485,26 @@ mod test {
         let absolute_point = ToioMat::GesundroidMat.rect().top_left;
         assert_eq!(absolute_point, relative_location.absolute_point());
     }
+
+    #[test]
+    fn position_payload1() {
+        let location = CubeLocation {
+            point: Point {
+                x: 0x0123,
+                y: 0x0456,
+            },
+            angle: 0x0789,
+        };
+        let payload = location.to_payload();
+        assert_eq!(payload, vec![0x23, 0x01, 0x56, 0x04, 0x89, 0x07]);
+        assert_eq!(CubeLocation::from_payload(&payload), Some(location));
+        assert_eq!(CubeLocation::from_payload(&payload[..5]), None);
+    }
+
+    #[test]
+    fn position_payload2() {
+        // out of range coordinates are clipped to u16
+        let point = Point { x: -1, y: 0x10000 };
+        assert_eq!(point.to_payload(), vec![0x00, 0x00, 0xff, 0xff]);
+    }
 }
@@ -1,13 +1,11 @@
-use std::error::Error;
+use crate::CoreCubeError;
 use std::time::Duration;
 use tokio::time;
 
 use btleplug::api::{Central, Manager as _, Peripheral as _, ScanFilter};
 use btleplug::platform::Manager;
 
-pub async fn scan_example(
-    filter: ScanFilter,
-) -> Result<(), Box<dyn Error + Send + Sync + 'static>> {
+pub async fn scan_example(filter: ScanFilter) -> Result<(), CoreCubeError> {
     let manager = Manager::new().await?;
     let adapter_list = manager.adapters().await?;
 
@@ -17,10 +15,7 @@ pub async fn scan_example(
 
     for adapter in adapter_list.iter() {
         println!("Starting scan on {}...", adapter.adapter_info().await?);
-        adapter
-            .start_scan(filter.clone())
-            .await
-            .expect("Can't scan BLE adapter for connected devices...");
+        adapter.start_scan(filter.clone()).await?;
         time::sleep(Duration::from_secs(2)).await;
         let peripherals = adapter.peripherals().await?;
         if peripherals.is_empty() {
@@ -30,8 +25,7 @@ pub async fn scan_example(
                 let properties = peripheral.properties().await?;
                 let is_connected = peripheral.is_connected().await?;
                 let local_name = properties
-                    .unwrap()
-                    .local_name
+                    .and_then(|properties| properties.local_name)
                     .unwrap_or_else(|| String::from("(peripheral name unknown)"));
                 println!(
                     "Peripheral {:?} is connected {:?}",
@@ -62,10 +56,9 @@ pub async fn scan_example(
                 }
                 if is_connected {
                     println!("Disconnecting from peripheral {:?}...", &local_name);
-                    peripheral
-                        .disconnect()
-                        .await
-                        .expect("Error disconnecting from BLE peripheral");
+                    if let Err(err) = peripheral.disconnect().await {
+                        eprintln!("Error disconnecting from BLE peripheral: {}", err);
+                    }
                 }
             }
         }
@@ -0,0 +1,11 @@
+//! Simulation models of toio core cube
+
+pub mod acceleration;
+pub mod id_sensor;
+pub mod kinematics;
+pub mod target;
+
+pub use self::acceleration::AccelerationControl;
+pub use self::id_sensor::IdSensor;
+pub use self::kinematics::Pose;
+pub use self::target::{TargetController, TargetOutput};
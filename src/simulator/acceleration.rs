@@ -0,0 +1,145 @@
+//! Motor control with acceleration specified
+//!
+//! ref:<https://toio.github.io/toio-spec/en/docs/ble_motor/#motor-control-with-acceleration-specified>
+
+use crate::characteristic::motor::{MovingDirection, Priority, RotationDirection};
+use crate::simulator::kinematics::{DOTS_PER_SECOND_PER_SPEED, MOTOR_SPEED_MAX, TIRE_WIDTH};
+use std::time::Duration;
+
+/// Period of the acceleration (speed increment per 100 ms)
+const ACCELERATION_PERIOD: Duration = Duration::from_millis(100);
+
+/// Translational speed and rotation velocity controller
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct AccelerationControl {
+    /// target translational speed (speed indication value, negative value is backward)
+    pub target_speed: f64,
+    /// speed increment per 100 ms (0: reach the target speed immediately)
+    pub acceleration: f64,
+    /// degrees per second (positive value is clockwise)
+    pub rotation_velocity: f64,
+    pub priority: Priority,
+    /// current translational speed
+    pub speed: f64,
+}
+
+impl AccelerationControl {
+    pub fn new(
+        translational_speed: u8,
+        acceleration: u8,
+        rotation_velocity: u16,
+        rotation_direction: RotationDirection,
+        moving_direction: MovingDirection,
+        priority: Priority,
+        current_speed: f64,
+    ) -> Self {
+        let target_speed = match moving_direction {
+            MovingDirection::Forward => f64::from(translational_speed),
+            MovingDirection::Backward => -f64::from(translational_speed),
+        };
+        let rotation_velocity = match rotation_direction {
+            RotationDirection::Positive => f64::from(rotation_velocity),
+            RotationDirection::Negative => -f64::from(rotation_velocity),
+        };
+        Self {
+            target_speed,
+            acceleration: f64::from(acceleration),
+            rotation_velocity,
+            priority,
+            speed: if acceleration == 0 {
+                target_speed
+            } else {
+                current_speed
+            },
+        }
+    }
+
+    /// accelerate for `dt` and return the speed indication values of (left, right) motors
+    pub fn wheel_speeds(&mut self, dt: Duration) -> (f64, f64) {
+        let increment = self.acceleration * dt.as_secs_f64() / ACCELERATION_PERIOD.as_secs_f64();
+        if self.speed < self.target_speed {
+            self.speed = (self.speed + increment).min(self.target_speed);
+        } else {
+            self.speed = (self.speed - increment).max(self.target_speed);
+        }
+
+        let mut speed = self.speed;
+        // difference between the motor speeds which realizes the rotation velocity
+        let mut rotation =
+            self.rotation_velocity.to_radians() * TIRE_WIDTH / DOTS_PER_SECOND_PER_SPEED / 2.0;
+        if speed.abs() + rotation.abs() > MOTOR_SPEED_MAX {
+            match self.priority {
+                Priority::TranslationalSpeed => {
+                    speed = speed.clamp(-MOTOR_SPEED_MAX, MOTOR_SPEED_MAX);
+                    rotation = rotation.signum() * (MOTOR_SPEED_MAX - speed.abs());
+                }
+                Priority::RotationVelocity => {
+                    rotation = rotation.clamp(-MOTOR_SPEED_MAX, MOTOR_SPEED_MAX);
+                    speed = speed.signum() * (MOTOR_SPEED_MAX - rotation.abs());
+                }
+            }
+        }
+        (speed + rotation, speed - rotation)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn _setup() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    #[test]
+    fn acceleration_control1() {
+        _setup();
+
+        let mut control = AccelerationControl::new(
+            50,
+            10,
+            0,
+            RotationDirection::Positive,
+            MovingDirection::Backward,
+            Priority::TranslationalSpeed,
+            0.0,
+        );
+        assert_eq!(
+            control.wheel_speeds(Duration::from_millis(100)),
+            (-10.0, -10.0)
+        );
+        assert_eq!(
+            control.wheel_speeds(Duration::from_millis(300)),
+            (-40.0, -40.0)
+        );
+        assert_eq!(
+            control.wheel_speeds(Duration::from_millis(300)),
+            (-50.0, -50.0)
+        );
+    }
+
+    #[test]
+    fn acceleration_control2() {
+        _setup();
+
+        let mut control = AccelerationControl::new(
+            100,
+            0,
+            360,
+            RotationDirection::Positive,
+            MovingDirection::Forward,
+            Priority::TranslationalSpeed,
+            0.0,
+        );
+        let (left, right) = control.wheel_speeds(Duration::from_millis(10));
+        assert_eq!(left, MOTOR_SPEED_MAX);
+        assert!(left > right);
+        assert_eq!((left + right) / 2.0, 100.0);
+
+        control.priority = Priority::RotationVelocity;
+        let (left, right) = control.wheel_speeds(Duration::from_millis(10));
+        assert_eq!(left, MOTOR_SPEED_MAX);
+        assert!((left + right) / 2.0 < 100.0);
+    }
+}
@@ -0,0 +1,161 @@
+//! Notification timing of the ID sensor
+//!
+//! ref:<https://toio.github.io/toio-spec/en/docs/ble_configuration#identification-sensor-id-notification-settings>
+
+use crate::characteristic::id::IdInformation;
+use std::time::Duration;
+
+/// Interval of the ID sensor readings
+pub const ID_SENSOR_PERIOD: Duration = Duration::from_millis(10);
+
+/// Notification stops when the reading does not change for this period (condition 0xff)
+const UNCHANGED_TIMEOUT: Duration = Duration::from_millis(300);
+
+/// Decides which ID sensor readings are notified
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct IdSensor {
+    /// minimum notification interval
+    pub interval: Duration,
+    /// notification condition (0x00: always, 0x01: at change, 0xff: stop after 300 ms without change)
+    pub condition: u8,
+    /// delay before the missed notification
+    pub missed_sensitivity: Duration,
+    last_notified: Option<IdInformation>,
+    since_notification: Duration,
+    unchanged: Duration,
+    missed: Duration,
+}
+
+impl Default for IdSensor {
+    fn default() -> Self {
+        Self {
+            interval: Duration::ZERO,
+            condition: 0x00u8,
+            missed_sensitivity: Duration::from_millis(70),
+            last_notified: None,
+            since_notification: Duration::ZERO,
+            unchanged: Duration::ZERO,
+            missed: Duration::ZERO,
+        }
+    }
+}
+
+impl IdSensor {
+    /// record a reading `dt` after the previous one and return it if it should be notified
+    pub fn read(&mut self, reading: IdInformation, dt: Duration) -> Option<IdInformation> {
+        self.since_notification += dt;
+        let missed = matches!(
+            reading,
+            IdInformation::PositionIdMissed | IdInformation::StandardIdMissed
+        );
+        if missed {
+            self.missed += dt;
+            // missed is notified only once
+            if self.last_notified != Some(reading) && self.missed >= self.missed_sensitivity {
+                return Some(self.notified(reading));
+            }
+            return None;
+        }
+        self.missed = Duration::ZERO;
+
+        let changed = self.last_notified != Some(reading);
+        if changed {
+            self.unchanged = Duration::ZERO;
+        } else {
+            self.unchanged += dt;
+        }
+        if self.since_notification < self.interval {
+            return None;
+        }
+        let notify = match self.condition {
+            0x01u8 => changed,
+            0xffu8 => changed || self.unchanged < UNCHANGED_TIMEOUT,
+            _ => true,
+        };
+        if notify {
+            Some(self.notified(reading))
+        } else {
+            None
+        }
+    }
+
+    /// record a reading which is notified regardless of the timing (e.g. the cube is placed)
+    pub fn reset(&mut self, reading: IdInformation) {
+        self.notified(reading);
+        self.unchanged = Duration::ZERO;
+        self.missed = Duration::ZERO;
+    }
+
+    fn notified(&mut self, reading: IdInformation) -> IdInformation {
+        self.last_notified = Some(reading);
+        self.since_notification = Duration::ZERO;
+        reading
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::characteristic::id::PositionIdData;
+    use crate::position::{CubeLocation, Point};
+
+    fn _setup() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    fn position_id(x: isize) -> IdInformation {
+        let location = CubeLocation {
+            point: Point { x, y: 100 },
+            angle: 0,
+        };
+        IdInformation::PositionId(PositionIdData {
+            center: location,
+            sensor: location,
+        })
+    }
+
+    #[test]
+    fn id_sensor_condition1() {
+        _setup();
+
+        let mut sensor = IdSensor {
+            interval: Duration::from_millis(20),
+            ..IdSensor::default()
+        };
+        let notified = (0..10)
+            .filter_map(|_| sensor.read(position_id(100), ID_SENSOR_PERIOD))
+            .count();
+        assert_eq!(notified, 5);
+
+        let mut sensor = IdSensor {
+            condition: 0x01u8,
+            ..IdSensor::default()
+        };
+        let notified = (0..10)
+            .filter_map(|x| sensor.read(position_id(100 + x / 5), ID_SENSOR_PERIOD))
+            .count();
+        assert_eq!(notified, 2);
+
+        let mut sensor = IdSensor {
+            condition: 0xffu8,
+            ..IdSensor::default()
+        };
+        let notified = (0..100)
+            .filter_map(|_| sensor.read(position_id(100), ID_SENSOR_PERIOD))
+            .count();
+        assert_eq!(notified, 30);
+    }
+
+    #[test]
+    fn id_sensor_missed1() {
+        _setup();
+
+        let mut sensor = IdSensor::default();
+        sensor.reset(position_id(100));
+        let notified: Vec<IdInformation> = (0..20)
+            .filter_map(|_| sensor.read(IdInformation::PositionIdMissed, ID_SENSOR_PERIOD))
+            .collect();
+        assert_eq!(notified, vec![IdInformation::PositionIdMissed]);
+    }
+}
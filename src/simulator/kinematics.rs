@@ -0,0 +1,138 @@
+//! Differential-drive model of a cube
+//!
+//! Dimensions follow the cube simulator of toio SDK for Unity.
+//! Angles are in degrees and increase clockwise on the mat (same as Position ID).
+
+use crate::position::{CubeLocation, Point};
+use std::f64::consts::PI;
+use std::time::Duration;
+
+/// Mat units per meter
+pub const DOTS_PER_METER: f64 = 411.0 / 0.560;
+
+/// Distance between the tires (mat units)
+pub const TIRE_WIDTH: f64 = 0.0266 * DOTS_PER_METER;
+
+/// Tire speed (mat units per second) of motor speed indication value 1
+pub const DOTS_PER_SECOND_PER_SPEED: f64 = 4.3 * PI * 0.0125 / 60.0 * DOTS_PER_METER;
+
+/// Motors do not turn below this speed indication value
+pub const MOTOR_SPEED_MIN: f64 = 10.0;
+
+/// Speed indication values above this are handled as this value
+pub const MOTOR_SPEED_MAX: f64 = 115.0;
+
+/// Convert motor speed indication value to tire speed (mat units per second)
+pub fn tire_speed(speed: f64) -> f64 {
+    if speed.abs() < MOTOR_SPEED_MIN {
+        0.0
+    } else {
+        speed.clamp(-MOTOR_SPEED_MAX, MOTOR_SPEED_MAX) * DOTS_PER_SECOND_PER_SPEED
+    }
+}
+
+/// Pose of a cube
+
+#[derive(Default, Debug, Copy, Clone, PartialEq)]
+pub struct Pose {
+    pub x: f64,
+    pub y: f64,
+    pub angle: f64,
+}
+
+impl From<CubeLocation> for Pose {
+    fn from(location: CubeLocation) -> Self {
+        Self {
+            x: location.point.x as f64,
+            y: location.point.y as f64,
+            angle: f64::from(location.angle).rem_euclid(360.0),
+        }
+    }
+}
+
+impl Pose {
+    /// location as reported by Position ID
+    pub fn location(&self) -> CubeLocation {
+        CubeLocation {
+            point: self.point(),
+            angle: (self.angle.round() as u16) % 360,
+        }
+    }
+
+    pub fn point(&self) -> Point {
+        Point {
+            x: self.x.round() as isize,
+            y: self.y.round() as isize,
+        }
+    }
+
+    /// move with the specified motor speed indication values for `dt`
+    pub fn step(&mut self, left: f64, right: f64, dt: Duration) {
+        let left = tire_speed(left);
+        let right = tire_speed(right);
+        let velocity = (left + right) / 2.0;
+        // positive angular velocity turns clockwise on the mat
+        let angular_velocity = (left - right) / TIRE_WIDTH;
+        let dt = dt.as_secs_f64();
+        let theta = self.angle.to_radians();
+        let next_theta = theta + angular_velocity * dt;
+        if angular_velocity.abs() < f64::EPSILON {
+            self.x += velocity * theta.cos() * dt;
+            self.y += velocity * theta.sin() * dt;
+        } else {
+            let radius = velocity / angular_velocity;
+            self.x += radius * (next_theta.sin() - theta.sin());
+            self.y -= radius * (next_theta.cos() - theta.cos());
+        }
+        self.angle = next_theta.to_degrees().rem_euclid(360.0);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn _setup() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    #[test]
+    fn kinematics_straight1() {
+        _setup();
+
+        let mut pose = Pose {
+            x: 100.0,
+            y: 100.0,
+            angle: 90.0,
+        };
+        pose.step(50.0, 50.0, Duration::from_secs(1));
+        assert_eq!(pose.point().x, 100);
+        assert_eq!(
+            pose.point().y,
+            100 + (50.0 * DOTS_PER_SECOND_PER_SPEED).round() as isize
+        );
+        assert_eq!(pose.location().angle, 90);
+    }
+
+    #[test]
+    fn kinematics_rotate1() {
+        _setup();
+
+        let mut pose = Pose::default();
+        let half_turn = PI * TIRE_WIDTH / 2.0 / (30.0 * DOTS_PER_SECOND_PER_SPEED);
+        pose.step(30.0, -30.0, Duration::from_secs_f64(half_turn / 2.0));
+        assert_eq!(pose.point(), Point::origin());
+        assert_eq!(pose.location().angle, 90);
+        pose.step(-30.0, 30.0, Duration::from_secs_f64(half_turn));
+        assert_eq!(pose.location().angle, 270);
+    }
+
+    #[test]
+    fn kinematics_deadzone1() {
+        _setup();
+
+        let mut pose = Pose::default();
+        pose.step(9.0, -9.0, Duration::from_secs(1));
+        assert_eq!(pose, Pose::default());
+    }
+}
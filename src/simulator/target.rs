@@ -0,0 +1,778 @@
+//! Motor control with target specified / with multiple targets specified
+//!
+//! ref:<https://toio.github.io/toio-spec/en/docs/ble_motor/#motor-control-with-target-specified>
+//! ref:<https://toio.github.io/toio-spec/en/docs/ble_motor/#motor-control-with-multiple-targets-specified>
+
+use crate::characteristic::motor::{
+    CommandId, MotorInformation, MovementType, RequestId, ResponseCode,
+    ResponseMotorControlMultipleTargets, ResponseMotorControlTarget, RotationOption,
+    SpeedChangeType, WriteMode,
+};
+use crate::simulator::kinematics::{MOTOR_SPEED_MAX, MOTOR_SPEED_MIN};
+use crate::simulator::Pose;
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// Timeout used when 0 is written
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Maximum number of targets in a write
+pub const MAX_TARGETS: usize = 29;
+
+/// Number of commands the simulator queues in append mode
+pub const MAX_APPENDED_COMMANDS: usize = 8;
+
+/// The cube has arrived when it is closer than this distance (mat units)
+pub const ARRIVAL_DISTANCE: f64 = 5.0;
+
+/// The cube has turned to the target angle when it is closer than this (degrees)
+pub const ARRIVAL_ANGLE: f64 = 5.0;
+
+/// Coordinate value which means "same as at writing"
+const SAME_AS_AT_WRITING: u16 = 0xffff;
+
+/// Linear movement rotates on the spot while the heading error is larger than this (degrees)
+const LINEAR_HEADING_THRESHOLD: f64 = 10.0;
+
+/// Motor speed difference per degree of the heading error
+const STEERING_GAIN: f64 = 1.0;
+
+/// Slowest speed used for the speed change and the rotation on the spot
+const SPEED_MIN: f64 = 15.0;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum TurnDirection {
+    Optimal,
+    Positive,
+    Negative,
+}
+
+/// Resolved target
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Target {
+    x: f64,
+    y: f64,
+    /// (absolute angle, direction) to turn to after arriving at the point
+    rotation: Option<(f64, TurnDirection)>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Phase {
+    Move,
+    Rotate { remaining: f64, last_angle: f64 },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct TargetCommand {
+    command: CommandId,
+    request_id: RequestId,
+    timeout: Duration,
+    movement_type: MovementType,
+    max_speed: f64,
+    speed_change_type: SpeedChangeType,
+    targets: VecDeque<Target>,
+    phase: Phase,
+    elapsed: Duration,
+    /// start point of the current segment
+    origin: (f64, f64),
+    path_length: f64,
+    completed_length: f64,
+}
+
+impl TargetCommand {
+    fn response(&self, response_code: ResponseCode) -> MotorInformation {
+        response(self.command, self.request_id, response_code)
+    }
+
+    fn start(&mut self, pose: Pose) {
+        self.origin = (pose.x, pose.y);
+        self.path_length = 0.0;
+        let mut point = self.origin;
+        for target in &self.targets {
+            self.path_length += (target.x - point.0).hypot(target.y - point.1);
+            point = (target.x, target.y);
+        }
+        self.completed_length = 0.0;
+        self.elapsed = Duration::ZERO;
+        self.phase = Phase::Move;
+    }
+
+    fn speed(&self, distance: f64) -> f64 {
+        let min = SPEED_MIN.min(self.max_speed);
+        let segment_length = match self.targets.front() {
+            Some(target) => (target.x - self.origin.0).hypot(target.y - self.origin.1),
+            None => 0.0,
+        };
+        let progress = if self.path_length > 0.0 {
+            ((self.completed_length + segment_length - distance) / self.path_length).clamp(0.0, 1.0)
+        } else {
+            1.0
+        };
+        match self.speed_change_type {
+            SpeedChangeType::Constant => self.max_speed,
+            SpeedChangeType::Acceleration => min + (self.max_speed - min) * progress,
+            SpeedChangeType::Deceleration => self.max_speed - (self.max_speed - min) * progress,
+            SpeedChangeType::AccelerationAndDeceleration => {
+                if progress < 0.5 {
+                    min + (self.max_speed - min) * progress * 2.0
+                } else {
+                    self.max_speed - (self.max_speed - min) * (progress - 0.5) * 2.0
+                }
+            }
+        }
+    }
+
+    /// wheel speeds for the pose (None: all targets are reached)
+    fn control(&mut self, pose: Pose) -> Option<(f64, f64)> {
+        loop {
+            let target = *self.targets.front()?;
+            match self.phase {
+                Phase::Move => {
+                    let (dx, dy) = (target.x - pose.x, target.y - pose.y);
+                    let distance = dx.hypot(dy);
+                    if distance <= ARRIVAL_DISTANCE {
+                        self.completed_length +=
+                            (target.x - self.origin.0).hypot(target.y - self.origin.1);
+                        self.origin = (target.x, target.y);
+                        match target.rotation {
+                            Some((angle, direction)) => {
+                                self.phase = Phase::Rotate {
+                                    remaining: rotation_amount(pose.angle, angle, direction),
+                                    last_angle: pose.angle,
+                                };
+                            }
+                            None => {
+                                self.targets.pop_front();
+                            }
+                        }
+                        continue;
+                    }
+                    return Some(self.drive(pose, dy.atan2(dx).to_degrees(), distance));
+                }
+                Phase::Rotate {
+                    remaining,
+                    last_angle,
+                } => {
+                    let remaining = remaining - normalize_angle(pose.angle - last_angle);
+                    if remaining.abs() <= ARRIVAL_ANGLE {
+                        self.targets.pop_front();
+                        self.phase = Phase::Move;
+                        continue;
+                    }
+                    self.phase = Phase::Rotate {
+                        remaining,
+                        last_angle: pose.angle,
+                    };
+                    return Some(self.rotate(remaining));
+                }
+            }
+        }
+    }
+
+    fn rotate(&self, remaining: f64) -> (f64, f64) {
+        let speed = remaining
+            .abs()
+            .clamp(SPEED_MIN.min(self.max_speed), self.max_speed);
+        let speed = speed.copysign(remaining);
+        (speed, -speed)
+    }
+
+    fn drive(&self, pose: Pose, heading: f64, distance: f64) -> (f64, f64) {
+        let mut error = normalize_angle(heading - pose.angle);
+        let mut speed = self.speed(distance);
+        match self.movement_type {
+            MovementType::Linear => {
+                if error.abs() > LINEAR_HEADING_THRESHOLD {
+                    return self.rotate(error);
+                }
+            }
+            MovementType::Curve => {
+                // drives backward when the target is behind the cube
+                if error.abs() > 90.0 {
+                    error = normalize_angle(error + 180.0);
+                    speed = -speed;
+                }
+            }
+            MovementType::CurveWithoutReverse => (),
+        }
+        let speed = speed * error.to_radians().cos().max(0.0);
+        let turn = error * STEERING_GAIN;
+        (
+            (speed + turn).clamp(-MOTOR_SPEED_MAX, MOTOR_SPEED_MAX),
+            (speed - turn).clamp(-MOTOR_SPEED_MAX, MOTOR_SPEED_MAX),
+        )
+    }
+}
+
+/// angle in (-180, 180]
+fn normalize_angle(angle: f64) -> f64 {
+    let angle = angle.rem_euclid(360.0);
+    if angle > 180.0 {
+        angle - 360.0
+    } else {
+        angle
+    }
+}
+
+/// signed rotation (positive is clockwise) from `current` to `target`
+fn rotation_amount(current: f64, target: f64, direction: TurnDirection) -> f64 {
+    let difference = (target - current).rem_euclid(360.0);
+    if difference.min(360.0 - difference) <= ARRIVAL_ANGLE {
+        return 0.0;
+    }
+    match direction {
+        TurnDirection::Optimal => normalize_angle(difference),
+        TurnDirection::Positive => difference,
+        TurnDirection::Negative => difference - 360.0,
+    }
+}
+
+fn response(
+    command: CommandId,
+    request_id: RequestId,
+    response_code: ResponseCode,
+) -> MotorInformation {
+    match command {
+        CommandId::MultiTargetPositions => {
+            MotorInformation::MotorControlMultipleTargets(ResponseMotorControlMultipleTargets {
+                request_id,
+                response_code,
+            })
+        }
+        _ => MotorInformation::MotorControlTarget(ResponseMotorControlTarget {
+            request_id,
+            response_code,
+        }),
+    }
+}
+
+/// Parse a target (x, y, angle and rotation option)
+fn parse_target(
+    bytes: &[u8],
+    previous: (f64, f64),
+    angle_at_writing: f64,
+) -> Result<Target, ResponseCode> {
+    let coordinate = |value: u16, previous: f64| {
+        if value == SAME_AS_AT_WRITING {
+            previous
+        } else {
+            f64::from(value)
+        }
+    };
+    let x = u16::from_le_bytes([bytes[0], bytes[1]]);
+    let y = u16::from_le_bytes([bytes[2], bytes[3]]);
+    let angle_and_option = u16::from_le_bytes([bytes[4], bytes[5]]);
+    let angle = f64::from(angle_and_option & 0b0001_1111_1111_1111);
+    let rotation_option = RotationOption::try_from((angle_and_option >> 13) as u8)
+        .map_err(|_| ResponseCode::ErrorInvalidParameter)?;
+    let rotation = match rotation_option {
+        RotationOption::AbsoluteOptimal => Some((angle, TurnDirection::Optimal)),
+        RotationOption::AbsolutePositive => Some((angle, TurnDirection::Positive)),
+        RotationOption::AbsoluteNegative => Some((angle, TurnDirection::Negative)),
+        RotationOption::RelativePositive => {
+            Some((angle_at_writing + angle, TurnDirection::Positive))
+        }
+        RotationOption::RelativeNegative => {
+            Some((angle_at_writing - angle, TurnDirection::Negative))
+        }
+        RotationOption::WithoutRotation => None,
+        RotationOption::SameAsAtWriting => Some((angle_at_writing, TurnDirection::Optimal)),
+    };
+    Ok(Target {
+        x: coordinate(x, previous.0),
+        y: coordinate(y, previous.1),
+        rotation: rotation.map(|(angle, direction)| (angle.rem_euclid(360.0), direction)),
+    })
+}
+
+/// Output of [`TargetController::step`]
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TargetOutput {
+    /// (left, right) motor speed indication values (None: no target control is running)
+    pub wheel_speeds: Option<(f64, f64)>,
+    /// responses to notify
+    pub responses: Vec<MotorInformation>,
+}
+
+/// Executes target controls in the same way as the firmware
+
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct TargetController {
+    current: Option<TargetCommand>,
+    appended: VecDeque<TargetCommand>,
+}
+
+impl TargetController {
+    pub fn is_active(&self) -> bool {
+        self.current.is_some()
+    }
+
+    /// Accept a target command (0x03 or 0x04)
+    ///
+    /// `pose` is None while the cube does not read Position ID.
+    /// Returns the responses to notify immediately.
+    pub fn write(&mut self, bytes: &[u8], pose: Option<Pose>) -> Vec<MotorInformation> {
+        let (command, header_length) = match bytes.first() {
+            Some(0x03u8) => (CommandId::TargetPosition, 7),
+            Some(0x04u8) => (CommandId::MultiTargetPositions, 8),
+            _ => return vec![],
+        };
+        let Some(request_id) = bytes.get(1).map(|id| RequestId::received(*id)) else {
+            return vec![];
+        };
+        let invalid_parameter = vec![response(
+            command,
+            request_id,
+            ResponseCode::ErrorInvalidParameter,
+        )];
+
+        let target_bytes = bytes.get(header_length..).unwrap_or_default();
+        let target_count = target_bytes.len() / 6;
+        if target_count == 0 || target_count > MAX_TARGETS || target_bytes.len() % 6 != 0 {
+            return invalid_parameter;
+        }
+        if command == CommandId::TargetPosition && target_count != 1 {
+            return invalid_parameter;
+        }
+        let (Ok(movement_type), Ok(speed_change_type)) = (
+            MovementType::try_from(bytes[3]),
+            SpeedChangeType::try_from(bytes[5]),
+        ) else {
+            return invalid_parameter;
+        };
+        if f64::from(bytes[4]) < MOTOR_SPEED_MIN {
+            return invalid_parameter;
+        }
+        let write_mode = match command {
+            CommandId::MultiTargetPositions => match WriteMode::try_from(bytes[7]) {
+                Ok(write_mode) => write_mode,
+                Err(_) => return invalid_parameter,
+            },
+            _ => WriteMode::Overwrite,
+        };
+
+        let Some(pose) = pose else {
+            return vec![response(command, request_id, ResponseCode::ErrorIdMissed)];
+        };
+        if write_mode == WriteMode::Append {
+            let appendable = match &self.current {
+                Some(current) => current.command == CommandId::MultiTargetPositions,
+                None => true,
+            };
+            if !appendable || self.appended.len() >= MAX_APPENDED_COMMANDS {
+                return vec![response(
+                    command,
+                    request_id,
+                    ResponseCode::ErrorFailToAppend,
+                )];
+            }
+        }
+
+        // "same as at writing" of appended command refers to the last target of the queue
+        let mut previous = (pose.x, pose.y);
+        if write_mode == WriteMode::Append {
+            if let Some(target) = self
+                .appended
+                .back()
+                .or(self.current.as_ref())
+                .and_then(|command| command.targets.back())
+            {
+                previous = (target.x, target.y);
+            }
+        }
+        let mut targets: VecDeque<Target> = VecDeque::new();
+        for chunk in target_bytes.chunks(6) {
+            match parse_target(chunk, previous, pose.angle) {
+                Ok(target) => {
+                    previous = (target.x, target.y);
+                    targets.push_back(target);
+                }
+                Err(_) => return invalid_parameter,
+            }
+        }
+
+        let timeout = match bytes[2] {
+            0u8 => DEFAULT_TIMEOUT,
+            seconds => Duration::from_secs(seconds.into()),
+        };
+        let mut new_command = TargetCommand {
+            command,
+            request_id,
+            timeout,
+            movement_type,
+            max_speed: f64::from(bytes[4]).min(MOTOR_SPEED_MAX),
+            speed_change_type,
+            targets,
+            phase: Phase::Move,
+            elapsed: Duration::ZERO,
+            origin: (pose.x, pose.y),
+            path_length: 0.0,
+            completed_length: 0.0,
+        };
+        match write_mode {
+            WriteMode::Append if self.current.is_some() => {
+                self.appended.push_back(new_command);
+                vec![]
+            }
+            _ => {
+                let responses = self.cancel();
+                new_command.start(pose);
+                self.current = Some(new_command);
+                responses
+            }
+        }
+    }
+
+    /// Stop all target controls because another motor control is written
+    pub fn cancel(&mut self) -> Vec<MotorInformation> {
+        self.current
+            .take()
+            .into_iter()
+            .chain(self.appended.drain(..))
+            .map(|command| command.response(ResponseCode::SuccessWithOverwrite))
+            .collect()
+    }
+
+    /// Progress the control by `dt` (`pose` is None while Position ID is missed)
+    pub fn step(&mut self, pose: Option<Pose>, dt: Duration) -> TargetOutput {
+        let mut output = TargetOutput {
+            wheel_speeds: None,
+            responses: vec![],
+        };
+        if self.current.is_none() {
+            return output;
+        }
+        // stops in any case when the control finishes
+        output.wheel_speeds = Some((0.0, 0.0));
+        let Some(pose) = pose else {
+            output.responses = self
+                .current
+                .take()
+                .into_iter()
+                .chain(self.appended.drain(..))
+                .map(|command| command.response(ResponseCode::ErrorIdMissed))
+                .collect();
+            return output;
+        };
+        let Some(current) = self.current.as_mut() else {
+            return output;
+        };
+        current.elapsed += dt;
+        let response_code = match current.control(pose) {
+            Some(wheel_speeds) if current.elapsed < current.timeout => {
+                output.wheel_speeds = Some(wheel_speeds);
+                return output;
+            }
+            Some(_) => ResponseCode::ErrorTimeout,
+            None => ResponseCode::Success,
+        };
+        output.responses.push(current.response(response_code));
+        self.current = self.appended.pop_front();
+        if let Some(next) = &mut self.current {
+            next.start(pose);
+            if let Some(wheel_speeds) = next.control(pose) {
+                output.wheel_speeds = Some(wheel_speeds);
+            }
+        }
+        output
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::characteristic::motor::{
+        MotorControlMultipleTargets, MotorControlTarget, Speed, TargetPosition, Timeout,
+    };
+    use crate::payload::ToPayload;
+    use crate::position::{CubeLocation, Point};
+
+    fn _setup() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    const STEP: Duration = Duration::from_millis(10);
+
+    fn target(x: isize, y: isize, angle: u16, rotation_option: RotationOption) -> TargetPosition {
+        TargetPosition {
+            cube_location: CubeLocation {
+                point: Point { x, y },
+                angle,
+            },
+            rotation_option,
+        }
+    }
+
+    /// run until the control finishes and return the responses
+    fn run(controller: &mut TargetController, pose: &mut Pose) -> Vec<MotorInformation> {
+        for _ in 0..6000 {
+            let output = controller.step(Some(*pose), STEP);
+            if let Some((left, right)) = output.wheel_speeds {
+                pose.step(left.round(), right.round(), STEP);
+            }
+            if !output.responses.is_empty() {
+                return output.responses;
+            }
+        }
+        unreachable!();
+    }
+
+    fn response_code(response: &MotorInformation) -> ResponseCode {
+        match response {
+            MotorInformation::MotorControlTarget(response) => response.response_code,
+            MotorInformation::MotorControlMultipleTargets(response) => response.response_code,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn target_control1() {
+        _setup();
+
+        let movement_types = [
+            MovementType::Curve,
+            MovementType::CurveWithoutReverse,
+            MovementType::Linear,
+        ];
+        for movement_type in movement_types {
+            let mut controller = TargetController::default();
+            let mut pose = Pose {
+                x: 100.0,
+                y: 100.0,
+                angle: 180.0,
+            };
+            let command = MotorControlTarget {
+                movement_type,
+                speed: Speed {
+                    max: 80,
+                    speed_change_type: SpeedChangeType::AccelerationAndDeceleration,
+                },
+                target: target(300, 200, 90, RotationOption::AbsoluteOptimal),
+                ..MotorControlTarget::new(RequestId::received(3))
+            };
+            assert!(controller
+                .write(&command.to_payload(), Some(pose))
+                .is_empty());
+            let responses = run(&mut controller, &mut pose);
+            assert_eq!(
+                responses,
+                vec![MotorInformation::MotorControlTarget(
+                    ResponseMotorControlTarget {
+                        request_id: RequestId::received(3),
+                        response_code: ResponseCode::Success,
+                    }
+                )]
+            );
+            assert!(pose.point().distance(Point { x: 300, y: 200 }) <= ARRIVAL_DISTANCE as isize);
+            assert!(normalize_angle(pose.angle - 90.0).abs() <= ARRIVAL_ANGLE);
+            assert!(!controller.is_active());
+        }
+    }
+
+    #[test]
+    fn target_control2() {
+        _setup();
+
+        // relative rotation and "same as at writing" coordinates
+        let mut controller = TargetController::default();
+        let mut pose = Pose {
+            x: 200.0,
+            y: 200.0,
+            angle: 10.0,
+        };
+        let command = MotorControlTarget {
+            speed: Speed {
+                max: 50,
+                ..Speed::default()
+            },
+            target: target(0, 0, 90, RotationOption::RelativeNegative),
+            ..MotorControlTarget::new(RequestId::received(0))
+        };
+        let mut payload = command.to_payload();
+        payload[7..11].copy_from_slice(&[0xff, 0xff, 0xff, 0xff]);
+        controller.write(&payload, Some(pose));
+        let responses = run(&mut controller, &mut pose);
+        assert_eq!(response_code(&responses[0]), ResponseCode::Success);
+        assert_eq!(pose.point(), Point { x: 200, y: 200 });
+        assert!(normalize_angle(pose.angle - 280.0).abs() <= ARRIVAL_ANGLE);
+    }
+
+    #[test]
+    fn target_control_error1() {
+        _setup();
+
+        let pose = Pose {
+            x: 100.0,
+            y: 100.0,
+            angle: 0.0,
+        };
+        let mut controller = TargetController::default();
+
+        // speed is too slow
+        let command = MotorControlTarget {
+            speed: Speed {
+                max: 5,
+                ..Speed::default()
+            },
+            ..MotorControlTarget::new(RequestId::received(0))
+        };
+        let responses = controller.write(&command.to_payload(), Some(pose));
+        assert_eq!(
+            response_code(&responses[0]),
+            ResponseCode::ErrorInvalidParameter
+        );
+
+        // position ID is not read at writing
+        let command = MotorControlTarget {
+            speed: Speed {
+                max: 50,
+                ..Speed::default()
+            },
+            ..MotorControlTarget::new(RequestId::received(0))
+        };
+        let responses = controller.write(&command.to_payload(), None);
+        assert_eq!(response_code(&responses[0]), ResponseCode::ErrorIdMissed);
+        assert!(!controller.is_active());
+
+        // position ID is missed while moving
+        let responses = controller.write(&command.to_payload(), Some(pose));
+        assert!(responses.is_empty());
+        controller.step(Some(pose), STEP);
+        let output = controller.step(None, STEP);
+        assert_eq!(output.wheel_speeds, Some((0.0, 0.0)));
+        assert_eq!(
+            response_code(&output.responses[0]),
+            ResponseCode::ErrorIdMissed
+        );
+
+        // cube does not move (e.g. held by hand) until the timeout
+        let command = MotorControlTarget {
+            timeout: Timeout::Second(1),
+            ..command
+        };
+        controller.write(&command.to_payload(), Some(pose));
+        let responses: Vec<MotorInformation> = (0..100)
+            .flat_map(|_| controller.step(Some(pose), STEP).responses)
+            .collect();
+        assert_eq!(response_code(&responses[0]), ResponseCode::ErrorTimeout);
+    }
+
+    #[test]
+    fn multiple_targets_control1() {
+        _setup();
+
+        let mut controller = TargetController::default();
+        let mut pose = Pose {
+            x: 100.0,
+            y: 100.0,
+            angle: 0.0,
+        };
+        let command = MotorControlMultipleTargets {
+            speed: Speed {
+                max: 60,
+                ..Speed::default()
+            },
+            target_list: vec![
+                target(200, 100, 0, RotationOption::WithoutRotation),
+                target(200, 200, 0, RotationOption::WithoutRotation),
+            ],
+            ..MotorControlMultipleTargets::new(RequestId::received(1))
+        };
+        controller.write(&command.clone().to_payload(), Some(pose));
+
+        // appended command starts after the first one
+        let appended = MotorControlMultipleTargets {
+            id: RequestId::received(2),
+            write_mode: WriteMode::Append,
+            target_list: vec![target(100, 200, 180, RotationOption::AbsoluteOptimal)],
+            ..command.clone()
+        };
+        assert!(controller
+            .write(&appended.to_payload(), Some(pose))
+            .is_empty());
+
+        let responses = run(&mut controller, &mut pose);
+        assert_eq!(
+            responses,
+            vec![MotorInformation::MotorControlMultipleTargets(
+                ResponseMotorControlMultipleTargets {
+                    request_id: RequestId::received(1),
+                    response_code: ResponseCode::Success,
+                }
+            )]
+        );
+        assert!(pose.point().distance(Point { x: 200, y: 200 }) <= ARRIVAL_DISTANCE as isize);
+        let responses = run(&mut controller, &mut pose);
+        assert_eq!(response_code(&responses[0]), ResponseCode::Success);
+        assert!(pose.point().distance(Point { x: 100, y: 200 }) <= ARRIVAL_DISTANCE as isize);
+        assert!(normalize_angle(pose.angle - 180.0).abs() <= ARRIVAL_ANGLE);
+    }
+
+    #[test]
+    fn multiple_targets_control2() {
+        _setup();
+
+        let pose = Pose {
+            x: 100.0,
+            y: 100.0,
+            angle: 0.0,
+        };
+        let mut controller = TargetController::default();
+        let single = MotorControlTarget {
+            speed: Speed {
+                max: 50,
+                ..Speed::default()
+            },
+            target: target(300, 300, 0, RotationOption::WithoutRotation),
+            ..MotorControlTarget::new(RequestId::received(1))
+        };
+        controller.write(&single.to_payload(), Some(pose));
+
+        // multiple targets cannot be appended to a single target control
+        let multiple = MotorControlMultipleTargets {
+            speed: Speed {
+                max: 50,
+                ..Speed::default()
+            },
+            write_mode: WriteMode::Append,
+            target_list: vec![target(200, 200, 0, RotationOption::WithoutRotation)],
+            ..MotorControlMultipleTargets::new(RequestId::received(2))
+        };
+        let responses = controller.write(&multiple.clone().to_payload(), Some(pose));
+        assert_eq!(
+            response_code(&responses[0]),
+            ResponseCode::ErrorFailToAppend
+        );
+
+        // overwrite
+        let overwrite = MotorControlMultipleTargets {
+            write_mode: WriteMode::Overwrite,
+            ..multiple.clone()
+        };
+        let responses = controller.write(&overwrite.to_payload(), Some(pose));
+        assert_eq!(
+            responses,
+            vec![MotorInformation::MotorControlTarget(
+                ResponseMotorControlTarget {
+                    request_id: RequestId::received(1),
+                    response_code: ResponseCode::SuccessWithOverwrite,
+                }
+            )]
+        );
+
+        // queue is full
+        for _ in 0..MAX_APPENDED_COMMANDS {
+            assert!(controller
+                .write(&multiple.clone().to_payload(), Some(pose))
+                .is_empty());
+        }
+        let responses = controller.write(&multiple.clone().to_payload(), Some(pose));
+        assert_eq!(
+            response_code(&responses[0]),
+            ResponseCode::ErrorFailToAppend
+        );
+        assert_eq!(controller.cancel().len(), 1 + MAX_APPENDED_COMMANDS);
+    }
+}